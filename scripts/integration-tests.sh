@@ -10,12 +10,9 @@ if [ -z "$MINIKUBE" ]; then
   exit 1
 fi
 
-# start minikube
+# start minikube; the tests connect to the cluster of $KUBECONFIG (or ~/.kube/config), so any cluster works
 minikube start
 
-export MINIKUBE_IP="$(minikube ip)"
-echo "minikube IP: $MINIKUBE_IP"
-
 $KUBECTL create namespace default
 
 cargo test --features integration-tests $@
@@ -0,0 +1,176 @@
+//! ## Error
+//!
+//! `error` exposes the failures which can be told apart programmatically
+
+use std::fmt;
+
+use remotefs::fs::{RemoteError, RemoteErrorType};
+use thiserror::Error;
+
+/// A failure of the kube clients which can be told apart from the others.
+///
+/// It's carried in the message of the [`RemoteError`] returned by the clients;
+/// get it back with [`KubeFsError::from_remote`].
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KubeFsError {
+    /// The shell (or the sudo binary) used to run commands doesn't exist in the container
+    #[error("shell not found in the container")]
+    ShellMissing,
+    /// The connection to the cluster wasn't established within the connect timeout
+    #[error("connect timed out")]
+    ConnectTimeout,
+    /// The operation was cancelled through the cancellation token
+    #[error("cancelled")]
+    Cancelled,
+    /// The path exists but it's not a directory
+    #[error("not a directory")]
+    NotADirectory,
+    /// The container filesystem is full
+    #[error("no space left on device")]
+    NoSpace,
+    /// The container user isn't allowed to access the path
+    #[error("permission denied")]
+    PermissionDenied,
+    /// The transfer requires tar, which isn't in the container
+    #[error("tar is not available in the container")]
+    TarUnavailable,
+    /// The API server refused the request (403), e.g. because RBAC doesn't grant `pods/exec`
+    #[error("forbidden by the api server")]
+    Forbidden,
+    /// The destination of a non-overwriting operation, such as `rename`, already exists
+    #[error("destination already exists")]
+    AlreadyExists,
+    /// The condition waited for, e.g. by `wait_for_file`, wasn't met within the timeout
+    #[error("wait timed out")]
+    WaitTimeout,
+}
+
+impl KubeFsError {
+    const ALL: [Self; 10] = [
+        Self::ShellMissing,
+        Self::ConnectTimeout,
+        Self::Cancelled,
+        Self::NotADirectory,
+        Self::NoSpace,
+        Self::PermissionDenied,
+        Self::TarUnavailable,
+        Self::Forbidden,
+        Self::AlreadyExists,
+        Self::WaitTimeout,
+    ];
+
+    /// Get the error carried by `err`, if any
+    pub fn from_remote(err: &RemoteError) -> Option<Self> {
+        let msg = err.msg.as_deref()?;
+        Self::ALL.into_iter().find(|kube_err| {
+            msg.strip_prefix(kube_err.to_string().as_str())
+                .is_some_and(|detail| detail.is_empty() || detail.starts_with(": "))
+        })
+    }
+
+    /// Make a [`RemoteError`] of `kind` carrying this error
+    pub(crate) fn into_remote(self, kind: RemoteErrorType) -> RemoteError {
+        RemoteError::new_ex(kind, self)
+    }
+
+    /// Make a [`RemoteError`] of `kind` carrying this error, with `detail` appended to the message
+    pub(crate) fn into_remote_with(
+        self,
+        kind: RemoteErrorType,
+        detail: impl fmt::Display,
+    ) -> RemoteError {
+        RemoteError::new_ex(kind, format!("{self}: {detail}"))
+    }
+}
+
+/// Make a [`RemoteError`] of `kind` from the kube `err` returned by `action` (e.g. `create pods/exec`).
+///
+/// A 403 response becomes a [`KubeFsError::Forbidden`] `AuthenticationFailed`, naming the action.
+pub(crate) fn kube_error(err: kube::Error, kind: RemoteErrorType, action: &str) -> RemoteError {
+    match err {
+        kube::Error::Api(response) if response.code == 403 => KubeFsError::Forbidden
+            .into_remote_with(
+                RemoteErrorType::AuthenticationFailed,
+                format!("{action}: {}", response.message),
+            ),
+        err => RemoteError::new_ex(kind, err),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use kube::error::ErrorResponse;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_get_error_back_from_remote_error() {
+        for kube_err in KubeFsError::ALL {
+            let err = kube_err.into_remote(RemoteErrorType::ProtocolError);
+            assert_eq!(KubeFsError::from_remote(&err), Some(kube_err));
+            let err = kube_err.into_remote_with(RemoteErrorType::IoError, "\"/tmp/a.txt\"");
+            assert_eq!(KubeFsError::from_remote(&err), Some(kube_err));
+        }
+    }
+
+    #[test]
+    fn should_not_get_error_from_other_remote_errors() {
+        assert_eq!(
+            KubeFsError::from_remote(&RemoteError::new(RemoteErrorType::NotConnected)),
+            None
+        );
+        assert_eq!(
+            KubeFsError::from_remote(&RemoteError::new_ex(
+                RemoteErrorType::ProtocolError,
+                "cancelled by the server"
+            )),
+            None
+        );
+        assert_eq!(
+            KubeFsError::from_remote(&RemoteError::new_ex(
+                RemoteErrorType::StatFailed,
+                "stat failed: permission denied"
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn should_map_forbidden_response() {
+        let response = ErrorResponse {
+            status: "Failure".to_string(),
+            message: r#"pods "my-pod" is forbidden: User "system:serviceaccount:default:app" cannot create resource "pods/exec" in API group "" in the namespace "default""#.to_string(),
+            reason: "Forbidden".to_string(),
+            code: 403,
+        };
+        let err = kube_error(
+            kube::Error::Api(response),
+            RemoteErrorType::ProtocolError,
+            "create pods/exec",
+        );
+        assert_eq!(err.kind, RemoteErrorType::AuthenticationFailed);
+        assert_eq!(KubeFsError::from_remote(&err), Some(KubeFsError::Forbidden));
+        let msg = err.msg.unwrap();
+        assert!(msg.starts_with("forbidden by the api server: create pods/exec: "));
+        assert!(msg.contains("cannot create resource"));
+    }
+
+    #[test]
+    fn should_keep_kind_of_other_api_errors() {
+        let response = ErrorResponse {
+            status: "Failure".to_string(),
+            message: r#"pods "my-pod" not found"#.to_string(),
+            reason: "NotFound".to_string(),
+            code: 404,
+        };
+        let err = kube_error(
+            kube::Error::Api(response),
+            RemoteErrorType::NoSuchFileOrDirectory,
+            "get pods",
+        );
+        assert_eq!(err.kind, RemoteErrorType::NoSuchFileOrDirectory);
+        assert_eq!(KubeFsError::from_remote(&err), None);
+    }
+}
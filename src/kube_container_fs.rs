@@ -1,15 +1,88 @@
 //! ## Kube Container FS
 //!
 //! The `KubeContainerFs` client is a client that allows you to interact with a container in a pod.
-
+//!
+//! ### Options
+//!
+//! The client is configured with its builder methods, before `connect()`:
+//!
+//! - `kubeconfig_path`, `kubeconfig_paths`: the files are read on `connect()`, which fails with `ConnectionError`
+//!   if they're missing or invalid. They're ignored if `config` is called, and must be set before `client_cert`
+//!   and `ca_cert`. Multiple files are merged as kube does with `$KUBECONFIG`: the first file to set a value
+//!   or a named entry (cluster, context or user) wins, and entries are never merged field by field.
+//! - `client_cert`, `ca_cert`, `cluster_url`: if `config` hasn't been called, the default kubeconfig is loaded
+//!   and extended, keeping its namespace and credentials.
+//! - `connect_timeout`: overrides `Config::connect_timeout` and bounds the whole `connect()` call,
+//!   so that it doesn't hang on unreachable clusters.
+//! - `create_parents` (disabled by default), `overwrite` (enabled by default): when disabled, `copy` and `mov`
+//!   fail if the destination already exists.
+//! - `op_retries`: `stat`, `list_dir`, `exists` and `open_file` are retried with exponential backoff when they fail
+//!   on a transient transport error, e.g. a stream reset. Operations changing the filesystem are never retried.
+//!   Defaults to `0`.
+//! - `physical_paths`: the working directory is stored as `pwd -P` prints it, instead of as it was entered.
+//! - `strict_listing`: detects an unsupported `ls` format, instead of missing files.
+//! - `sudo`, `sudo_binary`: for exec users which aren't root, but can use sudo without a password; if sudo asks
+//!   for a password, operations fail with `AuthenticationFailed`. The binary defaults to `sudo`.
+//! - `tar_binary`: may be a command with arguments, such as `busybox tar`. Whether it's GNU tar or busybox tar
+//!   is detected on the first transfer, to pass the supported flags only. Defaults to `tar`.
+//! - `atomic`: the file is uploaded to a temporary sibling file, which is then renamed to the target path,
+//!   so readers never see a partially written file.
+//! - `default_file_mode`: by default, the mode is left to `tar`.
+//! - `respect_umask`: the umask, read on the first call, masks the modes passed to `create_dir`, `create_dir_all`,
+//!   `ensure_dir` and the uploads, as a local `mkdir` or `touch` would; uploaded files without a mode get `0666`
+//!   masked by it.
+//! - `cancellation_token`: once the token is cancelled, the running operation fails with a `ProtocolError`
+//!   carrying `KubeFsError::Cancelled`, as does any following one, until a new token is set.
+//! - `watch_pod`: the pod is watched in background, so that operations fail with `NotConnected` once it's deleted.
+//!   Disabled by default, since it keeps a watch open on the API server.
+//! - `transfer_block_size`: rounded up to a multiple of the tar block size (512 bytes). Defaults to 64 KiB.
+//! - `compress`: applies only if `gzip` is in the container, which is checked on the first transfer.
+//!   `open_file_limited` is never compressed.
+//! - `sparse`: the holes of sparse files (e.g. disk images) aren't transferred as zeros. It applies only if
+//!   the container has GNU tar, and never to uploads, which are archived locally from readers.
+//! - `streaming`: files larger than the local disk can be downloaded. If the download fails midway, the destination
+//!   keeps the part written until then and the download isn't retried; sparse files are transferred as zeros.
+//! - `temp_dir`: for when the system temp directory, the default, is too small or on a slow disk.
+//! - `transfer_keepalive`: proxies and load balancers may drop the exec connections when no data flows for a while,
+//!   and kube doesn't ping them, so the keepalive is set on their TCP sockets. The timeouts of the config still
+//!   apply; the config can't have a proxy, or connecting fails with `UnsupportedFeature`.
+
+#[cfg(feature = "find")]
+mod find;
+mod follow;
+#[cfg(feature = "find")]
+mod glob;
+mod keepalive;
+mod labels;
+mod list;
+mod metrics;
+mod op;
+mod pod_info;
+mod retry;
+mod shared_client;
+mod sudo;
+mod sync;
+mod tar_cmd;
+mod tar_stream;
+mod transfer;
+mod watch;
+
+use std::collections::HashMap;
+use std::io::{Read as _, Seek as _};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
 
-use futures_util::StreamExt as _;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec,
+};
 use k8s_openapi::api::core::v1::Pod;
-use kube::api::AttachParams;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Status};
+use kube::api::{AttachParams, AttachedProcess, PostParams};
+use kube::config::{KubeConfigOptions, Kubeconfig};
 use kube::{Api, Client, Config};
 use lazy_regex::{Lazy, Regex};
 use remotefs::fs::{
@@ -17,23 +90,87 @@ use remotefs::fs::{
     UnixPexClass, Welcome, WriteStream,
 };
 use remotefs::File;
-use tokio::io::AsyncWriteExt as _;
+use tokio::io::{AsyncBufReadExt as _, AsyncReadExt as _};
 use tokio::runtime::Runtime;
-
-use crate::utils::{fmt as fmt_utils, parser as parser_utils, path as path_utils};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+pub use self::follow::SymlinkFollow;
+use self::keepalive::ConnectorOptions;
+pub use self::labels::SecurityLabels;
+pub use self::list::{ListOptions, SortBy};
+pub use self::metrics::MetricsSink;
+use self::op::OpContext;
+pub use self::pod_info::PodInfo;
+pub use self::shared_client::SharedKubeClient;
+pub use self::sync::{SyncOptions, SyncReport};
+use self::tar_cmd::TarFlavor;
+use self::transfer::ArchiveEntry;
+use crate::error::kube_error;
+use crate::utils::{
+    fmt as fmt_utils, parser as parser_utils, path as path_utils, tls as tls_utils,
+};
+use crate::KubeFsError;
 
 /// NOTE: about this damn regex <https://stackoverflow.com/questions/32480890/is-there-a-regex-to-parse-the-values-from-an-ftp-directory-listing>
 static LS_RE: Lazy<Regex> = lazy_regex!(
-    r#"^([\-ld])([\-rwxsStT]{9})\s+(\d+)\s+(.+)\s+(.+)\s+(\d+)\s+(\w{3}\s+\d{1,2}\s+(?:\d{1,2}:\d{1,2}|\d{4}))\s+(.+)$"#
+    r#"^([\-ldpsbc])([\-rwxsStT]{9})\s+(\d+)\s+(.+)\s+(.+)\s+(\d+)\s+(\w{3}\s+\d{1,2}\s+(?:\d{1,2}:\d{1,2}|\d{4}))\s+(.+)$"#
 );
 
+/// Exit code of copy and move commands when the destination already exists and overwrite is disabled
+const DEST_EXISTS_RC: u32 = 17;
+
+/// Exit code of `rename` when the source doesn't exist
+const SRC_MISSING_RC: u32 = 2;
+
+/// Size of a tar block; transfer block sizes are multiple of it
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// Default size of the chunks used to stream data in file transfers (128 tar blocks)
+const DEFAULT_TRANSFER_BLOCK_SIZE: usize = 128 * TAR_BLOCK_SIZE;
+
+/// Upper bound of the bytes a single-file tar archive adds to the file: headers (long names included),
+/// padding and the record padding of the end of archive (20 blocks)
+const TAR_OVERHEAD: u64 = 40 * TAR_BLOCK_SIZE as u64;
+
 /// Kube "filesystem" client to interact with a container in a pod
 pub struct KubeContainerFs {
+    atomic: bool,
+    cancellation_token: Option<CancellationToken>,
     pub(crate) config: Option<Config>,
+    compress: bool,
+    connect_timeout: Option<Duration>,
     pub(crate) container: String,
+    create_parents: bool,
+    default_file_mode: Option<UnixPex>,
+    gzip_available: OnceLock<bool>,
+    home: Option<PathBuf>,
+    kubeconfig_paths: Vec<PathBuf>,
+    ls_literal: OnceLock<bool>,
+    metering: bool,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    op_retries: u32,
+    overwrite: bool,
+    physical_paths: bool,
+    pod_deleted: Arc<AtomicBool>,
     pub(crate) pod_name: String,
+    pod_watcher: Option<JoinHandle<()>>,
+    respect_umask: bool,
     pub(crate) pods: Option<Api<Pod>>,
     runtime: Arc<Runtime>,
+    shared_client: Option<Client>,
+    sparse: bool,
+    streaming: bool,
+    strict_listing: bool,
+    sudo: bool,
+    sudo_binary: String,
+    tar_binary: String,
+    tar_flavor: OnceLock<Option<TarFlavor>>,
+    temp_dir: Option<PathBuf>,
+    transfer_block_size: usize,
+    transfer_keepalive: Option<Duration>,
+    umask: OnceLock<u32>,
+    watch_pod: bool,
     pub(crate) wrkdir: PathBuf,
 }
 
@@ -43,1178 +180,5938 @@ impl KubeContainerFs {
     /// If `config()` is not called then, it will try to use the configuration from the default kubeconfig file
     pub fn new(pod_name: impl ToString, container: impl ToString, runtime: &Arc<Runtime>) -> Self {
         Self {
+            atomic: false,
+            cancellation_token: None,
             config: None,
+            compress: false,
+            connect_timeout: None,
             container: container.to_string(),
+            create_parents: false,
+            default_file_mode: None,
+            gzip_available: OnceLock::new(),
+            home: None,
+            kubeconfig_paths: Vec::new(),
+            ls_literal: OnceLock::new(),
+            metering: false,
+            metrics: None,
+            op_retries: 0,
+            overwrite: true,
+            physical_paths: false,
+            pod_deleted: Arc::new(AtomicBool::new(false)),
             pod_name: pod_name.to_string(),
+            pod_watcher: None,
+            respect_umask: false,
             pods: None,
             runtime: runtime.clone(),
+            shared_client: None,
+            sparse: false,
+            streaming: false,
+            strict_listing: false,
+            sudo: false,
+            sudo_binary: sudo::DEFAULT_SUDO_BINARY.to_string(),
+            tar_binary: tar_cmd::DEFAULT_TAR_BINARY.to_string(),
+            tar_flavor: OnceLock::new(),
+            temp_dir: None,
+            transfer_block_size: DEFAULT_TRANSFER_BLOCK_SIZE,
+            transfer_keepalive: None,
+            umask: OnceLock::new(),
+            watch_pod: false,
             wrkdir: PathBuf::from("/"),
         }
     }
 
+    /// Creates a new `KubeFs` using the kube client of `client`, instead of building its own.
+    ///
+    /// The configuration options (e.g. `config()` or `client_cert()`) are ignored,
+    /// and `refresh_auth()` keeps using the shared client.
+    pub fn from_shared_client(
+        pod_name: impl ToString,
+        container: impl ToString,
+        client: &SharedKubeClient,
+        runtime: &Arc<Runtime>,
+    ) -> Self {
+        let mut fs = Self::new(pod_name, container, runtime);
+        fs.shared_client = Some(client.client());
+        fs
+    }
+
     /// Set configuration
     pub fn config(mut self, config: Config) -> Self {
         self.config = Some(config);
         self
     }
 
-    // -- private
+    /// Load the configuration from the kubeconfig file at `path`
+    pub fn kubeconfig_path(mut self, path: PathBuf) -> Self {
+        self.kubeconfig_paths = vec![path];
+        self
+    }
 
-    /// Check connection status
-    fn check_connection(&mut self) -> RemoteResult<()> {
-        if self.is_connected() {
-            Ok(())
-        } else {
-            Err(RemoteError::new(RemoteErrorType::NotConnected))
-        }
+    /// Load the configuration merging the kubeconfig files at `paths`
+    pub fn kubeconfig_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.kubeconfig_paths = paths;
+        self
     }
 
-    /// Parse a line of `ls -l` output and tokenize the output into a `FsFile`
-    fn parse_ls_output(&self, path: &Path, line: &str) -> Result<File, ()> {
-        // Prepare list regex
-        trace!("Parsing LS line: '{}'", line);
-        // Apply regex to result
-        match LS_RE.captures(line) {
-            // String matches regex
-            Some(metadata) => {
-                // NOTE: metadata fmt: (regex, file_type, permissions, link_count, uid, gid, filesize, modified, filename)
-                // Expected 7 + 1 (8) values: + 1 cause regex is repeated at 0
-                if metadata.len() < 8 {
-                    return Err(());
-                }
-                // Collect metadata
-                // Get if is directory and if is symlink
-                let (is_dir, is_symlink): (bool, bool) = match metadata.get(1).unwrap().as_str() {
-                    "-" => (false, false),
-                    "l" => (false, true),
-                    "d" => (true, false),
-                    _ => return Err(()), // Ignore special files
-                };
-                // Check string length (unix pex)
-                if metadata.get(2).unwrap().as_str().len() < 9 {
-                    return Err(());
-                }
+    /// Set the timeout for establishing the connection to the cluster
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
 
-                let pex = |range: Range<usize>| {
-                    let mut count: u8 = 0;
-                    for (i, c) in metadata.get(2).unwrap().as_str()[range].chars().enumerate() {
-                        match c {
-                            '-' => {}
-                            _ => {
-                                count += match i {
-                                    0 => 4,
-                                    1 => 2,
-                                    2 => 1,
-                                    _ => 0,
-                                }
-                            }
-                        }
-                    }
-                    count
-                };
+    /// Set whether `copy` and `mov` create the missing parent directories of the destination
+    pub fn create_parents(mut self, create_parents: bool) -> Self {
+        self.create_parents = create_parents;
+        self
+    }
 
-                // Get unix pex
-                let mode = UnixPex::new(
-                    UnixPexClass::from(pex(0..3)),
-                    UnixPexClass::from(pex(3..6)),
-                    UnixPexClass::from(pex(6..9)),
-                );
+    /// Set whether `copy` and `mov` may overwrite an existing destination
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
 
-                // Parse modified and convert to SystemTime
-                let modified: SystemTime = match parser_utils::parse_lstime(
-                    metadata.get(7).unwrap().as_str(),
-                    "%b %d %Y",
-                    "%b %d %H:%M",
-                ) {
-                    Ok(t) => t,
-                    Err(_) => SystemTime::UNIX_EPOCH,
-                };
-                // Get uid
-                let uid: Option<u32> = match metadata.get(4).unwrap().as_str().parse::<u32>() {
-                    Ok(uid) => Some(uid),
-                    Err(_) => None,
-                };
-                // Get gid
-                let gid: Option<u32> = match metadata.get(5).unwrap().as_str().parse::<u32>() {
-                    Ok(gid) => Some(gid),
-                    Err(_) => None,
-                };
-                // Get filesize
-                let size = metadata
-                    .get(6)
-                    .unwrap()
-                    .as_str()
-                    .parse::<u64>()
-                    .unwrap_or(0);
-                // Get link and name
-                let (file_name, symlink): (String, Option<PathBuf>) = match is_symlink {
-                    true => self.get_name_and_link(metadata.get(8).unwrap().as_str()),
-                    false => (String::from(metadata.get(8).unwrap().as_str()), None),
-                };
-                // Sanitize file name
-                let file_name = PathBuf::from(&file_name)
-                    .file_name()
-                    .map(|x| x.to_string_lossy().to_string())
-                    .unwrap_or(file_name);
-                // Check if file_name is '.' or '..'
-                if file_name.as_str() == "." || file_name.as_str() == ".." {
-                    debug!("File name is {}; ignoring entry", file_name);
-                    return Err(());
-                }
-                // Re-check if is directory
-                let mut path: PathBuf = path.to_path_buf();
-                path.push(file_name.as_str());
-                // get file type
-                let file_type = if symlink.is_some() {
-                    FileType::Symlink
-                } else if is_dir {
-                    FileType::Directory
-                } else {
-                    FileType::File
-                };
-                // make metadata
-                let metadata = Metadata {
-                    accessed: None,
-                    created: None,
-                    file_type,
-                    gid,
-                    mode: Some(mode),
-                    modified: Some(modified),
-                    size,
-                    symlink,
-                    uid,
-                };
-                trace!(
-                    "Found entry at {} with metadata {:?}",
-                    path.display(),
-                    metadata
-                );
-                // Push to entries
-                Ok(File { path, metadata })
-            }
-            None => Err(()),
-        }
+    /// Set how many times the idempotent operations are retried on transient errors
+    pub fn op_retries(mut self, count: u32) -> Self {
+        self.op_retries = count;
+        self
     }
 
-    /// Perform shell cmd at path and return output and return code
-    fn shell_cmd_at_with_rc(
-        &self,
-        cmd: impl std::fmt::Display,
-        path: &Path,
-    ) -> RemoteResult<(u32, String)> {
-        const STDOUT_SIZE: usize = 2048;
+    /// Set whether the working directory is stored with symlinks resolved
+    pub fn physical_paths(mut self, physical_paths: bool) -> Self {
+        self.physical_paths = physical_paths;
+        self
+    }
 
-        let shell_cmd = format!(r#"cd {} && {}; echo -n ";$?""#, path.display(), cmd);
-        debug!("Executing shell command: {}", shell_cmd);
+    /// Set whether listing a directory fails on `ls` lines which can't be parsed
+    pub fn strict_listing(mut self, strict: bool) -> Self {
+        self.strict_listing = strict;
+        self
+    }
 
-        self.runtime.block_on(async {
-            let attach_params = AttachParams::default()
-                .stdout(true)
-                .stdin(false)
-                .stderr(true)
-                .container(self.container.clone())
-                .max_stdout_buf_size(STDOUT_SIZE);
+    /// Set whether commands and file transfers run through `sudo -n`
+    pub fn sudo(mut self, sudo: bool) -> Self {
+        self.sudo = sudo;
+        self
+    }
 
-            let mut process = self
-                .pods
-                .as_ref()
-                .unwrap()
-                .exec(
-                    &self.pod_name,
-                    vec!["/bin/sh", "-c", &shell_cmd],
-                    &attach_params,
-                )
-                .await
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+    /// Set the binary used to elevate permissions when `sudo` is enabled
+    pub fn sudo_binary(mut self, binary: impl ToString) -> Self {
+        self.sudo_binary = binary.to_string();
+        self
+    }
 
-            let stdout_reader =
-                tokio_util::io::ReaderStream::new(process.stdout().ok_or_else(|| {
-                    RemoteError::new_ex(RemoteErrorType::ProtocolError, "failed to read stdout")
-                })?);
+    /// Set the tar binary used for file transfers
+    pub fn tar_binary(mut self, binary: impl ToString) -> Self {
+        self.tar_binary = binary.to_string();
+        self.tar_flavor = OnceLock::new();
+        self
+    }
 
-            let stdout = stdout_reader
-                .filter_map(|r| async { r.ok().and_then(|v| String::from_utf8(v.to_vec()).ok()) })
-                .collect::<Vec<_>>()
-                .await
-                .join("");
-
-            // if level is debug print stderr
-            if log::log_enabled!(log::Level::Debug) {
-                let stderr_reader =
-                    tokio_util::io::ReaderStream::new(process.stderr().ok_or_else(|| {
-                        RemoteError::new_ex(RemoteErrorType::ProtocolError, "failed to read stderr")
-                    })?);
-
-                let stderr = stderr_reader
-                    .filter_map(|r| async {
-                        r.ok().and_then(|v| String::from_utf8(v.to_vec()).ok())
-                    })
-                    .collect::<Vec<_>>()
-                    .await
-                    .join("");
-                debug!("Shell command stderr: {stderr}",);
-            }
+    /// Set whether `create_file` writes files atomically
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
 
-            process.join().await.map_err(|err| {
-                RemoteError::new_ex(RemoteErrorType::ProtocolError, err.to_string())
-            })?;
+    /// Set the mode of the uploaded files when `metadata.mode` is unset
+    pub fn default_file_mode(mut self, mode: UnixPex) -> Self {
+        self.default_file_mode = Some(mode);
+        self
+    }
 
-            // collect rc from stdout
-            // count the number of tokens
-            let token_count = stdout.chars().filter(|c| *c == ';').count();
-            let mut tokens = stdout.split(';');
-            // stdout is all tokens, except the last one
-            let stdout = tokens
-                .by_ref()
-                .take(token_count)
-                .collect::<Vec<&str>>()
-                .join(";");
-            // last token is the return code
-            let rc = tokens
-                .next()
-                .ok_or_else(|| RemoteError::new(RemoteErrorType::ProtocolError))?
-                .parse::<u32>()
-                .map_err(|_| RemoteError::new(RemoteErrorType::ProtocolError))?;
+    /// Set whether the container umask applies to the modes of the created files and directories
+    pub fn respect_umask(mut self, respect_umask: bool) -> Self {
+        self.respect_umask = respect_umask;
+        self
+    }
 
-            debug!("Shell command exit code: {rc}",);
-            debug!("Shell command output: {stdout}");
+    /// Set the token used to cancel the operations on the container
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
 
-            Ok((rc, stdout))
-        })
+    /// Set whether to disconnect the client as soon as the pod is deleted
+    pub fn watch_pod(mut self, watch_pod: bool) -> Self {
+        self.watch_pod = watch_pod;
+        self
     }
 
-    /// Perform shell cmd and return output and return code
-    fn shell_cmd_with_rc(&self, cmd: impl std::fmt::Display) -> RemoteResult<(u32, String)> {
-        self.shell_cmd_at_with_rc(cmd, &self.wrkdir)
+    /// Set the size of the chunks used to stream the tar archives
+    pub fn transfer_block_size(mut self, size: usize) -> Self {
+        self.transfer_block_size = size.max(1).div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+        self
     }
 
-    /// Perform shell cmd and return output
-    fn shell_cmd(&self, cmd: impl std::fmt::Display) -> RemoteResult<String> {
-        self.shell_cmd_with_rc(cmd).map(|(_, output)| output)
+    /// Set whether file transfers through tar are compressed with gzip
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
     }
 
-    /// Returns from a `ls -l` command output file name token, the name of the file and the symbolic link (if there is any)
-    fn get_name_and_link(&self, token: &str) -> (String, Option<PathBuf>) {
-        let tokens: Vec<&str> = token.split(" -> ").collect();
-        let filename: String = String::from(*tokens.first().unwrap());
-        let symlink: Option<PathBuf> = tokens.get(1).map(PathBuf::from);
-        (filename, symlink)
+    /// Set whether downloads archive files with `tar --sparse`
+    pub fn sparse(mut self, sparse: bool) -> Self {
+        self.sparse = sparse;
+        self
     }
 
-    /// Execute setstat command and assert result is 0
-    fn assert_stat_command(&mut self, cmd: String) -> RemoteResult<()> {
-        match self.shell_cmd_with_rc(cmd) {
-            Ok((0, _)) => Ok(()),
-            Ok(_) => Err(RemoteError::new(RemoteErrorType::StatFailed)),
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
-        }
+    /// Set whether downloads are extracted while received, without buffering the archive locally
+    pub fn streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
     }
 
-    /// Returns whether file at `path` is a directory
-    fn is_directory(&mut self, path: &Path) -> RemoteResult<bool> {
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        match self.shell_cmd_with_rc(format!("test -d \"{}\"", path.display())) {
-            Ok((0, _)) => Ok(true),
-            Ok(_) => Ok(false),
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::StatFailed, err)),
-        }
+    /// Set the local directory where downloads are buffered
+    pub fn temp_dir(mut self, dir: PathBuf) -> Self {
+        self.temp_dir = Some(dir);
+        self
     }
-}
 
-impl RemoteFs for KubeContainerFs {
-    fn connect(&mut self) -> RemoteResult<Welcome> {
-        debug!("Initializing Kube connection...");
-        let api = self.runtime.block_on(async {
-            let client = match self.config.as_ref() {
-                Some(config) => Client::try_from(config.clone())
-                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err)),
-                None => Client::try_default()
-                    .await
-                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err)),
-            }?;
-            let api: Api<Pod> = Api::default_namespaced(client);
+    /// Enable TCP keepalive on the connections to the cluster, probing every `interval`
+    pub fn transfer_keepalive(mut self, interval: Duration) -> Self {
+        self.transfer_keepalive = Some(interval);
+        self
+    }
 
-            if api.get(&self.pod_name).await.is_err() {
-                Err(RemoteError::new(RemoteErrorType::ConnectionError))
-            } else {
-                Ok(api)
-            }
-        })?;
+    /// Set the sink receiving the name, duration and outcome of every [`RemoteFs`] operation.
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(sink);
+        self
+    }
 
-        debug!("Connection established with pod {}", self.pod_name);
-        // Set pods
-        self.pods = Some(api);
-        debug!("Getting working directory...");
-        // Get working directory
-        let wrkdir = self.shell_cmd("pwd")?;
-        if !wrkdir.starts_with('/') {
+    /// Set the client certificate and private key, in PEM format, used for mutual TLS
+    pub fn client_cert(mut self, cert_pem: Vec<u8>, key_pem: Vec<u8>) -> RemoteResult<Self> {
+        tls_utils::parse_certs(&cert_pem)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::SslError, err))?;
+        tls_utils::validate_private_key(&key_pem)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::SslError, err))?;
+
+        let config = self.config_mut()?;
+        config.auth_info.client_certificate = None;
+        config.auth_info.client_certificate_data = Some(BASE64_STANDARD.encode(cert_pem));
+        config.auth_info.client_key = None;
+        config.auth_info.client_key_data = Some(BASE64_STANDARD.encode(key_pem).into());
+        Ok(self)
+    }
+
+    /// Set the URL of the cluster and whether to accept its certificate even if invalid
+    pub fn cluster_url(mut self, url: &str, accept_invalid_certs: bool) -> RemoteResult<Self> {
+        let url = url
+            .parse()
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::BadAddress, err))?;
+
+        let config = self.config_mut()?;
+        config.cluster_url = url;
+        config.accept_invalid_certs = accept_invalid_certs;
+        Ok(self)
+    }
+
+    /// Set the CA certificates, in PEM format, used to verify the cluster certificate
+    pub fn ca_cert(mut self, pem: Vec<u8>) -> RemoteResult<Self> {
+        let certs = tls_utils::parse_certs(&pem)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::SslError, err))?;
+
+        self.config_mut()?.root_cert = Some(certs);
+        Ok(self)
+    }
+
+    /// Switch the client to another pod and container, reusing the current connection.
+    ///
+    /// The target is validated before being applied and the working and home directories are re-captured.
+    /// If the target doesn't exist, the previous pod and container are kept.
+    pub fn switch(
+        &mut self,
+        pod_name: impl ToString,
+        container: impl ToString,
+    ) -> RemoteResult<()> {
+        let pod_name = pod_name.to_string();
+        let container = container.to_string();
+        let api = self
+            .pods
+            .as_ref()
+            .ok_or_else(|| RemoteError::new(RemoteErrorType::NotConnected))?;
+        debug!("Switching to pod {pod_name} and container {container}");
+
+        let pod = self
+            .runtime
+            .block_on(async { api.get(&pod_name).await })
+            .map_err(|err| kube_error(err, RemoteErrorType::NoSuchFileOrDirectory, "get pods"))?;
+        let has_container = pod
+            .spec
+            .map(|spec| spec.containers.iter().any(|c| c.name == container))
+            .unwrap_or(false);
+        if !has_container {
             return Err(RemoteError::new_ex(
-                RemoteErrorType::ConnectionError,
-                format!("bad pwd response: {wrkdir}"),
+                RemoteErrorType::NoSuchFileOrDirectory,
+                format!("Container {container} not found in pod {pod_name}"),
             ));
         }
-        self.wrkdir = PathBuf::from(wrkdir.trim());
-        info!(
-            "Connection established; working directory: {}",
-            self.wrkdir.display()
-        );
-        Ok(Welcome::default())
+
+        let (prev_pod, prev_container) = self.set_target(pod_name, container);
+        let prev_wrkdir = std::mem::replace(&mut self.wrkdir, PathBuf::from("/"));
+
+        match self
+            .fetch_wrkdir()
+            .and_then(|wrkdir| Ok((wrkdir, self.fetch_home()?)))
+        {
+            Ok((wrkdir, home)) => {
+                self.wrkdir = wrkdir;
+                self.home = home;
+                self.start_pod_watch(pod.metadata);
+                info!(
+                    "Switched to pod {} and container {}; working directory: {}",
+                    self.pod_name,
+                    self.container,
+                    self.wrkdir.display()
+                );
+                Ok(())
+            }
+            Err(err) => {
+                self.set_target(prev_pod, prev_container);
+                self.wrkdir = prev_wrkdir;
+                Err(err)
+            }
+        }
     }
 
-    fn disconnect(&mut self) -> RemoteResult<()> {
-        if self.pods.is_none() {
-            return Err(RemoteError::new(RemoteErrorType::NotConnected));
+    /// Set the pod and container the commands run in, returning the previous ones.
+    ///
+    /// What was detected in the previous container, such as the tar flavor, is reset if either changes.
+    pub(crate) fn set_target(&mut self, pod_name: String, container: String) -> (String, String) {
+        if pod_name != self.pod_name || container != self.container {
+            self.tar_flavor = OnceLock::new();
+            self.gzip_available = OnceLock::new();
+            self.ls_literal = OnceLock::new();
+            self.umask = OnceLock::new();
         }
+        (
+            std::mem::replace(&mut self.pod_name, pod_name),
+            std::mem::replace(&mut self.container, container),
+        )
+    }
 
-        debug!("Disconnecting from remote...");
-        self.pods = None;
+    /// List directory entries at `path`, filtering and sorting them according to `opts`
+    pub fn list_dir_opts(&mut self, path: &Path, opts: ListOptions) -> RemoteResult<Vec<File>> {
+        self.check_connection()?;
+        let path = self.resolve(path);
+        let output = self.ls(path.as_path(), &opts.ls_flags())?;
+        let mut entries = self.parse_ls_lines(path.as_path(), output.as_str())?;
+        opts.sort(&mut entries);
+        Ok(entries)
+    }
 
-        info!("Disconnected from remote");
-        Ok(())
+    /// List directory entries at `path`, along with the total block count reported by `ls`.
+    ///
+    /// If `ls` doesn't report the total, 0 is returned.
+    pub fn list_dir_with_total(&mut self, path: &Path) -> RemoteResult<(u64, Vec<File>)> {
+        self.check_connection()?;
+        let path = self.resolve(path);
+        let output = self.ls(path.as_path(), "-la")?;
+        let total = output
+            .lines()
+            .next()
+            .and_then(parser_utils::parse_ls_total)
+            .unwrap_or(0);
+        Ok((total, self.parse_ls_lines(path.as_path(), output.as_str())?))
     }
 
-    fn is_connected(&mut self) -> bool {
-        if let Some(pods) = self.pods.as_ref() {
-            self.runtime
-                .block_on(async { pods.get_status(&self.pod_name).await.is_ok() })
-        } else {
-            false
-        }
+    /// Get the absolute path operations on `path` act on, resolving it against the working directory.
+    ///
+    /// A leading `~` is expanded to the home directory of the container, as captured on connect.
+    ///
+    /// No request is made to the container, so symlinks aren't resolved; see `canonicalize` for that.
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        let path = path_utils::expand_home(self.home.as_deref(), path);
+        path_utils::absolutize(self.wrkdir.as_path(), path.as_path())
     }
 
-    fn pwd(&mut self) -> RemoteResult<PathBuf> {
+    /// Get the home directory of the container, as captured on connect.
+    ///
+    /// If `$HOME` wasn't set then, it's queried again; fails with `NoSuchFileOrDirectory` if it's still unset.
+    pub fn home_dir(&mut self) -> RemoteResult<PathBuf> {
         self.check_connection()?;
-        Ok(self.wrkdir.clone())
+        if self.home.is_none() {
+            self.home = self.fetch_home()?;
+        }
+        self.home.clone().ok_or_else(|| {
+            RemoteError::new_ex(RemoteErrorType::NoSuchFileOrDirectory, "$HOME is not set")
+        })
     }
 
-    fn change_dir(&mut self, dir: &Path) -> RemoteResult<PathBuf> {
+    /// Find the entries under `dir` whose name matches the `name` wildcard and, if set, whose type is `file_type`.
+    ///
+    /// Unlike listing each directory, the search runs with a single `find` command in the container.
+    #[cfg(feature = "find")]
+    pub fn find_in(
+        &mut self,
+        dir: &Path,
+        name: &str,
+        file_type: Option<FileType>,
+    ) -> RemoteResult<Vec<File>> {
         self.check_connection()?;
-        let dir = path_utils::absolutize(self.wrkdir.as_path(), dir);
-        debug!("Changing working directory to {}", dir.display());
-        match self.shell_cmd(format!("cd \"{}\"; echo $?; pwd", dir.display())) {
-            Ok(output) => {
-                // Trim
-                let output: String = String::from(output.as_str().trim());
-                // Check if output starts with 0; should be 0{PWD}
-                match output.as_str().starts_with('0') {
-                    true => {
-                        // Set working directory
-                        self.wrkdir = PathBuf::from(&output.as_str()[1..].trim());
-                        debug!("Changed working directory to {}", self.wrkdir.display());
-                        Ok(self.wrkdir.clone())
-                    }
-                    false => Err(RemoteError::new_ex(
-                        // No such file or directory
-                        RemoteErrorType::NoSuchFileOrDirectory,
-                        format!("\"{}\"", dir.display()),
-                    )),
-                }
-            }
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
+        let dir = self.resolve(dir);
+        debug!("Finding {name} in {}", dir.display());
+        if !self.exists_abs(dir.as_path())? {
+            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
         }
+        self.run_find(dir.as_path(), &find::name_predicates(name, file_type))
     }
 
-    fn list_dir(&mut self, path: &Path) -> RemoteResult<Vec<File>> {
+    /// Get the entries matching the glob `pattern` (e.g. `/var/log/**/*.gz`), with a single `find` command.
+    ///
+    /// `*`, `?` and `[...]` match within a path component, while `**` matches any number of directories.
+    /// The pattern is resolved by `find`, not by the shell, so the container shell doesn't need globstar support.
+    #[cfg(feature = "find")]
+    pub fn glob(&mut self, pattern: &str) -> RemoteResult<Vec<File>> {
         self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        debug!("Getting file entries in {}", path.display());
-        // check if exists
-        if !self.exists(path.as_path()).ok().unwrap_or(false) {
-            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+        let glob = glob::Glob::new(&self.resolve(Path::new(pattern)));
+        debug!("Globbing {pattern}: {glob:?}");
+        if !glob.has_wildcards() {
+            return match self.stat_abs(glob.base.as_path()) {
+                Ok(file) => Ok(vec![file]),
+                Err(err) if err.kind == RemoteErrorType::NoSuchFileOrDirectory => Ok(Vec::new()),
+                Err(err) => Err(err),
+            };
         }
-        match self.shell_cmd(format!("ls -la \"{}/\"", path.display()).as_str()) {
-            Ok(output) => {
-                // Split output by (\r)\n
-                let lines: Vec<&str> = output.as_str().lines().collect();
-                let mut entries: Vec<File> = Vec::with_capacity(lines.len());
-                for line in lines.iter() {
-                    // First line must always be ignored
-                    // Parse row, if ok push to entries
-                    if let Ok(entry) = self.parse_ls_output(path.as_path(), line) {
-                        entries.push(entry);
-                    }
-                }
-                debug!(
-                    "Found {} out of {} valid file entries",
-                    entries.len(),
-                    lines.len()
-                );
-                Ok(entries)
-            }
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
+        if !self.exists_abs(glob.base.as_path())? {
+            return Ok(Vec::new());
         }
+        let files = self.run_find(glob.base.as_path(), &glob.find_predicates())?;
+        Ok(files
+            .into_iter()
+            .filter(|file| glob.matches(file.path()))
+            .collect())
     }
 
-    fn stat(&mut self, path: &Path) -> RemoteResult<File> {
-        self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        debug!("Stat {}", path.display());
-        // make command; Directories require `-d` option
-        let cmd = match self.is_directory(path.as_path())? {
-            true => format!("ls -ld \"{}\"", path.display()),
-            false => format!("ls -l \"{}\"", path.display()),
-        };
-        match self.shell_cmd(cmd.as_str()) {
-            Ok(line) => {
-                // Parse ls line
-                let parent: PathBuf = match path.as_path().parent() {
-                    Some(p) => PathBuf::from(p),
-                    None => {
-                        return Err(RemoteError::new_ex(
-                            RemoteErrorType::StatFailed,
-                            "Path has no parent",
-                        ))
-                    }
-                };
-                match self.parse_ls_output(parent.as_path(), line.as_str().trim()) {
-                    Ok(entry) => Ok(entry),
-                    Err(_) => Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory)),
-                }
-            }
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
+    /// Run `find` in the absolute `dir` with `predicates`, returning the entries found
+    #[cfg(feature = "find")]
+    fn run_find(&self, dir: &Path, predicates: &str) -> RemoteResult<Vec<File>> {
+        let (output, stderr) = self.find_output(find::find_cmd(dir, predicates, true))?;
+        if !find::is_printf_unsupported(&stderr) {
+            return Ok(find::parse_printf_output(&output));
         }
+
+        debug!("find doesn't support -printf; listing matches with ls");
+        let (output, _) = self.find_output(find::find_cmd(dir, predicates, false))?;
+        Ok(output
+            .lines()
+            .filter_map(|line| self.parse_find_ls_line(line))
+            .collect())
     }
 
-    fn exists(&mut self, path: &Path) -> RemoteResult<bool> {
-        self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        match self.shell_cmd_with_rc(format!("test -e \"{}\"", path.display())) {
-            Ok((0, _)) => Ok(true),
-            Ok(_) => Ok(false),
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::StatFailed, err)),
+    /// Run the `find` command `cmd`, returning its output and stderr.
+    ///
+    /// Unreadable directories make find exit with 1, but the other entries are printed anyway,
+    /// so a failure is only logged.
+    fn find_output(&self, cmd: String) -> RemoteResult<(String, String)> {
+        let (rc, output, stderr) = self.runtime.block_on(self.shell_cmd_in_with_stderr(
+            &self.pod_name,
+            &self.container,
+            cmd,
+        ))?;
+        if rc != 0 {
+            debug!("find exited with code {rc}: {stderr}");
         }
+
+        Ok((output, stderr))
     }
 
-    fn setstat(&mut self, path: &Path, metadata: Metadata) -> RemoteResult<()> {
+    /// Get the canonical path of `path`, resolving all the symlinks in the container.
+    ///
+    /// Uses `realpath` or `readlink -f`; if neither is available in the container,
+    /// the absolutized path is returned as is.
+    pub fn canonicalize(&mut self, path: &Path) -> RemoteResult<PathBuf> {
         self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        debug!("Setting attributes for {}", path.display());
-        if !self.exists(path.as_path()).ok().unwrap_or(false) {
+        let path = self.resolve(path);
+        debug!("Canonicalizing {}", path.display());
+        if !self.exists(path.as_path())? {
             return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
         }
-        // set mode with chmod
-        if let Some(mode) = metadata.mode {
-            self.assert_stat_command(format!(
-                "chmod {:o} \"{}\"",
-                u32::from(mode),
-                path.display()
-            ))?;
-        }
-        if let Some(user) = metadata.uid {
-            self.assert_stat_command(format!(
-                "chown {}{} \"{}\"",
-                user,
-                metadata.gid.map(|x| format!(":{x}")).unwrap_or_default(),
-                path.display()
-            ))?;
-        }
-        // set times
-        if let Some(accessed) = metadata.accessed {
-            self.assert_stat_command(format!(
-                "touch -a -t {} \"{}\"",
-                fmt_utils::fmt_time_utc(accessed, "%Y%m%d%H%M.%S"),
-                path.display()
-            ))?;
-        }
-        if let Some(modified) = metadata.modified {
-            self.assert_stat_command(format!(
-                "touch -m -t {} \"{}\"",
-                fmt_utils::fmt_time_utc(modified, "%Y%m%d%H%M.%S"),
-                path.display()
-            ))?;
+        let cmd = format!(
+            r#"if command -v realpath >/dev/null 2>&1; then realpath "{p}"; elif command -v readlink >/dev/null 2>&1; then readlink -f "{p}"; else (exit 127); fi"#,
+            p = path.display()
+        );
+        match self.shell_cmd_with_rc(cmd)? {
+            (0, output) if output.trim().starts_with('/') => Ok(PathBuf::from(output.trim())),
+            (127, _) => {
+                debug!("Neither realpath nor readlink are available; using absolute path");
+                Ok(path)
+            }
+            _ => Err(RemoteError::new_ex(
+                RemoteErrorType::NoSuchFileOrDirectory,
+                format!("could not resolve \"{}\"", path.display()),
+            )),
         }
-        Ok(())
     }
 
-    fn remove_file(&mut self, path: &Path) -> RemoteResult<()> {
+    /// Create a directory at `path` with `mode`, creating the missing parent directories too.
+    ///
+    /// Unlike `create_dir`, it succeeds if the directory already exists.
+    pub fn create_dir_all(&mut self, path: &Path, mode: UnixPex) -> RemoteResult<()> {
         self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        if !self.exists(path.as_path()).ok().unwrap_or(false) {
-            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
-        }
-        debug!("Removing file {}", path.display());
-        match self.shell_cmd_with_rc(format!("rm -f \"{}\"", path.display())) {
-            Ok((0, _)) => Ok(()),
-            Ok(_) => Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
+        let path = self.resolve(path);
+        let mode = format!("{:o}", u32::from(self.dir_mode(mode)));
+        debug!(
+            "Creating directory at {} with mode {} and its parents",
+            path.display(),
+            mode
+        );
+        match self.shell_cmd_with_rc(format!("mkdir -p -m {} \"{}\"", mode, path.display()))? {
+            (0, _) => Ok(()),
+            _ => Err(RemoteError::new(RemoteErrorType::FileCreateDenied)),
         }
     }
 
-    fn remove_dir(&mut self, path: &Path) -> RemoteResult<()> {
+    /// Make sure a directory exists at `path` with `mode`, creating it if missing, or changing its mode if it differs.
+    ///
+    /// Unlike `create_dir`, it succeeds if the directory already exists, which makes it suitable for idempotent
+    /// provisioning. Fails with `DirectoryAlreadyExists` if `path` exists but is not a directory.
+    pub fn ensure_dir(&mut self, path: &Path, mode: UnixPex) -> RemoteResult<()> {
         self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        if !self.exists(path.as_path()).ok().unwrap_or(false) {
-            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+        let path = self.resolve(path);
+        let mode = self.dir_mode(mode);
+        if !self.exists(path.as_path())? {
+            return self.create_dir(path.as_path(), mode);
+        }
+
+        let stat = self.stat(path.as_path())?;
+        if !stat.is_dir() {
+            return Err(KubeFsError::NotADirectory.into_remote_with(
+                RemoteErrorType::DirectoryAlreadyExists,
+                format!("{} exists and is not a directory", path.display()),
+            ));
         }
-        debug!("Removing directory {}", path.display());
-        match self.shell_cmd_with_rc(format!("rmdir \"{}\"", path.display())) {
-            Ok((0, _)) => Ok(()),
-            Ok(_) => Err(RemoteError::new(RemoteErrorType::DirectoryNotEmpty)),
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
+        if stat.metadata.mode.map(u32::from) != Some(u32::from(mode)) {
+            debug!(
+                "Fixing mode of directory {} to {:o}",
+                path.display(),
+                u32::from(mode)
+            );
+            self.setstat(path.as_path(), Metadata::default().mode(mode))?;
         }
+
+        Ok(())
     }
 
-    fn remove_dir_all(&mut self, path: &Path) -> RemoteResult<()> {
+    /// Get the disk usage of `path`, and of everything under it if it's a directory, in bytes, with `du -sk`.
+    ///
+    /// Entries which can't be read are skipped, so the result is a lower bound in that case.
+    pub fn disk_usage(&mut self, path: &Path) -> RemoteResult<u64> {
         self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        if !self.exists(path.as_path()).ok().unwrap_or(false) {
+        let path = self.resolve(path);
+        if !self.exists(path.as_path())? {
             return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
         }
-        debug!("Removing directory {} recursively", path.display());
-        match self.shell_cmd_with_rc(format!("rm -rf \"{}\"", path.display())) {
-            Ok((0, _)) => Ok(()),
-            Ok(_) => Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
+        debug!("Getting disk usage of {}", path.display());
+        let (rc, output) =
+            self.shell_cmd_with_rc(format!("du -sk {}", path_utils::quote(&path)))?;
+        if rc != 0 {
+            debug!("du exited with code {rc}; some entries could not be read");
         }
+        parser_utils::parse_du_output(&output).ok_or_else(|| {
+            RemoteError::new_ex(
+                RemoteErrorType::ProtocolError,
+                format!("bad du output: {output}"),
+            )
+        })
     }
 
-    fn create_dir(&mut self, path: &Path, mode: UnixPex) -> RemoteResult<()> {
+    /// Stat `path`, along with its security labels: the SELinux context, with `ls -Zd`, and the extended attributes,
+    /// with `getfattr`.
+    ///
+    /// It helps debugging permission issues on SELinux clusters. Labels the container can't report,
+    /// e.g. because `getfattr` isn't installed, are left empty rather than failing.
+    pub fn stat_ext(&mut self, path: &Path) -> RemoteResult<(File, SecurityLabels)> {
         self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        if self.exists(path.as_path()).ok().unwrap_or(false) {
-            return Err(RemoteError::new(RemoteErrorType::DirectoryAlreadyExists));
-        }
-        let mode = format!("{:o}", u32::from(mode));
-        debug!(
-            "Creating directory at {} with mode {}",
-            path.display(),
-            mode
-        );
-        match self.shell_cmd_with_rc(format!("mkdir -m {} \"{}\"", mode, path.display())) {
-            Ok((0, _)) => Ok(()),
-            Ok(_) => Err(RemoteError::new(RemoteErrorType::FileCreateDenied)),
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
-        }
+        let file = self.stat(path)?;
+        let path = file.path().to_path_buf();
+        debug!("Getting security labels of {}", path.display());
+
+        let selinux_context =
+            match self.shell_cmd_with_rc(format!("ls -Zd \"{}\" 2> /dev/null", path.display()))? {
+                (0, output) => parser_utils::parse_ls_context(&output),
+                (rc, _) => {
+                    debug!("ls -Z exited with code {rc}; no SELinux context");
+                    None
+                }
+            };
+        let xattrs = match self.shell_cmd_with_rc(format!(
+            "getfattr -d -m - --absolute-names \"{}\" 2> /dev/null",
+            path.display()
+        ))? {
+            (0, output) => parser_utils::parse_getfattr(&output),
+            (rc, _) => {
+                debug!("getfattr exited with code {rc}; no extended attributes");
+                Vec::new()
+            }
+        };
+
+        Ok((
+            file,
+            SecurityLabels {
+                selinux_context,
+                xattrs,
+            },
+        ))
     }
 
-    fn symlink(&mut self, path: &Path, target: &Path) -> RemoteResult<()> {
+    /// Returns whether `a` and `b` are the same file, e.g. hard links or a symbolic link and its target,
+    /// comparing their device and inode with a single `stat -L`.
+    ///
+    /// If `stat` isn't available, it falls back to `ls -i`, which only compares the inodes.
+    /// Fails with `NoSuchFileOrDirectory` if either file doesn't exist.
+    pub fn same_file(&mut self, a: &Path, b: &Path) -> RemoteResult<bool> {
         self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
+        let a = self.resolve(a);
+        let b = self.resolve(b);
         debug!(
-            "Creating a symlink at {} pointing at {}",
-            path.display(),
-            target.display()
+            "Checking whether {} and {} are the same file",
+            a.display(),
+            b.display()
         );
-        if !self.exists(target).ok().unwrap_or(false) {
-            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
-        }
-        if self.exists(path.as_path()).ok().unwrap_or(false) {
-            return Err(RemoteError::new(RemoteErrorType::FileCreateDenied));
-        }
+        let files = format!("\"{}\" \"{}\"", a.display(), b.display());
         match self.shell_cmd_with_rc(format!(
-            "ln -s \"{}\" \"{}\"",
-            target.display(),
-            path.display()
-        )) {
-            Ok((0, _)) => Ok(()),
-            Ok(_) => Err(RemoteError::new(RemoteErrorType::FileCreateDenied)),
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
+            "stat -L -c '%d:%i' {files} 2> /dev/null || ls -idL {files}"
+        ))? {
+            (0, output) => parser_utils::parse_same_file(&output).ok_or_else(|| {
+                RemoteError::new_ex(
+                    RemoteErrorType::ProtocolError,
+                    format!("unexpected file identities: {}", output.trim()),
+                )
+            }),
+            (_, output) => Err(RemoteError::new_ex(
+                RemoteErrorType::NoSuchFileOrDirectory,
+                output.trim().to_string(),
+            )),
         }
     }
 
-    fn copy(&mut self, src: &Path, dest: &Path) -> RemoteResult<()> {
+    /// Rename `from` to `to`, failing with [`KubeFsError::AlreadyExists`] if `to` exists, whatever the `overwrite` option.
+    ///
+    /// Unlike `mov`, which clobbers with `mv -f`, the existence of `to` is checked and the file is renamed
+    /// in a single command; another process could still create `to` in between, though.
+    pub fn rename(&mut self, from: &Path, to: &Path) -> RemoteResult<()> {
         self.check_connection()?;
-        let src = path_utils::absolutize(self.wrkdir.as_path(), src);
-        // check if file exists
-        if !self.exists(src.as_path()).ok().unwrap_or(false) {
-            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
-        }
-        let dest = path_utils::absolutize(self.wrkdir.as_path(), dest);
-        debug!("Copying {} to {}", src.display(), dest.display());
-        match self.shell_cmd_with_rc(
-            format!("cp -rf \"{}\" \"{}\"", src.display(), dest.display()).as_str(),
-        ) {
-            Ok((0, _)) => Ok(()),
-            Ok(_) => Err(RemoteError::new_ex(
-                // Could not copy file
+        let from = self.resolve(from);
+        let to = self.resolve(to);
+        debug!("Renaming {} to {}", from.display(), to.display());
+        match self.shell_cmd_with_rc(rename_cmd(&from, &to))? {
+            (0, _) => Ok(()),
+            (DEST_EXISTS_RC, _) => Err(KubeFsError::AlreadyExists.into_remote_with(
                 RemoteErrorType::FileCreateDenied,
-                format!("\"{}\"", dest.display()),
+                format!("\"{}\"", to.display()),
             )),
-            Err(err) => Err(RemoteError::new_ex(
-                RemoteErrorType::ProtocolError,
-                err.to_string(),
+            (SRC_MISSING_RC, _) => Err(RemoteError::new_ex(
+                RemoteErrorType::NoSuchFileOrDirectory,
+                format!("\"{}\"", from.display()),
+            )),
+            (rc, output) => Err(RemoteError::new_ex(
+                RemoteErrorType::FileCreateDenied,
+                format!("mv exited with code {rc}: {}", output.trim()),
             )),
         }
     }
 
-    fn mov(&mut self, src: &Path, dest: &Path) -> RemoteResult<()> {
+    /// Wait until `path` exists, checking it with `test -e` every `poll_interval`, e.g. to wait for a process
+    /// to produce its output.
+    ///
+    /// Fails with [`KubeFsError::WaitTimeout`] if it doesn't exist after `timeout`.
+    /// The wait is interrupted by the cancellation token, if set.
+    pub fn wait_for_file(
+        &mut self,
+        path: &Path,
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> RemoteResult<()> {
         self.check_connection()?;
-        let src = path_utils::absolutize(self.wrkdir.as_path(), src);
-        // check if file exists
-        if !self.exists(src.as_path()).ok().unwrap_or(false) {
-            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+        let path = self.resolve(path);
+        debug!("Waiting up to {timeout:?} for {}", path.display());
+        let started = std::time::Instant::now();
+        loop {
+            if self.exists_abs(path.as_path())? {
+                debug!("{} exists after {:?}", path.display(), started.elapsed());
+                return Ok(());
+            }
+            let Some(remaining) = timeout
+                .checked_sub(started.elapsed())
+                .filter(|d| !d.is_zero())
+            else {
+                return Err(KubeFsError::WaitTimeout.into_remote_with(
+                    RemoteErrorType::ProtocolError,
+                    format!("\"{}\" doesn't exist after {timeout:?}", path.display()),
+                ));
+            };
+            self.runtime.block_on(self.cancellable(async {
+                tokio::time::sleep(poll_interval.min(remaining)).await;
+                Ok(())
+            }))?;
         }
-        let dest = path_utils::absolutize(self.wrkdir.as_path(), dest);
-        debug!("Moving {} to {}", src.display(), dest.display());
-        match self.shell_cmd_with_rc(
-            format!("mv -f \"{}\" \"{}\"", src.display(), dest.display()).as_str(),
-        ) {
-            Ok((0, _)) => Ok(()),
-            Ok(_) => Err(RemoteError::new_ex(
-                // Could not copy file
+    }
+
+    /// Prepare the exec request running `cmd` in the container, so that external code can attach to it,
+    /// e.g. to run an interactive shell next to the file transfers. `cmd` is prefixed with sudo if enabled.
+    ///
+    /// The request has a relative URI and no credentials: send it with the kube client of the connection
+    /// (e.g. with [`Client::connect`]), which adds the cluster url and the authentication.
+    ///
+    /// # Security
+    ///
+    /// Whoever sends the request runs arbitrary commands in the container with the identity of the client,
+    /// so don't hand it to untrusted code. The command is part of the query string, thus it's recorded
+    /// in the API server audit logs: don't put secrets in it.
+    pub fn exec_endpoint(&self, cmd: &[&str]) -> RemoteResult<http::Request<Vec<u8>>> {
+        let pods = self
+            .pods
+            .as_ref()
+            .ok_or_else(|| RemoteError::new(RemoteErrorType::NotConnected))?;
+        let attach_params = AttachParams::default()
+            .stdin(true)
+            .stdout(true)
+            .stderr(true)
+            .container(self.container.clone());
+        kube::core::Request::new(pods.resource_url())
+            .exec(&self.pod_name, self.command(cmd.to_vec()), &attach_params)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))
+    }
+
+    /// Create a named pipe (FIFO) at `path` with `mode`, with `mkfifo`.
+    ///
+    /// FIFOs are reported as files by `stat` and `list_dir`. Fails with `UnsupportedFeature` if `mkfifo` is not
+    /// available in the container.
+    pub fn mkfifo(&mut self, path: &Path, mode: UnixPex) -> RemoteResult<()> {
+        self.check_connection()?;
+        let path = self.resolve(path);
+        if self.exists(path.as_path())? {
+            return Err(RemoteError::new_ex(
                 RemoteErrorType::FileCreateDenied,
-                format!("\"{}\"", dest.display()),
+                format!("{} already exists", path.display()),
+            ));
+        }
+        let mode = format!("{:o}", u32::from(mode));
+        debug!("Creating FIFO at {} with mode {}", path.display(), mode);
+        match self.shell_cmd_with_rc(format!("mkfifo -m {} {}", mode, path_utils::quote(&path)))? {
+            (0, _) => Ok(()),
+            (127, _) => Err(RemoteError::new_ex(
+                RemoteErrorType::UnsupportedFeature,
+                "mkfifo is not available",
             )),
-            Err(err) => Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err)),
+            _ => Err(RemoteError::new(RemoteErrorType::FileCreateDenied)),
         }
     }
 
-    fn exec(&mut self, cmd: &str) -> RemoteResult<(u32, String)> {
+    /// Change the mode of `path` and, if it's a directory, of everything under it, with `chmod -R`.
+    ///
+    /// Fails with `BadFile` if `path` resolves to `/`.
+    pub fn chmod_recursive(&mut self, path: &Path, mode: UnixPex) -> RemoteResult<()> {
         self.check_connection()?;
-        debug!(r#"Executing command "{}""#, cmd);
-        self.shell_cmd_at_with_rc(cmd, self.wrkdir.as_path())
+        let path = self.recursive_target(path)?;
+        debug!(
+            "Changing mode of {} to {:o} recursively",
+            path.display(),
+            u32::from(mode)
+        );
+        self.assert_stat_command(format!(
+            "chmod -R {:o} {}",
+            u32::from(mode),
+            path_utils::quote(&path)
+        ))
     }
 
-    fn append(&mut self, _path: &Path, _metadata: &Metadata) -> RemoteResult<WriteStream> {
-        Err(RemoteError::new(RemoteErrorType::UnsupportedFeature))
+    /// Change the owner of `path` and, if it's a directory, of everything under it, with `chown -R`.
+    ///
+    /// Symbolic links are changed themselves and never followed, as with [`SymlinkFollow::Never`].
+    /// Fails with `BadFile` if `path` resolves to `/`.
+    pub fn chown_recursive(&mut self, path: &Path, uid: u32, gid: u32) -> RemoteResult<()> {
+        self.chown_recursive_with(path, uid, gid, SymlinkFollow::default())
     }
 
-    fn create(&mut self, _path: &Path, _metadata: &Metadata) -> RemoteResult<WriteStream> {
-        Err(RemoteError::new(RemoteErrorType::UnsupportedFeature))
+    /// Change the owner of `path` and of everything under it like [`Self::chown_recursive`],
+    /// handling symbolic links according to `follow`.
+    ///
+    /// Following links may change files outside of `path`, including `/`.
+    pub fn chown_recursive_with(
+        &mut self,
+        path: &Path,
+        uid: u32,
+        gid: u32,
+        follow: SymlinkFollow,
+    ) -> RemoteResult<()> {
+        self.check_connection()?;
+        let path = match follow {
+            SymlinkFollow::Never => self.recursive_link_target(path)?,
+            SymlinkFollow::CommandLine | SymlinkFollow::Always => self.recursive_target(path)?,
+        };
+        debug!(
+            "Changing owner of {} to {uid}:{gid} recursively ({follow:?})",
+            path.display()
+        );
+        self.assert_stat_command(format!(
+            "chown -R {} {uid}:{gid} {}",
+            follow.chown_flags().join(" "),
+            path_utils::quote(&path)
+        ))
     }
 
-    fn open(&mut self, _path: &Path) -> RemoteResult<ReadStream> {
-        Err(RemoteError::new(RemoteErrorType::UnsupportedFeature))
+    /// Upload multiple files with a single `tar` stream, returning the size of each file.
+    ///
+    /// Missing parent directories are created. The `atomic` option doesn't apply here.
+    pub fn create_files(
+        &mut self,
+        files: Vec<(PathBuf, Metadata, Box<dyn std::io::Read + Send>)>,
+    ) -> RemoteResult<Vec<u64>> {
+        self.check_connection()?;
+        if files.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!("uploading {} files in a single archive", files.len());
+
+        let mut paths = Vec::with_capacity(files.len());
+        let mut entries = Vec::with_capacity(files.len());
+        for (path, metadata, reader) in files {
+            let path = self.resolve(path.as_path());
+            // entries are stored relative to the root
+            let tar_path = path
+                .strip_prefix("/")
+                .unwrap_or(path.as_path())
+                .to_path_buf();
+            paths.push(path);
+            entries.push(ArchiveEntry {
+                path: tar_path,
+                size: metadata.size,
+                mode: self.file_mode(metadata.mode),
+                reader,
+            });
+        }
+        let sizes: Vec<u64> = entries.iter().map(|entry| entry.size).collect();
+
+        let data = Self::make_archive(entries)?;
+        self.upload_archive(Path::new("/"), &data)?;
+
+        // check all the files have been created at once
+        let test_cmd = paths
+            .iter()
+            .map(|path| format!("test -e \"{}\"", path.display()))
+            .collect::<Vec<String>>()
+            .join(" && ");
+        match self.shell_cmd_with_rc(test_cmd)? {
+            (0, _) => Ok(sizes),
+            _ => Err(RemoteError::new_ex(
+                RemoteErrorType::NoSuchFileOrDirectory,
+                "failed to create files",
+            )),
+        }
     }
 
-    fn create_file(
+    /// Mirror the local directory `local_root` to `remote_root`, uploading only the files
+    /// whose size or mtime differ from the remote ones.
+    ///
+    /// If `opts.delete` is set, remote entries which don't exist locally are removed; remote entries of another
+    /// type than the local ones are always replaced. Local symlinks are neither followed nor synced.
+    pub fn sync_up(
         &mut self,
-        path: &Path,
-        metadata: &Metadata,
-        reader: Box<dyn std::io::Read + Send>,
-    ) -> RemoteResult<u64> {
+        local_root: &Path,
+        remote_root: &Path,
+        opts: SyncOptions,
+    ) -> RemoteResult<SyncReport> {
         self.check_connection()?;
-        let path = path_utils::absolutize(self.wrkdir.as_path(), path);
-        let file_name = path
-            .file_name()
-            .ok_or(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory))?;
-        let tar_path = PathBuf::from(file_name);
-        // prepare write
-        let mut header = tar::Header::new_gnu();
-        header
-            .set_path(tar_path)
+        let remote_root = self.resolve(remote_root);
+        debug!(
+            "Syncing {} to {}",
+            local_root.display(),
+            remote_root.display()
+        );
+        let local = sync::walk_local(local_root)
             .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
-        header.set_size(metadata.size);
-        header.set_cksum();
+        let remote = if self.is_directory(remote_root.as_path())? {
+            self.walk(remote_root.as_path())?
+        } else {
+            self.create_dir_all(remote_root.as_path(), UnixPex::from(0o755))?;
+            HashMap::new()
+        };
 
-        debug!("preparing archive to upload");
-        let mut ar = tar::Builder::new(Vec::new());
-        debug!("appending data to archive");
-        ar.append(&header, reader)
-            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
-        debug!("uploading archive to kube at: {}", path.display());
+        let report = SyncReport::plan(&local, &remote, opts);
+        let local: HashMap<PathBuf, Metadata> = local.into_iter().collect();
+        for path in report.deleted.iter() {
+            let dest = remote_root.join(path);
+            debug!("Removing {}", dest.display());
+            if remote[path].is_dir() {
+                self.remove_dir_all(dest.as_path())?;
+            } else {
+                self.remove_file(dest.as_path())?;
+            }
+        }
+        for path in report.created_dirs.iter() {
+            let mode = local[path].mode.unwrap_or(UnixPex::from(0o755));
+            self.create_dir_all(remote_root.join(path).as_path(), mode)?;
+        }
+        for path in report.uploaded.iter() {
+            let metadata = &local[path];
+            let dest = remote_root.join(path);
+            debug!("Uploading {} to {}", path.display(), dest.display());
+            let reader = std::fs::File::open(local_root.join(path))
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+            self.create_file(dest.as_path(), metadata, Box::new(reader))?;
+            // keep the local mtime, so the file matches on the next sync
+            if let Some(modified) = metadata.modified {
+                self.setstat(dest.as_path(), Metadata::default().modified(modified))?;
+            }
+        }
 
-        let data = ar
-            .into_inner()
-            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        Ok(report)
+    }
 
-        let dir_path = path.parent().unwrap_or(Path::new("/"));
-        debug!("uploading archive to kube in dir: {}", dir_path.display());
+    /// Read the first `bytes` bytes of the file at `path` with `head -c`, e.g. for previews, without downloading it.
+    ///
+    /// If the file is smaller, all of it is returned.
+    pub fn head(&mut self, path: &Path, bytes: u64) -> RemoteResult<Vec<u8>> {
+        self.check_connection()?;
+        let path = self.resolve(path);
+        debug!("Reading first {bytes} bytes of {}", path.display());
+        self.read_bytes("head", &path, bytes)
+    }
+
+    /// Read the last `bytes` bytes of the file at `path` with `tail -c`, without downloading it.
+    ///
+    /// If the file is smaller, all of it is returned.
+    pub fn tail_bytes(&mut self, path: &Path, bytes: u64) -> RemoteResult<Vec<u8>> {
+        self.check_connection()?;
+        let path = self.resolve(path);
+        debug!("Reading last {bytes} bytes of {}", path.display());
+        self.read_bytes("tail", &path, bytes)
+    }
 
-        let size = self.runtime.block_on(async {
+    /// Stream the last `lines` lines of the file at `path` to `on_line`.
+    ///
+    /// If `follow` is set, the lines appended to the file are streamed too, until `cancel` is cancelled.
+    pub fn tail(
+        &mut self,
+        path: &Path,
+        lines: usize,
+        follow: bool,
+        cancel: &CancellationToken,
+        mut on_line: impl FnMut(&str),
+    ) -> RemoteResult<()> {
+        self.check_connection()?;
+        let path = self.resolve(path);
+        if !self.exists_abs(path.as_path())? {
+            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+        }
+        let lines = lines.to_string();
+        let path = path.display().to_string();
+        let mut cmd = vec!["tail", "-n", &lines];
+        if follow {
+            cmd.push("-f");
+        }
+        cmd.push(&path);
+        debug!("Tailing file: {}", cmd.join(" "));
+
+        self.runtime.block_on(async {
             let attach_params = AttachParams::default()
-                .container(self.container.clone())
-                .stdin(true)
-                .stderr(false);
-            let mut cmd = self
+                .stdout(true)
+                .stdin(false)
+                .stderr(false)
+                .container(self.container.clone());
+            let mut process = self
                 .pods
                 .as_ref()
                 .unwrap()
-                .exec(
-                    &self.pod_name,
-                    vec!["tar", "xf", "-", "-C", &dir_path.display().to_string()],
-                    &attach_params,
-                )
+                .exec(&self.pod_name, self.command(cmd), &attach_params)
                 .await
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+                .map_err(|err| {
+                    kube_error(err, RemoteErrorType::ProtocolError, "create pods/exec")
+                })?;
 
-            cmd.stdin()
-                .ok_or_else(|| RemoteError::new(RemoteErrorType::ProtocolError))?
-                .write_all(&data)
-                .await
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+            let status = process.take_status();
+            let stdout = process.stdout().ok_or_else(|| {
+                RemoteError::new_ex(RemoteErrorType::ProtocolError, "failed to read stdout")
+            })?;
+            let mut stdout = tokio::io::BufReader::new(stdout).lines();
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => {
+                        debug!("tail cancelled");
+                        process.abort();
+                        return Ok(());
+                    }
+                    line = stdout.next_line() => match line {
+                        Ok(Some(line)) => on_line(&line),
+                        Ok(None) => break,
+                        Err(err) => {
+                            return Err(RemoteError::new_ex(RemoteErrorType::ProtocolError, err))
+                        }
+                    }
+                }
+            }
 
-            debug!("uploaded archive to kube at: {}", path.display());
+            let status = match status {
+                Some(status) => status.await,
+                None => None,
+            };
+            match status {
+                Some(status) if status.status.as_deref() != Some("Success") => {
+                    Err(RemoteError::new_ex(
+                        RemoteErrorType::ProtocolError,
+                        status.message.unwrap_or_else(|| "tail failed".to_string()),
+                    ))
+                }
+                _ => Ok(()),
+            }
+        })
+    }
 
-            cmd.join()
-                .await
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+    /// Get the node name, phase, image and resources of the pod and container the client is connected to
+    pub fn pod_info(&mut self) -> RemoteResult<PodInfo> {
+        self.check_connection()?;
+        debug!("Getting info of pod {}", self.pod_name);
+        let pod = self
+            .runtime
+            .block_on(self.pods.as_ref().unwrap().get(&self.pod_name))
+            .map_err(|err| kube_error(err, RemoteErrorType::ProtocolError, "get pods"))?;
 
-            Ok(metadata.size)
-        })?;
+        Ok(PodInfo::from_pod(&pod, &self.container))
+    }
 
-        if !self.exists(path.as_path())? {
-            return Err(RemoteError::new_ex(
-                RemoteErrorType::NoSuchFileOrDirectory,
-                "failed to create file",
-            ));
+    /// Get the environment variables of the container, as seen by commands run by the client.
+    ///
+    /// `env -0` is used where supported, so values can contain newlines unambiguously.
+    pub fn env(&mut self) -> RemoteResult<Vec<(String, String)>> {
+        self.check_connection()?;
+        debug!("Getting environment variables");
+        match self.shell_cmd_with_rc("if env -0 >/dev/null 2>&1; then env -0; else env; fi")? {
+            (0, output) => Ok(parser_utils::parse_env(&output)),
+            (rc, _) => Err(RemoteError::new_ex(
+                RemoteErrorType::ProtocolError,
+                format!("env exited with code {rc}"),
+            )),
         }
+    }
 
-        Ok(size)
-    }
-
-    fn open_file(
+    /// Download the file at `src` to `dest`, like `open_file`, but stop the transfer and fail with `IoError`
+    /// once the file turns out to be larger than `max_bytes`.
+    ///
+    /// Nothing is written to `dest` if the file is too large.
+    pub fn open_file_limited(
         &mut self,
         src: &Path,
         mut dest: Box<dyn std::io::Write + Send>,
+        max_bytes: u64,
     ) -> RemoteResult<u64> {
         self.check_connection()?;
+        let src = self.resolve(src);
+        self.with_retries("open_file_limited", || {
+            self.download_file(src.as_path(), dest.as_mut(), Some(max_bytes))
+        })
+    }
 
-        let src = path_utils::absolutize(self.wrkdir.as_path(), src);
-        debug!("opening file from kube at: {}", src.display());
-
-        let tempfile = tempfile::NamedTempFile::new()
-            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?;
+    /// Upload the local file at `local` to `remote`, taking its size and mode from the local filesystem.
+    ///
+    /// Unlike `create_file`, the size can't mismatch the data, so the remote file is never truncated or padded.
+    pub fn upload(&mut self, local: &Path, remote: &Path) -> RemoteResult<u64> {
+        self.check_connection()?;
+        let reader = std::fs::File::open(local)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        let metadata = std::fs::metadata(local)
+            .map(Metadata::from)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        if !metadata.is_file() {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::IoError,
+                format!("\"{}\" is not a file", local.display()),
+            ));
+        }
+        debug!(
+            "Uploading {} to {} ({} bytes)",
+            local.display(),
+            remote.display(),
+            metadata.size
+        );
+        self.create_file(remote, &metadata, Box::new(reader))
+    }
 
-        let file_size = self.runtime.block_on(async {
-            let mut tar_writer = tokio::fs::File::create(tempfile.path())
-                .await
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?;
+    /// Download the file at `remote` to the local path `local`, applying the mode of the remote file to it.
+    ///
+    /// The file is downloaded to a temporary file in the directory of `local`, which replaces `local` once complete;
+    /// so `local` is left as it was if the download fails.
+    pub fn download(&mut self, remote: &Path, local: &Path) -> RemoteResult<u64> {
+        self.check_connection()?;
+        let remote = self.resolve(remote);
+        let file = self.stat(remote.as_path())?;
+        if !file.is_file() {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::CouldNotOpenFile,
+                format!("\"{}\" is not a file", remote.display()),
+            ));
+        }
+        debug!("Downloading {} to {}", remote.display(), local.display());
+        replace_local_file(local, |dest| {
+            let size = self.with_retries("download", || {
+                // start over, if a previous attempt wrote part of the file
+                dest.set_len(0)
+                    .and_then(|()| dest.rewind())
+                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+                self.download_file(remote.as_path(), dest, None)
+            })?;
 
-            let attach_params = AttachParams::default()
-                .container(self.container.clone())
-                .stdout(true)
-                .stderr(true)
-                .stdin(false);
-            let mut cmd = self
-                .pods
-                .as_ref()
-                .unwrap()
-                .exec(
-                    &self.pod_name,
-                    vec![
-                        "tar",
-                        "cf",
-                        "-",
-                        "-C",
-                        src.parent()
-                            .unwrap_or(Path::new("/"))
-                            .display()
-                            .to_string()
-                            .as_str(),
-                        src.file_name()
-                            .unwrap()
-                            .to_string_lossy()
-                            .to_string()
-                            .as_str(),
-                    ],
-                    &attach_params,
-                )
-                .await
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+            #[cfg(target_family = "unix")]
+            if let Some(mode) = file.metadata().mode {
+                use std::os::unix::fs::PermissionsExt as _;
 
-            let mut reader = cmd
-                .stdout()
-                .ok_or_else(|| RemoteError::new(RemoteErrorType::ProtocolError))?;
+                dest.set_permissions(std::fs::Permissions::from_mode(u32::from(mode)))
+                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+            }
 
-            let file_size: u64 = tokio::io::copy(&mut reader, &mut tar_writer)
-                .await
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+            Ok(size)
+        })
+    }
 
-            cmd.join()
-                .await
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+    /// Open the file at `src` for reading, e.g. to pipe it into another process with `io::copy`.
+    ///
+    /// The file is downloaded to a local temporary file first, which is removed once the reader is dropped.
+    pub fn open_reader(&mut self, src: &Path) -> RemoteResult<impl std::io::Read + Send> {
+        self.check_connection()?;
+        let src = self.resolve(src);
+        self.with_retries("open_reader", || self.download_to_tempfile(src.as_path()))
+    }
 
-            debug!(
-                "copied from kube to tar {}; {file_size} bytes",
-                tempfile.path().display()
+    /// Download several files with a single `tar` stream, returning the content of each one, in the order of `srcs`.
+    ///
+    /// Files which don't exist, or which are directories, are left out of the result.
+    /// The contents are held in memory, so it's meant for many small files, such as configs and logs.
+    pub fn open_files(&mut self, srcs: Vec<PathBuf>) -> RemoteResult<Vec<(PathBuf, Vec<u8>)>> {
+        self.check_connection()?;
+        let paths: Vec<PathBuf> = srcs.iter().map(|src| self.resolve(src)).collect();
+        debug!("downloading {} files in a single archive", paths.len());
+
+        // pseudo-files are archived empty, so they're downloaded one by one, as without tar
+        let (archived, mut single): (Vec<PathBuf>, Vec<PathBuf>) = paths
+            .iter()
+            .cloned()
+            .partition(|path| self.tar_flavor().is_some() && !is_pseudo_file(path));
+        let mut contents: HashMap<PathBuf, Vec<u8>> = HashMap::new();
+        if !archived.is_empty() {
+            // entries are stored relative to the root
+            let names: Vec<String> = archived
+                .iter()
+                .map(|path| path.strip_prefix("/").unwrap_or(path).display().to_string())
+                .collect();
+            let names: Vec<&str> = names.iter().map(String::as_str).collect();
+            let archive = self.with_retries("open_files", || {
+                self.download_archive_of(Path::new("/"), &names, None)
+            })?;
+            let tar_reader = std::io::BufReader::with_capacity(
+                self.transfer_block_size,
+                archive
+                    .reopen()
+                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?,
             );
-
-            let tar_reader = std::fs::File::open(tempfile.path())
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?;
-
             let mut ar = tar::Archive::new(tar_reader);
-            let mut file_to_extract = ar
+            for entry in ar
                 .entries()
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?
-                .next()
-                .ok_or(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory))?
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?;
-
-            let file_size = std::io::copy(&mut file_to_extract, &mut dest)
-                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?;
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?
+            {
+                let mut entry =
+                    entry.map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+                let path = Path::new("/").join(
+                    entry
+                        .path()
+                        .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?,
+                );
+                // the content of the directories is archived too
+                if !archived.contains(&path) {
+                    continue;
+                }
+                let entry_type = entry.header().entry_type();
+                if entry_type.is_file() || entry_type.is_gnu_sparse() {
+                    let mut data = Vec::with_capacity(entry.size() as usize);
+                    entry
+                        .read_to_end(&mut data)
+                        .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+                    if may_be_pseudo_file(data.len() as u64) {
+                        single.push(path);
+                    } else {
+                        contents.insert(path, data);
+                    }
+                } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+                    single.push(path);
+                }
+            }
+        }
+        for path in single {
+            let mut data = Vec::new();
+            match self.download_file(path.as_path(), &mut data, None) {
+                Ok(_) => {
+                    contents.insert(path, data);
+                }
+                Err(err) if err.kind == RemoteErrorType::NoSuchFileOrDirectory => {
+                    debug!("{} doesn't exist; skipping", path.display());
+                }
+                Err(err) => return Err(err),
+            }
+        }
 
-            debug!("extracted file to dest; {file_size} bytes");
+        Ok(srcs
+            .into_iter()
+            .zip(paths)
+            .filter_map(|(src, path)| contents.get(&path).map(|data| (src, data.clone())))
+            .collect())
+    }
 
-            Ok(file_size)
-        })?;
+    /// Execute `cmd` in `dir`, returning its exit code and output, without changing the working directory.
+    pub fn exec_in(&mut self, dir: &Path, cmd: &str) -> RemoteResult<(u32, String)> {
+        self.check_connection()?;
+        let dir = self.resolve(dir);
+        debug!(r#"Executing command "{}" in {}"#, cmd, dir.display());
+        self.shell_cmd_at_with_rc(cmd, dir.as_path())
+    }
 
-        Ok(file_size)
+    /// Execute `cmd` in the working directory, returning its exit code and its raw output.
+    ///
+    /// Unlike [`RemoteFs::exec`], which decodes the output as UTF-8 replacing invalid sequences with `U+FFFD`,
+    /// the output is returned byte by byte, so nothing is lost when the command prints binary data.
+    pub fn exec_bytes(&mut self, cmd: &str) -> RemoteResult<(u32, Vec<u8>)> {
+        self.check_connection()?;
+        debug!(r#"Executing command "{}""#, cmd);
+        self.shell_cmd_at_bytes(cmd, self.wrkdir.as_path())
     }
-}
 
-#[cfg(test)]
-mod test {
+    /// Execute `argv` in the working directory, returning its exit code and output.
+    ///
+    /// The arguments are passed to the program as they are, without any shell parsing (quoting, globs, variables).
+    /// Since kube exec can't set the working directory, the program is still started by `/bin/sh`,
+    /// which only changes directory and then replaces itself with the program.
+    pub fn exec_native(&mut self, argv: &[&str]) -> RemoteResult<(u32, String)> {
+        self.check_connection()?;
+        debug!("Executing {:?}", argv);
+        self.runtime
+            .block_on(self.exec_argv(argv_at(&self.wrkdir, argv)))
+            .map(|(rc, stdout)| (rc, decode_output(&stdout)))
+    }
 
-    #[cfg(feature = "integration-tests")]
-    use std::io::Cursor;
+    /// Execute `argv` in the working directory with a pseudo-TTY, returning its exit code and output,
+    /// for programs which behave differently, or hang, when they aren't attached to a terminal.
+    ///
+    /// A TTY has a single output stream, so the output mixes stdout and stderr, which can't be told apart.
+    /// The terminal also ends lines with `\r\n`, which are turned back into `\n`.
+    /// The arguments are passed as with [`Self::exec_native`].
+    pub fn exec_tty(&mut self, argv: &[&str]) -> RemoteResult<(u32, String)> {
+        self.check_connection()?;
+        debug!("Executing {:?} with a TTY", argv);
+        self.runtime
+            .block_on(self.exec_argv_with(argv_at(&self.wrkdir, argv), true))
+            .map(|(rc, output)| (rc, decode_output(&output).replace("\r\n", "\n")))
+    }
 
-    use pretty_assertions::assert_eq;
-    #[cfg(feature = "integration-tests")]
-    use serial_test::serial;
+    /// Execute `argv` directly, without any shell, returning its exit code and output.
+    ///
+    /// Meant for containers without a shell, such as distroless ones: the program is looked up in the `PATH`
+    /// of the container and it runs in the container working directory, not in the client one.
+    /// The exit code is taken from the status of the process.
+    pub fn exec_raw(&mut self, argv: Vec<String>) -> RemoteResult<(u32, String)> {
+        self.check_connection()?;
+        debug!("Executing {:?} without shell", argv);
+        if argv.is_empty() {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::ProtocolError,
+                "empty command",
+            ));
+        }
+        self.runtime
+            .block_on(self.exec_argv(argv))
+            .map(|(rc, stdout)| (rc, decode_output(&stdout)))
+    }
 
-    use super::*;
+    /// Execute `cmd` in the working directory, like `exec`, but fail if it exits with a non-zero code.
+    ///
+    /// Returns the output of the command; on failure, the `ProtocolError` reports the exit code and stderr.
+    pub fn exec_checked(&mut self, cmd: &str) -> RemoteResult<String> {
+        self.check_connection()?;
+        debug!(r#"Executing command "{}""#, cmd);
+        let (rc, stdout, stderr) = self.runtime.block_on(self.shell_cmd_in_with_stderr(
+            &self.pod_name,
+            &self.container,
+            cd_command(&self.wrkdir, cmd),
+        ))?;
+        match rc {
+            0 => Ok(stdout),
+            rc => Err(RemoteError::new_ex(
+                RemoteErrorType::ProtocolError,
+                format!("command exited with code {rc}: {}", stderr.trim()),
+            )),
+        }
+    }
 
-    #[test]
-    fn should_init_kube_fs() {
-        let rt = Arc::new(
-            tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap(),
+    /// Tell whether `path` is on a filesystem mounted read-only, such as a ConfigMap or Secret volume,
+    /// according to `/proc/mounts`.
+    ///
+    /// `path` may not exist yet, in which case the mount of its parent directory is checked.
+    pub fn is_read_only_mount(&mut self, path: &Path) -> RemoteResult<bool> {
+        self.check_connection()?;
+        let path = self.resolve(path);
+        // resolve symlinks, since ConfigMap and Secret volumes are made of them
+        let path = match self.canonicalize(path.as_path()) {
+            Ok(path) => path,
+            Err(err) if err.kind == RemoteErrorType::NoSuchFileOrDirectory => {
+                match (path.parent(), path.file_name()) {
+                    (Some(parent), Some(name)) => self.canonicalize(parent)?.join(name),
+                    _ => return Err(err),
+                }
+            }
+            Err(err) => return Err(err),
+        };
+        debug!(
+            "Checking whether {} is on a read-only mount",
+            path.display()
         );
-        let mut client = KubeContainerFs::new("test", "test", &rt);
-        assert!(client.config.is_none());
-        assert_eq!(client.is_connected(), false);
+
+        let mounts = match self.shell_cmd_with_rc("cat /proc/mounts")? {
+            (0, output) => parser_utils::parse_proc_mounts(&output),
+            (rc, _) => {
+                return Err(RemoteError::new_ex(
+                    RemoteErrorType::ProtocolError,
+                    format!("could not read /proc/mounts (exit code {rc})"),
+                ))
+            }
+        };
+        let read_only = parser_utils::find_mount(&mounts, path.as_path())
+            .map(|mount| mount.read_only)
+            .unwrap_or(false);
+        debug!("{} is read-only: {read_only}", path.display());
+
+        Ok(read_only)
     }
 
-    #[test]
-    fn should_fail_connection_to_bad_server() {
-        let rt = Arc::new(
-            tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap(),
-        );
-        let mut client = KubeContainerFs::new("aaaaaa", "test", &rt);
-        assert!(client.connect().is_err());
+    /// Edit the file at `path` in place: its content is passed to `editor`, whose output is then uploaded atomically.
+    ///
+    /// Symlinks are resolved, so that the target is edited, and the file keeps its permissions.
+    /// Returns the size of the new content.
+    pub fn edit(
+        &mut self,
+        path: &Path,
+        editor: impl FnOnce(&[u8]) -> Vec<u8>,
+    ) -> RemoteResult<u64> {
+        self.check_connection()?;
+        let path = self.canonicalize(path)?;
+        let stat = self.stat(path.as_path())?;
+        if stat.is_dir() {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::BadFile,
+                "cannot edit a directory",
+            ));
+        }
+        debug!("Editing file {}", path.display());
+
+        let mut buffer = tempfile_in(self.temp_dir.as_deref())?;
+        let writer = buffer
+            .try_clone()
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        self.open_file(path.as_path(), Box::new(writer))?;
+        let mut content = Vec::with_capacity(stat.metadata.size as usize);
+        buffer
+            .seek(std::io::SeekFrom::Start(0))
+            .and_then(|_| buffer.read_to_end(&mut content))
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+
+        let content = editor(&content);
+        let metadata = Metadata::default().size(content.len() as u64);
+        let size = self.upload_file_atomic(
+            path.as_path(),
+            &metadata,
+            Box::new(std::io::Cursor::new(content)),
+        )?;
+        if let Some(mode) = stat.metadata.mode {
+            self.setstat(path.as_path(), Metadata::default().mode(mode))?;
+        }
+
+        Ok(size)
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_append_to_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.txt");
-        // Append to file
-        let file_data = "Hello, world!\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        assert!(client
-            .append_file(p, &Metadata::default(), Box::new(reader))
-            .is_err());
-        finalize_client(pods, client);
+    /// Rebuild the kube client, reloading the credentials, while preserving pod, container and working directory.
+    ///
+    /// Kube already refreshes expired tokens from token files and exec credential plugins;
+    /// this is useful when credentials are rotated in a way the current client can't notice.
+    pub fn refresh_auth(&mut self) -> RemoteResult<()> {
+        if self.pods.is_none() {
+            return Err(RemoteError::new(RemoteErrorType::NotConnected));
+        }
+        debug!("Refreshing kube client credentials...");
+        let api = self.runtime.block_on(async {
+            let client = self.build_client().await?;
+            Ok::<Api<Pod>, RemoteError>(Api::default_namespaced(client))
+        })?;
+        self.pods = Some(api);
+        info!("Kube client credentials refreshed");
+        Ok(())
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_change_directory() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        let pwd = client.pwd().ok().unwrap();
-        assert!(client.change_dir(Path::new("/tmp")).is_ok());
-        assert!(client.change_dir(pwd.as_path()).is_ok());
-        finalize_client(pods, client);
+    /// Check whether the client is allowed to exec into the pod, asking the API server with a `SelfSubjectAccessReview`.
+    ///
+    /// Every operation execs into the container, so without the `pods/exec` permission they'd all fail;
+    /// this lets the RBAC gap be detected up front. The reason of a denial is logged.
+    pub fn check_exec_permission(&mut self) -> RemoteResult<bool> {
+        let client = self
+            .pods
+            .as_ref()
+            .ok_or_else(|| RemoteError::new(RemoteErrorType::NotConnected))?
+            .clone()
+            .into_client();
+        let review = exec_access_review(client.default_namespace(), &self.pod_name);
+        debug!(
+            "Checking pods/exec permission on {} in namespace {}",
+            self.pod_name,
+            client.default_namespace()
+        );
+        let review = self.runtime.block_on(self.cancellable(async {
+            Api::<SelfSubjectAccessReview>::all(client)
+                .create(&PostParams::default(), &review)
+                .await
+                .map_err(|err| {
+                    kube_error(
+                        err,
+                        RemoteErrorType::ProtocolError,
+                        "create selfsubjectaccessreviews",
+                    )
+                })
+        }))?;
+        let status = review.status.unwrap_or_default();
+        if !status.allowed {
+            warn!(
+                "pods/exec is not allowed on {}: {}",
+                self.pod_name,
+                status.reason.as_deref().unwrap_or("no reason given")
+            );
+        }
+
+        Ok(status.allowed)
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_change_directory_relative() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        assert!(client
-            .create_dir(
-                Path::new("should_change_directory_relative"),
-                UnixPex::from(0o755)
-            )
-            .is_ok());
-        assert!(client
-            .change_dir(Path::new("should_change_directory_relative/"))
-            .is_ok());
-        finalize_client(pods, client);
+    /// Build the kube client from the configuration, or from the default kubeconfig if not set;
+    /// the shared client, if any, is reused instead.
+    ///
+    /// The same client serves both the REST calls and the websocket upgrades used by `exec`,
+    /// so TLS settings, such as `root_cert`, apply to both.
+    pub(crate) async fn build_client(&self) -> RemoteResult<Client> {
+        if let Some(client) = self.shared_client.as_ref() {
+            debug!("Using the shared kube client");
+            return Ok(client.clone());
+        }
+        let mut config = match self.config.as_ref() {
+            Some(config) => config.clone(),
+            None => self.default_config().await?,
+        };
+        if let Some(timeout) = self.connect_timeout {
+            config.connect_timeout = Some(timeout);
+        }
+        if self.transfer_keepalive.is_some() && config.proxy_url.is_some() {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::UnsupportedFeature,
+                "transfer keepalive can't be used with a proxy",
+            ));
+        }
+        match self.connector_options(&config) {
+            Some(options) => options.build_client(config),
+            None => Client::try_from(config),
+        }
+        .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err))
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_change_directory() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        assert!(client
-            .change_dir(Path::new("/tmp/sdfghjuireghiuergh/useghiyuwegh"))
-            .is_err());
-        finalize_client(pods, client);
+    /// Get the options of the connector of the client built from `config`; `None` to use the default kube one
+    fn connector_options(&self, config: &Config) -> Option<ConnectorOptions> {
+        self.transfer_keepalive.map(|keepalive| ConnectorOptions {
+            keepalive,
+            connect_timeout: config.connect_timeout,
+        })
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_copy_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.txt");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        assert!(client.copy(p, Path::new("b.txt")).is_ok());
-        assert!(client.stat(p).is_ok());
-        assert!(client.stat(Path::new("b.txt")).is_ok());
-        finalize_client(pods, client);
+    /// Load the configuration from the kubeconfig files, if set, or infer it from the environment
+    pub(crate) async fn default_config(&self) -> RemoteResult<Config> {
+        if self.kubeconfig_paths.is_empty() {
+            return Config::infer()
+                .await
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err));
+        }
+        let kubeconfig = Self::read_kubeconfigs(&self.kubeconfig_paths)?;
+        Config::from_custom_kubeconfig(kubeconfig, &KubeConfigOptions::default())
+            .await
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err))
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_copy_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.txt");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        assert!(client.copy(p, Path::new("aaa/bbbb/ccc/b.txt")).is_err());
-        finalize_client(pods, client);
+    /// Run the connect future, bounding it to the connect timeout, if any is set.
+    pub(crate) async fn with_connect_timeout<T, F>(&self, fut: F) -> RemoteResult<T>
+    where
+        F: std::future::Future<Output = RemoteResult<T>>,
+    {
+        let timeout = self
+            .connect_timeout
+            .or_else(|| self.config.as_ref().and_then(|c| c.connect_timeout));
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, fut).await.map_err(|_| {
+                KubeFsError::ConnectTimeout.into_remote(RemoteErrorType::ConnectionError)
+            })?,
+            None => fut.await,
+        }
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_create_directory() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // create directory
-        assert!(client
-            .create_dir(Path::new("mydir"), UnixPex::from(0o755))
-            .is_ok());
-        let p = PathBuf::from(format!("{}/mydir", client.pwd().unwrap().display()));
-        assert!(client.exists(&p).unwrap());
-        finalize_client(pods, client);
+    /// Run `fut`, failing if the cancellation token, if any is set, is cancelled before it completes.
+    pub(crate) async fn cancellable<T, F>(&self, fut: F) -> RemoteResult<T>
+    where
+        F: std::future::Future<Output = RemoteResult<T>>,
+    {
+        let Some(token) = self.cancellation_token.as_ref() else {
+            return fut.await;
+        };
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => {
+                debug!("operation cancelled");
+                Err(KubeFsError::Cancelled.into_remote(RemoteErrorType::ProtocolError))
+            }
+            result = fut => result,
+        }
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_create_directory_cause_already_exists() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // create directory
-        assert!(client
-            .create_dir(Path::new("mydir"), UnixPex::from(0o755))
-            .is_ok());
-        assert_eq!(
-            client
-                .create_dir(Path::new("mydir"), UnixPex::from(0o755))
-                .err()
-                .unwrap()
-                .kind,
-            RemoteErrorType::DirectoryAlreadyExists
-        );
-        finalize_client(pods, client);
+    /// Load the configuration from the kubeconfig files listed in `$KUBECONFIG`, or from `~/.kube/config` if unset.
+    ///
+    /// The files are merged like [`KubeContainerFs::kubeconfig_paths`] does; unlike the default configuration,
+    /// the in-cluster one is never used. The result can be edited and passed to [`KubeContainerFs::config`].
+    pub async fn config_from_env() -> RemoteResult<Config> {
+        Self::config_from_kubeconfig_var(std::env::var_os("KUBECONFIG")).await
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_create_directory() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // create directory
-        assert!(client
-            .create_dir(
-                Path::new("/tmp/werfgjwerughjwurih/iwerjghiwgui"),
-                UnixPex::from(0o755)
-            )
-            .is_err());
-        finalize_client(pods, client);
+    /// Load the configuration from the kubeconfig files in `var`, a list of paths like `$KUBECONFIG`,
+    /// or from `~/.kube/config` if unset or empty
+    pub async fn config_from_kubeconfig_var(
+        var: Option<std::ffi::OsString>,
+    ) -> RemoteResult<Config> {
+        let paths: Vec<PathBuf> = var
+            .map(|var| {
+                std::env::split_paths(&var)
+                    .filter(|path| !path.as_os_str().is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let kubeconfig = if paths.is_empty() {
+            Kubeconfig::read()
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err))?
+        } else {
+            Self::read_kubeconfigs(&paths)?
+        };
+        Config::from_custom_kubeconfig(kubeconfig, &KubeConfigOptions::default())
+            .await
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err))
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_create_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.txt");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert_eq!(
-            client.create_file(p, &metadata, Box::new(reader)).unwrap(),
-            10
-        );
-        // Verify size
-        assert_eq!(client.stat(p).ok().unwrap().metadata().size, 10);
-        finalize_client(pods, client);
-    }
+    // -- private
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_create_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("/tmp/ahsufhauiefhuiashf/hfhfhfhf");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_err());
-        finalize_client(pods, client);
+    /// Read and merge the kubeconfig files at `paths`
+    fn read_kubeconfigs(paths: &[PathBuf]) -> RemoteResult<Kubeconfig> {
+        let mut merged: Option<Kubeconfig> = None;
+        for path in paths {
+            debug!("Loading kubeconfig from {}", path.display());
+            let kubeconfig = Kubeconfig::read_from(path)
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err))?;
+            merged = Some(match merged {
+                Some(merged) => merged
+                    .merge(kubeconfig)
+                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err))?,
+                None => kubeconfig,
+            });
+        }
+
+        merged.ok_or_else(|| {
+            RemoteError::new_ex(RemoteErrorType::ConnectionError, "no kubeconfig file")
+        })
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_exec_command() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        assert_eq!(
-            client.exec("echo 5").ok().unwrap(),
-            (0, String::from("5\n"))
-        );
-        finalize_client(pods, client);
+    /// Get the configuration to edit, loading the default one if not set
+    fn config_mut(&mut self) -> RemoteResult<&mut Config> {
+        if self.config.is_none() {
+            let config = self.runtime.block_on(self.default_config())?;
+            self.config = Some(config);
+        }
+        Ok(self.config.as_mut().unwrap())
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_tell_whether_file_exists() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.txt");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        // Verify size
-        assert_eq!(client.exists(p).ok().unwrap(), true);
-        assert_eq!(client.exists(Path::new("b.txt")).ok().unwrap(), false);
-        assert_eq!(
-            client.exists(Path::new("/tmp/ppppp/bhhrhu")).ok().unwrap(),
-            false
-        );
-        assert_eq!(client.exists(Path::new("/tmp")).ok().unwrap(), true);
-        finalize_client(pods, client);
+    /// Get the working directory from the container
+    fn fetch_wrkdir(&self) -> RemoteResult<PathBuf> {
+        let wrkdir = self.shell_cmd(self.pwd_cmd())?;
+        if !wrkdir.starts_with('/') {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::ConnectionError,
+                format!("bad pwd response: {wrkdir}"),
+            ));
+        }
+        Ok(path_utils::parse_pwd(&wrkdir))
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_list_dir() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let wrkdir = client.pwd().ok().unwrap();
-        let p = Path::new("a.txt");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        // Verify size
-        let file = client
-            .list_dir(wrkdir.as_path())
-            .ok()
-            .unwrap()
-            .get(0)
-            .unwrap()
-            .clone();
-        assert_eq!(file.name().as_str(), "a.txt");
-        let mut expected_path = wrkdir;
-        expected_path.push(p);
-        assert_eq!(file.path.as_path(), expected_path.as_path());
-        assert_eq!(file.extension().as_deref().unwrap(), "txt");
-        assert_eq!(file.metadata.size, 10);
-        finalize_client(pods, client);
+    /// Get the `$HOME` of the container, if set to an absolute path
+    fn fetch_home(&self) -> RemoteResult<Option<PathBuf>> {
+        let home = self.shell_cmd(r#"printf '%s' "$HOME""#)?;
+        debug!("Home directory: {home}");
+        Ok(home.starts_with('/').then(|| path_utils::parse_pwd(&home)))
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_list_dir() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        assert!(client.list_dir(Path::new("/tmp/auhhfh/hfhjfhf/")).is_err());
-        finalize_client(pods, client);
+    /// Get the `pwd` command printing the working directory according to the `physical_paths` option
+    fn pwd_cmd(&self) -> &'static str {
+        if self.physical_paths {
+            "pwd -P"
+        } else {
+            "pwd -L"
+        }
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_move_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.txt");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        // Verify size
-        let dest = Path::new("b.txt");
-        assert!(client.mov(p, dest).is_ok());
-        assert_eq!(client.exists(p).ok().unwrap(), false);
-        assert_eq!(client.exists(dest).ok().unwrap(), true);
-        finalize_client(pods, client);
+    /// Check connection status
+    fn check_connection(&mut self) -> RemoteResult<()> {
+        if self.pod_deleted.load(Ordering::Relaxed) {
+            self.stop_pod_watch();
+            self.pods = None;
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::NotConnected,
+                format!("pod {} has been deleted", self.pod_name),
+            ));
+        }
+        if self.is_connected() {
+            Ok(())
+        } else {
+            Err(RemoteError::new(RemoteErrorType::NotConnected))
+        }
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_move_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.txt");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        // Verify size
-        let dest = Path::new("/tmp/wuefhiwuerfh/whjhh/b.txt");
-        assert!(client.mov(p, dest).is_err());
-        assert!(client
-            .mov(Path::new("/tmp/wuefhiwuerfh/whjhh/b.txt"), p)
-            .is_err());
-        finalize_client(pods, client);
+    /// Start watching the pod with `metadata` for deletion, if `watch_pod` is enabled, replacing the previous watch
+    fn start_pod_watch(&mut self, metadata: ObjectMeta) {
+        self.stop_pod_watch();
+        let Some(api) = self.pods.clone().filter(|_| self.watch_pod) else {
+            return;
+        };
+        debug!("Watching pod {} for deletion", self.pod_name);
+        self.pod_deleted = Arc::new(AtomicBool::new(false));
+        self.pod_watcher = Some(self.runtime.spawn(watch::watch_pod(
+            api,
+            metadata,
+            self.pod_deleted.clone(),
+        )));
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_open_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.txt");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let metadata = Metadata::default().size(file_data.len() as u64);
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        // Verify size
-        let buffer: Box<dyn std::io::Write + Send> = Box::new(Vec::with_capacity(512));
-        assert_eq!(client.open_file(p, buffer).ok().unwrap(), 10);
-        finalize_client(pods, client);
+    /// Stop watching the pod for deletion
+    fn stop_pod_watch(&mut self) {
+        if let Some(watcher) = self.pod_watcher.take() {
+            watcher.abort();
+        }
+        self.pod_deleted = Arc::new(AtomicBool::new(false));
     }
 
-    #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
+    /// Parse a line of `ls -l` output and tokenize the output into a `FsFile`
+    fn parse_ls_output(&self, path: &Path, line: &str) -> Result<File, ()> {
+        // Prepare list regex
+        trace!("Parsing LS line: '{}'", line);
+        // Apply regex to result
+        match LS_RE.captures(line) {
+            // String matches regex
+            Some(metadata) => {
+                // NOTE: metadata fmt: (regex, file_type, permissions, link_count, uid, gid, filesize, modified, filename)
+                // Expected 7 + 1 (8) values: + 1 cause regex is repeated at 0
+                if metadata.len() < 8 {
+                    return Err(());
+                }
+                // Collect metadata
+                // Get if is directory and if is symlink
+                let (is_dir, is_symlink): (bool, bool) = match metadata.get(1).unwrap().as_str() {
+                    "-" => (false, false),
+                    "l" => (false, true),
+                    "d" => (true, false),
+                    // remotefs has no type for FIFOs, sockets and devices; they're reported as files
+                    "p" | "s" | "b" | "c" => (false, false),
+                    _ => return Err(()),
+                };
+                // Check string length (unix pex)
+                if metadata.get(2).unwrap().as_str().len() < 9 {
+                    return Err(());
+                }
+
+                let pex = |range: Range<usize>| {
+                    let mut count: u8 = 0;
+                    for (i, c) in metadata.get(2).unwrap().as_str()[range].chars().enumerate() {
+                        match c {
+                            '-' => {}
+                            _ => {
+                                count += match i {
+                                    0 => 4,
+                                    1 => 2,
+                                    2 => 1,
+                                    _ => 0,
+                                }
+                            }
+                        }
+                    }
+                    count
+                };
+
+                // Get unix pex
+                let mode = UnixPex::new(
+                    UnixPexClass::from(pex(0..3)),
+                    UnixPexClass::from(pex(3..6)),
+                    UnixPexClass::from(pex(6..9)),
+                );
+
+                // Parse modified and convert to SystemTime
+                let modified: SystemTime = match parser_utils::parse_lstime(
+                    metadata.get(7).unwrap().as_str(),
+                    "%b %d %Y",
+                    "%b %d %H:%M",
+                ) {
+                    Ok(t) => t,
+                    Err(_) => SystemTime::UNIX_EPOCH,
+                };
+                // Get uid
+                let uid: Option<u32> = metadata.get(4).unwrap().as_str().parse::<u32>().ok();
+                // Get gid
+                let gid: Option<u32> = metadata.get(5).unwrap().as_str().parse::<u32>().ok();
+                // Get filesize; devices have their major and minor numbers instead
+                let size = match metadata.get(1).unwrap().as_str() {
+                    "b" | "c" => 0,
+                    _ => metadata
+                        .get(6)
+                        .unwrap()
+                        .as_str()
+                        .parse::<u64>()
+                        .unwrap_or(0),
+                };
+                // Get link and name
+                let (file_name, symlink): (String, Option<PathBuf>) = match is_symlink {
+                    true => self.get_name_and_link(metadata.get(8).unwrap().as_str()),
+                    false => (self.ls_name(metadata.get(8).unwrap().as_str()), None),
+                };
+                // Check if file_name is '.' or '..'; some ls flavors print them as './' and '../'
+                if matches!(file_name.trim_end_matches('/'), "." | "..") {
+                    debug!("File name is {}; ignoring entry", file_name);
+                    return Err(());
+                }
+                // Sanitize file name
+                let file_name = PathBuf::from(&file_name)
+                    .file_name()
+                    .map(|x| x.to_string_lossy().to_string())
+                    .unwrap_or(file_name);
+                // Re-check if is directory
+                let mut path: PathBuf = path.to_path_buf();
+                path.push(file_name.as_str());
+                // get file type
+                let file_type = if symlink.is_some() {
+                    FileType::Symlink
+                } else if is_dir {
+                    FileType::Directory
+                } else {
+                    FileType::File
+                };
+                // make metadata
+                let metadata = Metadata {
+                    accessed: None,
+                    created: None,
+                    file_type,
+                    gid,
+                    mode: Some(mode),
+                    modified: Some(modified),
+                    size,
+                    symlink,
+                    uid,
+                };
+                trace!(
+                    "Found entry at {} with metadata {:?}",
+                    path.display(),
+                    metadata
+                );
+                // Push to entries
+                Ok(File { path, metadata })
+            }
+            None => Err(()),
+        }
+    }
+
+    /// Perform shell cmd in `container` of `pod` and return output and return code
+    pub(crate) async fn shell_cmd_in(
+        &self,
+        pod: &str,
+        container: &str,
+        cmd: impl std::fmt::Display,
+    ) -> RemoteResult<(u32, String)> {
+        self.shell_cmd_in_with_stderr(pod, container, cmd)
+            .await
+            .map(|(rc, stdout, _)| (rc, stdout))
+    }
+
+    /// Perform shell cmd in `container` of `pod` and return return code, output and stderr.
+    ///
+    /// The output is decoded as UTF-8 lossily, see [`Self::shell_cmd_in_bytes`]
+    pub(crate) async fn shell_cmd_in_with_stderr(
+        &self,
+        pod: &str,
+        container: &str,
+        cmd: impl std::fmt::Display,
+    ) -> RemoteResult<(u32, String, String)> {
+        self.shell_cmd_in_bytes(pod, container, cmd)
+            .await
+            .map(|(rc, stdout, stderr)| (rc, decode_output(&stdout), stderr))
+    }
+
+    /// Perform shell cmd in `container` of `pod` and return return code, raw output and stderr
+    pub(crate) async fn shell_cmd_in_bytes(
+        &self,
+        pod: &str,
+        container: &str,
+        cmd: impl std::fmt::Display,
+    ) -> RemoteResult<(u32, Vec<u8>, String)> {
+        const STDOUT_SIZE: usize = 2048;
+
+        // the exit code is echoed too, for when the status of the process doesn't report it
+        let marker = exit_code_marker();
+        let shell_cmd = format!(r#"{cmd}; __rc=$?; echo -n "{marker}$__rc"; exit $__rc"#);
+        let op = OpContext::new(pod, container);
+        debug!("{op} Executing shell command: {}", shell_cmd);
+
+        self.cancellable(async {
+            let attach_params = AttachParams::default()
+                .stdout(true)
+                .stdin(false)
+                .stderr(true)
+                .container(container)
+                .max_stdout_buf_size(STDOUT_SIZE);
+
+            let mut process = self
+                .pods
+                .as_ref()
+                .ok_or_else(|| RemoteError::new(RemoteErrorType::NotConnected))?
+                .exec(
+                    pod,
+                    self.command(vec!["/bin/sh", "-c", &shell_cmd]),
+                    &attach_params,
+                )
+                .await
+                .map_err(|err| {
+                    kube_error(err, RemoteErrorType::ProtocolError, "create pods/exec")
+                })?;
+            let status = process.take_status();
+
+            let (stdout, stderr) = transfer::read_output(&mut process, true).await?;
+            debug!("{op} Shell command stderr: {stderr}",);
+            self.check_sudo(&stderr)?;
+
+            process.join().await.map_err(|err| {
+                RemoteError::new_ex(RemoteErrorType::ProtocolError, err.to_string())
+            })?;
+
+            let status = match status {
+                Some(status) => status.await,
+                None => None,
+            };
+            let Some((rc, output)) = shell_exit_code(status.as_ref(), &stdout, &marker) else {
+                // without any exit code, the shell didn't run at all
+                if let Some(message) = transfer::command_not_found(status.as_ref()) {
+                    return Err(KubeFsError::ShellMissing
+                        .into_remote_with(RemoteErrorType::ProtocolError, message));
+                }
+                return Err(RemoteError::new(RemoteErrorType::ProtocolError));
+            };
+
+            debug!("{op} Shell command exit code: {rc}",);
+            debug!("{op} Shell command output: {}", decode_output(output));
+
+            Ok((rc, output.to_vec(), stderr))
+        })
+        .await
+    }
+
+    /// Perform shell cmd at path and return output and return code
+    fn shell_cmd_at_with_rc(
+        &self,
+        cmd: impl std::fmt::Display,
+        path: &Path,
+    ) -> RemoteResult<(u32, String)> {
+        self.runtime.block_on(self.shell_cmd_in(
+            &self.pod_name,
+            &self.container,
+            cd_command(path, cmd),
+        ))
+    }
+
+    /// Perform shell cmd at path and return raw output and return code
+    fn shell_cmd_at_bytes(
+        &self,
+        cmd: impl std::fmt::Display,
+        path: &Path,
+    ) -> RemoteResult<(u32, Vec<u8>)> {
+        self.runtime
+            .block_on(self.shell_cmd_in_bytes(
+                &self.pod_name,
+                &self.container,
+                cd_command(path, cmd),
+            ))
+            .map(|(rc, stdout, _)| (rc, stdout))
+    }
+
+    /// Perform shell cmd and return output and return code
+    fn shell_cmd_with_rc(&self, cmd: impl std::fmt::Display) -> RemoteResult<(u32, String)> {
+        self.shell_cmd_at_with_rc(cmd, &self.wrkdir)
+    }
+
+    /// Perform shell cmd and return output
+    fn shell_cmd(&self, cmd: impl std::fmt::Display) -> RemoteResult<String> {
+        self.shell_cmd_with_rc(cmd).map(|(_, output)| output)
+    }
+
+    /// Run `ls` with the provided flags on the directory at the absolute `path` and return its output
+    fn ls(&self, path: &Path, flags: &str) -> RemoteResult<String> {
+        debug!("Getting file entries in {}", path.display());
+        // check if exists; errors are propagated, so that transient ones can be retried
+        if !self.exists_abs(path)? {
+            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+        }
+        self.shell_cmd(
+            self.ls_cmd(format!("{} \"{}/\"", flags, path.display()))
+                .as_str(),
+        )
+    }
+
+    /// Parse the whole `ls -l` output of the directory at `path` into its entries.
+    ///
+    /// The `total` header and the `.` and `..` entries are skipped; the lines which can't be parsed are skipped too,
+    /// unless `strict_listing` is enabled.
+    fn parse_ls_lines(&self, path: &Path, output: &str) -> RemoteResult<Vec<File>> {
+        // Split output by (\r)\n
+        let lines: Vec<&str> = output.lines().collect();
+        let mut entries: Vec<File> = Vec::with_capacity(lines.len());
+        for line in lines.iter() {
+            // Skip the total blocks header and blank lines; an empty directory has nothing else
+            if line.trim().is_empty() || parser_utils::parse_ls_total(line).is_some() {
+                continue;
+            }
+            // Parse row, if ok push to entries
+            match self.parse_ls_output(path, line) {
+                Ok(entry) => entries.push(entry),
+                Err(()) if self.strict_listing && !self.is_dot_entry(line) => {
+                    return Err(RemoteError::new_ex(
+                        RemoteErrorType::ProtocolError,
+                        format!("unsupported ls output line: {line}"),
+                    ));
+                }
+                Err(()) => {}
+            }
+        }
+        debug!(
+            "Found {} out of {} valid file entries",
+            entries.len(),
+            lines.len()
+        );
+        Ok(entries)
+    }
+
+    /// Parse a line of `ls -ld` output printed for an absolute path
+    #[cfg(feature = "find")]
+    fn parse_find_ls_line(&self, line: &str) -> Option<File> {
+        let name = LS_RE.captures(line)?.get(8)?.as_str();
+        let (path, _) = self.get_name_and_link(name);
+        let parent = Path::new(&path).parent()?;
+        self.parse_ls_output(parent, line).ok()
+    }
+
+    /// Returns whether the `ls -l` output `line` is the `.` or `..` entry
+    fn is_dot_entry(&self, line: &str) -> bool {
+        LS_RE
+            .captures(line)
+            .and_then(|metadata| metadata.get(8))
+            .map(|name| self.ls_name(name.as_str()))
+            .is_some_and(|name| matches!(name.trim_end_matches('/'), "." | ".."))
+    }
+
+    /// Make the shell command to copy or move `src` to `dest` with `cmd` (e.g. `cp -rf`),
+    /// creating the destination parents first, if enabled.
+    ///
+    /// If overwrite is disabled, the command exits with `DEST_EXISTS_RC` when `dest` exists.
+    fn copy_cmd(&self, cmd: &str, src: &Path, dest: &Path) -> String {
+        let mut shell_cmd = String::new();
+        if self.create_parents {
+            if let Some(parent) = dest.parent() {
+                shell_cmd.push_str(&format!("mkdir -p \"{}\" && ", parent.display()));
+            }
+        }
+        let copy = format!("{} \"{}\" \"{}\"", cmd, src.display(), dest.display());
+        if self.overwrite {
+            shell_cmd.push_str(&copy);
+        } else {
+            shell_cmd.push_str(&format!(
+                "if [ -e \"{dest}\" ] || [ -L \"{dest}\" ]; then (exit {DEST_EXISTS_RC}); else {copy}; fi",
+                dest = dest.display(),
+            ));
+        }
+        shell_cmd
+    }
+
+    /// Upload the file read from `reader` to the absolute `path` through `tar`, or `base64` if tar is unavailable
+    fn upload_file(
+        &self,
+        path: &Path,
+        metadata: &Metadata,
+        reader: Box<dyn std::io::Read + Send>,
+    ) -> RemoteResult<u64> {
+        let file_name = path
+            .file_name()
+            .ok_or(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory))?;
+        if self.tar_flavor().is_none() {
+            return self.upload_file_base64(path, metadata, reader);
+        }
+        let tar_path = PathBuf::from(file_name);
+
+        debug!("preparing archive to upload");
+        let data = Self::make_archive(vec![ArchiveEntry {
+            path: tar_path,
+            size: metadata.size,
+            mode: self.file_mode(metadata.mode),
+            reader,
+        }])?;
+        debug!("uploading archive to kube at: {}", path.display());
+
+        let dir_path = path.parent().unwrap_or(Path::new("/"));
+        self.upload_archive(dir_path, &data)?;
+        debug!("uploaded archive to kube at: {}", path.display());
+
+        if !self.exists_abs(path)? {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::NoSuchFileOrDirectory,
+                "failed to create file",
+            ));
+        }
+
+        Ok(metadata.size)
+    }
+
+    /// Upload a file to the absolute `path` through a temporary sibling file, which is then renamed to `path`
+    fn upload_file_atomic(
+        &self,
+        path: &Path,
+        metadata: &Metadata,
+        reader: Box<dyn std::io::Read + Send>,
+    ) -> RemoteResult<u64> {
+        let tmp_name: String = std::iter::repeat_with(fastrand::alphanumeric)
+            .take(12)
+            .collect();
+        let tmp_path = path.with_file_name(format!(".tmp-{tmp_name}"));
+        debug!(
+            "writing {} atomically through {}",
+            path.display(),
+            tmp_path.display()
+        );
+        let res = self
+            .upload_file(tmp_path.as_path(), metadata, reader)
+            .and_then(|size| {
+                match self.shell_cmd_with_rc(format!(
+                    "mv -f {} {}",
+                    path_utils::quote(&tmp_path),
+                    path_utils::quote(path)
+                ))? {
+                    (0, _) => Ok(size),
+                    _ => Err(RemoteError::new_ex(
+                        RemoteErrorType::FileCreateDenied,
+                        format!("\"{}\"", path.display()),
+                    )),
+                }
+            });
+        if res.is_err() {
+            debug!("removing temporary file {}", tmp_path.display());
+            let _ = self.shell_cmd_with_rc(format!("rm -f {}", path_utils::quote(&tmp_path)));
+        }
+        res
+    }
+
+    /// Extract the first entry of the tar archive read from `tar_reader` to `dest`, returning its size.
+    ///
+    /// Exactly the size of the entry is written, so the block padding never ends up in `dest`;
+    /// the holes of sparse entries are written as zeros.
+    fn extract_file(
+        tar_reader: impl std::io::Read,
+        dest: &mut (dyn std::io::Write + Send),
+        max_bytes: Option<u64>,
+    ) -> RemoteResult<u64> {
+        let mut ar = tar::Archive::new(tar_reader);
+        let mut file_to_extract = ar
+            .entries()
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?
+            .next()
+            .ok_or(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory))?
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?;
+        // the size of the file, which is larger than the data in the archive for sparse entries
+        let size = file_to_extract.size();
+        if let Some(max_bytes) = max_bytes {
+            if size > max_bytes {
+                return Err(transfer::size_limit_exceeded(max_bytes));
+            }
+        }
+
+        let file_size = std::io::copy(&mut file_to_extract, dest)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?;
+        if file_size != size {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::IoError,
+                format!("truncated archive: extracted {file_size} of {size} bytes"),
+            ));
+        }
+
+        Ok(file_size)
+    }
+
+    /// Make a tar archive with the provided entries, each one stored at its tar path.
+    ///
+    /// At most `size` bytes are read from each entry, since more data would be taken for the next header.
+    fn make_archive(entries: Vec<ArchiveEntry>) -> RemoteResult<Vec<u8>> {
+        let mut ar = tar::Builder::new(Vec::new());
+        for entry in entries {
+            // prepare write
+            let mut header = tar::Header::new_gnu();
+            header
+                .set_path(entry.path)
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+            header.set_size(entry.size);
+            if let Some(mode) = entry.mode {
+                header.set_mode(u32::from(mode));
+            }
+            header.set_cksum();
+
+            debug!("appending data to archive");
+            ar.append(&header, entry.reader.take(entry.size))
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        }
+
+        ar.into_inner()
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))
+    }
+
+    /// Download the file at the absolute path `src` to `dest`, failing if it's larger than `max_bytes`, if set
+    fn download_file(
+        &self,
+        src: &Path,
+        dest: &mut (dyn std::io::Write + Send),
+        max_bytes: Option<u64>,
+    ) -> RemoteResult<u64> {
+        if self.tar_flavor().is_none() {
+            return self.download_file_base64(src, dest, max_bytes);
+        }
+        if is_pseudo_file(src) {
+            return self.download_file_cat(src, dest, max_bytes);
+        }
+        let file_size = if self.streaming {
+            self.download_file_streaming(src, dest, max_bytes)?
+        } else {
+            let op = OpContext::new(&self.pod_name, &self.container);
+            let archive = self.download_archive(src, max_bytes)?;
+            let tar_reader = std::io::BufReader::with_capacity(
+                self.transfer_block_size,
+                archive.reopen().map_err(|err| {
+                    RemoteError::new_ex(RemoteErrorType::IoError, err.to_string())
+                })?,
+            );
+
+            let file_size = Self::extract_file(tar_reader, dest, max_bytes)?;
+            debug!("{op} extracted file to dest; {file_size} bytes");
+            file_size
+        };
+        if may_be_pseudo_file(file_size) {
+            return self.download_file_cat(src, dest, max_bytes);
+        }
+
+        Ok(file_size)
+    }
+
+    /// Download the file at the absolute path `src` to `dest`, extracting it from the tar archive while it's received,
+    /// failing if it's larger than `max_bytes`, if set.
+    ///
+    /// Once part of the file has been written to `dest`, transient failures are reported as `IoError`,
+    /// so they're not retried.
+    fn download_file_streaming(
+        &self,
+        src: &Path,
+        dest: &mut (dyn std::io::Write + Send),
+        max_bytes: Option<u64>,
+    ) -> RemoteResult<u64> {
+        let op = OpContext::new(&self.pod_name, &self.container);
+        debug!("{op} streaming file from kube at: {}", src.display());
+        // the limit is checked on the size in the entry header, so the archive can be compressed
+        let gzip = self.gzip();
+        let mut entry = tar_stream::EntryWriter::new(dest, max_bytes);
+
+        let result = self.runtime.block_on(self.cancellable(async {
+            let attach_params = AttachParams::default()
+                .container(self.container.clone())
+                .stdout(true)
+                .stderr(true)
+                .stdin(false);
+            let mut cmd = self
+                .pods
+                .as_ref()
+                .unwrap()
+                .exec(
+                    &self.pod_name,
+                    self.command(tar_cmd::create_args(
+                        &self.tar_binary,
+                        &src.parent().unwrap_or(Path::new("/")).display().to_string(),
+                        &[src.file_name().unwrap().to_string_lossy().as_ref()],
+                        gzip,
+                        false,
+                    )),
+                    &attach_params,
+                )
+                .await
+                .map_err(|err| {
+                    kube_error(err, RemoteErrorType::ProtocolError, "create pods/exec")
+                })?;
+
+            let mut reader = tokio::io::BufReader::with_capacity(
+                self.transfer_block_size,
+                cmd.stdout()
+                    .ok_or_else(|| RemoteError::new(RemoteErrorType::ProtocolError))?,
+            );
+            let archive_size = if gzip {
+                let mut decoder = flate2::write::GzDecoder::new(&mut entry);
+                let size = transfer::copy_buf_to_sync(&mut reader, &mut decoder).await?;
+                // nothing is written if sudo asked for a password, which is reported below
+                if size > 0 {
+                    decoder
+                        .try_finish()
+                        .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+                }
+                size
+            } else {
+                transfer::copy_buf_to_sync(&mut reader, &mut entry).await?
+            };
+            self.check_exec_sudo(&mut cmd).await?;
+
+            cmd.join()
+                .await
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+
+            debug!("{op} streamed archive from kube; {archive_size} bytes");
+
+            Ok(())
+        }));
+        if let Err(err) = result {
+            return Err(match max_bytes {
+                Some(max_bytes) if entry.limit_exceeded() => {
+                    transfer::size_limit_exceeded(max_bytes)
+                }
+                // retrying would write the file again after the part already in dest
+                _ if entry.written() > 0 && retry::is_transient(&err) => RemoteError::new_ex(
+                    RemoteErrorType::IoError,
+                    format!(
+                        "download failed after writing {} bytes: {err}",
+                        entry.written()
+                    ),
+                ),
+                _ => err,
+            });
+        }
+        let file_size = entry.finish()?;
+        debug!("{op} extracted file to dest; {file_size} bytes");
+
+        Ok(file_size)
+    }
+
+    /// Download the file at the absolute path `src` to a local temporary file,
+    /// as a tar archive failing if it's larger than `max_bytes`, if set
+    fn download_archive(
+        &self,
+        src: &Path,
+        max_bytes: Option<u64>,
+    ) -> RemoteResult<tempfile::NamedTempFile> {
+        let op = OpContext::new(&self.pod_name, &self.container);
+        debug!("{op} opening file from kube at: {}", src.display());
+        self.download_archive_of(
+            src.parent().unwrap_or(Path::new("/")),
+            &[src.file_name().unwrap().to_string_lossy().as_ref()],
+            max_bytes,
+        )
+    }
+
+    /// Download the files `names` of the directory `dir` to a local temporary file, as a single tar archive
+    /// failing if it's larger than `max_bytes`, if set.
+    ///
+    /// Missing files are left out of the archive.
+    fn download_archive_of(
+        &self,
+        dir: &Path,
+        names: &[&str],
+        max_bytes: Option<u64>,
+    ) -> RemoteResult<tempfile::NamedTempFile> {
+        let op = OpContext::new(&self.pod_name, &self.container);
+
+        let tempfile = named_tempfile_in(self.temp_dir.as_deref())?;
+        // the limit bounds the size of the archive, which isn't predictable once compressed
+        let gzip = max_bytes.is_none() && self.gzip();
+        let sparse = self.sparse && self.tar_flavor() == Some(TarFlavor::Gnu);
+
+        self.runtime.block_on(self.cancellable(async {
+            let mut tar_writer = tokio::fs::File::create(tempfile.path())
+                .await
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.to_string()))?;
+
+            let attach_params = AttachParams::default()
+                .container(self.container.clone())
+                .stdout(true)
+                .stderr(true)
+                .stdin(false);
+            let mut cmd = self
+                .pods
+                .as_ref()
+                .unwrap()
+                .exec(
+                    &self.pod_name,
+                    self.command(tar_cmd::create_args(
+                        &self.tar_binary,
+                        &dir.display().to_string(),
+                        names,
+                        gzip,
+                        sparse,
+                    )),
+                    &attach_params,
+                )
+                .await
+                .map_err(|err| {
+                    kube_error(err, RemoteErrorType::ProtocolError, "create pods/exec")
+                })?;
+
+            let mut reader = tokio::io::BufReader::with_capacity(
+                self.transfer_block_size,
+                cmd.stdout()
+                    .ok_or_else(|| RemoteError::new(RemoteErrorType::ProtocolError))?,
+            );
+
+            let file_size: u64 = match max_bytes {
+                // the archive is larger than the file, because of headers and padding
+                Some(max_bytes) => {
+                    transfer::copy_buf_limited(
+                        &mut reader,
+                        &mut tar_writer,
+                        max_bytes.saturating_add(TAR_OVERHEAD),
+                    )
+                    .await
+                    // report the limit on the file, not on the archive; transfer failures are protocol errors
+                    .map_err(|err| match err.kind {
+                        RemoteErrorType::IoError => transfer::size_limit_exceeded(max_bytes),
+                        _ => err,
+                    })?
+                }
+                None => transfer::copy_buf(&mut reader, &mut tar_writer).await?,
+            };
+            self.check_exec_sudo(&mut cmd).await?;
+
+            cmd.join()
+                .await
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+
+            debug!(
+                "{op} copied from kube to tar {}; {file_size} bytes",
+                tempfile.path().display()
+            );
+
+            Ok(())
+        }))?;
+
+        if !gzip {
+            return Ok(tempfile);
+        }
+        let mut archive = named_tempfile_in(self.temp_dir.as_deref())?;
+        let compressed = tempfile
+            .reopen()
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        let size = transfer::gunzip(compressed, &mut archive)?;
+        debug!("{op} decompressed archive; {size} bytes");
+
+        Ok(archive)
+    }
+
+    /// Download the file at the absolute path `src` to a local temporary file, returning a reader of its content
+    fn download_to_tempfile(&self, src: &Path) -> RemoteResult<std::io::Take<std::fs::File>> {
+        let tar_flavor = self.tar_flavor();
+        if tar_flavor.is_some() && !is_pseudo_file(src) {
+            let reader =
+                Self::entry_reader(self.download_archive(src, None)?, self.temp_dir.as_deref())?;
+            if !may_be_pseudo_file(reader.limit()) {
+                return Ok(reader);
+            }
+        }
+        let mut file = tempfile_in(self.temp_dir.as_deref())?;
+        let size = match tar_flavor {
+            Some(_) => self.download_file_cat(src, &mut file, None)?,
+            None => self.download_file_base64(src, &mut file, None)?,
+        };
+        file.seek(std::io::SeekFrom::Start(0))
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        Ok(file.take(size))
+    }
+
+    /// Get a reader of the data of the first entry of the tar archive `archive`, without the tar framing.
+    ///
+    /// Sparse entries are extracted to a temporary file in `temp_dir`, or in the system temp directory if unset.
+    fn entry_reader(
+        archive: tempfile::NamedTempFile,
+        temp_dir: Option<&Path>,
+    ) -> RemoteResult<std::io::Take<std::fs::File>> {
+        let mut file = archive.into_file();
+        file.seek(std::io::SeekFrom::Start(0))
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        let (offset, size) = {
+            let mut ar = tar::Archive::new(&mut file);
+            let mut entry = ar
+                .entries()
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?
+                .next()
+                .ok_or(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory))?
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+            // the data of sparse entries isn't contiguous in the archive, so it's extracted instead
+            if entry.header().entry_type().is_gnu_sparse() {
+                let mut extracted = tempfile_in(temp_dir)?;
+                let size = std::io::copy(&mut entry, &mut extracted)
+                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+                extracted
+                    .seek(std::io::SeekFrom::Start(0))
+                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+                return Ok(extracted.take(size));
+            }
+            let size = entry
+                .header()
+                .size()
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+            (entry.raw_file_position(), size)
+        };
+        let available = file
+            .metadata()
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?
+            .len()
+            .saturating_sub(offset);
+        if available < size {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::IoError,
+                format!("truncated archive: got {available} of {size} bytes"),
+            ));
+        }
+        file.seek(std::io::SeekFrom::Start(offset))
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        Ok(file.take(size))
+    }
+
+    /// Download the file at the absolute path `src` to `dest` through `base64`, for containers without tar
+    fn download_file_base64(
+        &self,
+        src: &Path,
+        dest: &mut (dyn std::io::Write + Send),
+        max_bytes: Option<u64>,
+    ) -> RemoteResult<u64> {
+        debug!("downloading {} through base64", src.display());
+        if let Some(max_bytes) = max_bytes {
+            if self.stat_abs(src)?.metadata().size > max_bytes {
+                return Err(transfer::size_limit_exceeded(max_bytes));
+            }
+        }
+        let output = match self.shell_cmd_with_rc(format!("base64 {}", path_utils::quote(src)))? {
+            (0, output) => output,
+            (rc, output) => {
+                return Err(RemoteError::new_ex(
+                    RemoteErrorType::NoSuchFileOrDirectory,
+                    format!("base64 exited with code {rc}: {}", output.trim()),
+                ))
+            }
+        };
+        let data = transfer::decode_base64(&output)?;
+        dest.write_all(&data)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+
+        Ok(data.len() as u64)
+    }
+
+    /// Read `bytes` bytes of the file at the absolute `path` with `tool -c`, i.e. `head` or `tail`
+    fn read_bytes(&self, tool: &str, path: &Path, bytes: u64) -> RemoteResult<Vec<u8>> {
+        let (rc, data) = self.runtime.block_on(self.exec_argv(vec![
+            tool.to_string(),
+            "-c".to_string(),
+            bytes.to_string(),
+            path.display().to_string(),
+        ]))?;
+        if rc != 0 {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::NoSuchFileOrDirectory,
+                format!("{tool} exited with code {rc}"),
+            ));
+        }
+        Ok(data)
+    }
+
+    /// Download the file at the absolute path `src` to `dest` through `cat`, for pseudo-files such as `/proc/1/status`:
+    /// they report a size of 0, so tar archives them empty
+    fn download_file_cat(
+        &self,
+        src: &Path,
+        dest: &mut (dyn std::io::Write + Send),
+        max_bytes: Option<u64>,
+    ) -> RemoteResult<u64> {
+        debug!("downloading {} through cat", src.display());
+        let (rc, data) = self
+            .runtime
+            .block_on(self.exec_argv(vec!["cat".to_string(), src.display().to_string()]))?;
+        if rc != 0 {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::NoSuchFileOrDirectory,
+                format!("cat exited with code {rc}"),
+            ));
+        }
+        if let Some(max_bytes) = max_bytes {
+            if data.len() as u64 > max_bytes {
+                return Err(transfer::size_limit_exceeded(max_bytes));
+            }
+        }
+        dest.write_all(&data)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+
+        Ok(data.len() as u64)
+    }
+
+    /// Upload the tar archive `data` and extract it in the absolute `dir_path`
+    fn upload_archive(&self, dir_path: &Path, data: &[u8]) -> RemoteResult<()> {
+        debug!("uploading archive to kube in dir: {}", dir_path.display());
+        let tar_flavor = self.tar_flavor().ok_or_else(tar_unavailable)?;
+        let gzip = self.gzip();
+        let args = tar_cmd::extract_args(
+            &self.tar_binary,
+            tar_flavor,
+            &dir_path.display().to_string(),
+            gzip,
+        );
+        let (rc, stderr) = if gzip {
+            self.write_to_command(args, &transfer::gzip(data)?)?
+        } else {
+            self.write_to_command(args, data)?
+        };
+        transfer::check_extract(rc, &stderr)
+    }
+
+    /// Upload the file read from `reader` to the absolute `path` through `base64`, for containers without tar
+    fn upload_file_base64(
+        &self,
+        path: &Path,
+        metadata: &Metadata,
+        mut reader: Box<dyn std::io::Read + Send>,
+    ) -> RemoteResult<u64> {
+        debug!("uploading {} through base64", path.display());
+        let mut data = Vec::with_capacity(metadata.size as usize);
+        reader
+            .read_to_end(&mut data)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))?;
+        let args = vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            format!("base64 -d > {}", path_utils::quote(path)),
+        ];
+        let (rc, stderr) =
+            self.write_to_command(args, transfer::encode_base64(&data).as_bytes())?;
+        transfer::check_write("base64", rc, &stderr)?;
+
+        if let Some(mode) = self.file_mode(metadata.mode) {
+            self.assert_stat_command(format!(
+                "chmod {:o} \"{}\"",
+                u32::from(mode),
+                path.display()
+            ))?;
+        }
+
+        Ok(data.len() as u64)
+    }
+
+    /// Run the command `args`, writing `data` to its stdin; returns its exit code and stderr
+    fn write_to_command(&self, args: Vec<String>, data: &[u8]) -> RemoteResult<(u32, String)> {
+        let op = OpContext::new(&self.pod_name, &self.container);
+        debug!("{op} writing {} bytes to {}", data.len(), args.join(" "));
+
+        self.runtime.block_on(self.cancellable(async {
+            let attach_params = AttachParams::default()
+                .container(self.container.clone())
+                .stdin(true)
+                .stderr(true);
+            let mut cmd = self
+                .pods
+                .as_ref()
+                .unwrap()
+                .exec(&self.pod_name, self.command(args), &attach_params)
+                .await
+                .map_err(|err| {
+                    kube_error(err, RemoteErrorType::ProtocolError, "create pods/exec")
+                })?;
+
+            let status = cmd.take_status();
+            let mut stdin = cmd
+                .stdin()
+                .ok_or_else(|| RemoteError::new(RemoteErrorType::ProtocolError))?;
+            let written =
+                transfer::write_chunks(&mut stdin, data, self.transfer_block_size).await?;
+            debug!("{op} written {written} bytes to stdin");
+            drop(stdin);
+
+            let mut stderr = String::new();
+            if let Some(mut reader) = cmd.stderr() {
+                reader
+                    .read_to_string(&mut stderr)
+                    .await
+                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+            }
+            let status = match status {
+                Some(status) => status.await,
+                None => None,
+            };
+            cmd.join()
+                .await
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+
+            let rc = transfer::exit_code(status.as_ref())?;
+            debug!("{op} command exited with code {rc}; stderr: {stderr}");
+            self.check_sudo(&stderr)?;
+            Ok((rc, stderr))
+        }))
+    }
+
+    /// Exec `argv` in the current container without any shell; returns its exit code, from the status of the process,
+    /// and its raw output
+    async fn exec_argv(&self, argv: Vec<String>) -> RemoteResult<(u32, Vec<u8>)> {
+        self.exec_argv_with(argv, false).await
+    }
+
+    /// Exec `argv` like `exec_argv`, with a pseudo-TTY if `tty`; the TTY merges stderr into the output
+    async fn exec_argv_with(&self, argv: Vec<String>, tty: bool) -> RemoteResult<(u32, Vec<u8>)> {
+        let op = OpContext::new(&self.pod_name, &self.container);
+        debug!("{op} executing {argv:?}");
+
+        self.cancellable(async {
+            let attach_params = AttachParams::default()
+                .container(self.container.clone())
+                .stdin(false)
+                .stdout(true)
+                .stderr(!tty)
+                .tty(tty);
+            let mut process = self
+                .pods
+                .as_ref()
+                .ok_or_else(|| RemoteError::new(RemoteErrorType::NotConnected))?
+                .exec(&self.pod_name, self.command(argv), &attach_params)
+                .await
+                .map_err(|err| {
+                    kube_error(err, RemoteErrorType::ProtocolError, "create pods/exec")
+                })?;
+            let status = process.take_status();
+
+            let (stdout, stderr) = transfer::read_output(&mut process, !tty).await?;
+            let status = match status {
+                Some(status) => status.await,
+                None => None,
+            };
+            process
+                .join()
+                .await
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+            self.check_sudo(&stderr)?;
+
+            // the program couldn't be started at all
+            if let Some(message) = transfer::command_not_found(status.as_ref()) {
+                return Err(RemoteError::new_ex(
+                    RemoteErrorType::ProtocolError,
+                    message.to_string(),
+                ));
+            }
+            let rc = transfer::exit_code(status.as_ref())?;
+            debug!("{op} command exited with code {rc}; stderr: {stderr}");
+
+            Ok((rc, stdout))
+        })
+        .await
+    }
+
+    /// Copy the file or directory at the absolute `src`, in the current container, to the absolute `dest`
+    /// in `container` of `pod`, streaming `tar cf -` into `tar xf -` without storing the data locally.
+    ///
+    /// If `dest` is a directory, `src` is copied into it. Returns the size of the streamed archive.
+    pub(crate) fn copy_to_container(
+        &self,
+        src: &Path,
+        pod: &str,
+        container: &str,
+        dest: &Path,
+    ) -> RemoteResult<u64> {
+        let src_name = src.file_name().ok_or_else(|| {
+            RemoteError::new_ex(
+                RemoteErrorType::CouldNotOpenFile,
+                format!("cannot copy \"{}\"", src.display()),
+            )
+        })?;
+        if !self.exists_abs(src)? {
+            return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+        }
+
+        let test_dest = |flag: &str, path: &Path| {
+            self.runtime
+                .block_on(self.shell_cmd_in(
+                    pod,
+                    container,
+                    format!("test {flag} {}", path_utils::quote(path)),
+                ))
+                .map(|(rc, _)| rc == 0)
+        };
+        // like `cp`, copy into `dest` if it's a directory
+        let (dest_dir, dest) = if test_dest("-d", dest)? {
+            (dest.to_path_buf(), dest.join(src_name))
+        } else {
+            let dest_dir = dest.parent().unwrap_or(Path::new("/")).to_path_buf();
+            (dest_dir, dest.to_path_buf())
+        };
+        if !self.overwrite && test_dest("-e", &dest)? {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::FileCreateDenied,
+                format!("\"{}\" already exists", dest.display()),
+            ));
+        }
+        debug!(
+            "Copying {} to {} in {pod}/{container}",
+            src.display(),
+            dest.display()
+        );
+
+        if dest.file_name() == Some(src_name) {
+            return self.stream_archive(src, pod, container, &dest_dir);
+        }
+        // tar keeps the source name, so extract in a temporary directory and rename the entry
+        let tmp_name: String = std::iter::repeat_with(fastrand::alphanumeric)
+            .take(12)
+            .collect();
+        let tmp_dir = dest_dir.join(format!(".tmp-{tmp_name}"));
+        let shell = |cmd: String| {
+            self.runtime
+                .block_on(self.shell_cmd_in(pod, container, cmd))
+        };
+        match shell(format!("mkdir {}", path_utils::quote(&tmp_dir)))? {
+            (0, _) => {}
+            _ => {
+                return Err(RemoteError::new_ex(
+                    RemoteErrorType::FileCreateDenied,
+                    format!("\"{}\"", tmp_dir.display()),
+                ))
+            }
+        }
+        let res = self
+            .stream_archive(src, pod, container, &tmp_dir)
+            .and_then(|size| {
+                match shell(format!(
+                    "mv -f {} {}",
+                    path_utils::quote(&tmp_dir.join(src_name)),
+                    path_utils::quote(&dest)
+                ))? {
+                    (0, _) => Ok(size),
+                    _ => Err(RemoteError::new_ex(
+                        RemoteErrorType::FileCreateDenied,
+                        format!("\"{}\"", dest.display()),
+                    )),
+                }
+            });
+        debug!("removing temporary directory {}", tmp_dir.display());
+        let _ = shell(format!("rm -rf {}", path_utils::quote(&tmp_dir)));
+        res
+    }
+
+    /// Stream the archive of the absolute `src`, in the current container, to `tar xf -` in `dir` of `container` of `pod`,
+    /// run without sudo
+    fn stream_archive(
+        &self,
+        src: &Path,
+        pod: &str,
+        container: &str,
+        dir: &Path,
+    ) -> RemoteResult<u64> {
+        let op = OpContext::new(&self.pod_name, &self.container);
+        let pods = self
+            .pods
+            .as_ref()
+            .ok_or_else(|| RemoteError::new(RemoteErrorType::NotConnected))?;
+        // the tar binary, sudo and the flavor of tar are configured and detected for the current container only,
+        // so the destination runs a plain `tar` with the basic flags
+        let extract_args =
+            tar_cmd::extract_args("tar", TarFlavor::Busybox, &dir.display().to_string(), false);
+        let create_args = tar_cmd::create_args(
+            &self.tar_binary,
+            &src.parent().unwrap_or(Path::new("/")).display().to_string(),
+            &[src
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .as_ref()],
+            false,
+            false,
+        );
+
+        self.runtime.block_on(self.cancellable(async {
+            let mut source = pods
+                .exec(
+                    &self.pod_name,
+                    self.command(create_args),
+                    &AttachParams::default()
+                        .container(self.container.clone())
+                        .stdout(true)
+                        .stderr(true)
+                        .stdin(false),
+                )
+                .await
+                .map_err(|err| {
+                    kube_error(err, RemoteErrorType::ProtocolError, "create pods/exec")
+                })?;
+            let mut target = pods
+                .exec(
+                    pod,
+                    extract_args,
+                    &AttachParams::default()
+                        .container(container)
+                        .stdin(true)
+                        .stderr(true),
+                )
+                .await
+                .map_err(|err| {
+                    kube_error(err, RemoteErrorType::ProtocolError, "create pods/exec")
+                })?;
+            let source_status = source.take_status();
+            let status = target.take_status();
+
+            let mut reader = tokio::io::BufReader::with_capacity(
+                self.transfer_block_size,
+                source
+                    .stdout()
+                    .ok_or_else(|| RemoteError::new(RemoteErrorType::ProtocolError))?,
+            );
+            let mut stdin = target
+                .stdin()
+                .ok_or_else(|| RemoteError::new(RemoteErrorType::ProtocolError))?;
+            // stderr is read along with the archive, so that tar doesn't block on it when full
+            let (size, source_stderr) = tokio::try_join!(
+                async { Ok(transfer::copy_buf(&mut reader, &mut stdin).await?) },
+                self.check_exec_sudo(&mut source)
+            )?;
+            drop(stdin);
+            debug!("{op} streamed {size} bytes to {pod}/{container}");
+
+            let mut stderr = String::new();
+            if let Some(mut stderr_reader) = target.stderr() {
+                stderr = transfer::read_to_string_lossy(&mut stderr_reader)
+                    .await
+                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+            }
+            let source_status = match source_status {
+                Some(status) => status.await,
+                None => None,
+            };
+            let status = match status {
+                Some(status) => status.await,
+                None => None,
+            };
+            source
+                .join()
+                .await
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+            target
+                .join()
+                .await
+                .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+
+            let source_rc = transfer::exit_code(source_status.as_ref())?;
+            debug!("{op} archive exited with code {source_rc}; stderr: {source_stderr}");
+            if source_rc != 0 {
+                return Err(RemoteError::new_ex(
+                    RemoteErrorType::NoSuchFileOrDirectory,
+                    format!("tar exited with code {source_rc}: {}", source_stderr.trim()),
+                ));
+            }
+            let rc = transfer::exit_code(status.as_ref())?;
+            debug!("{op} extraction in {pod}/{container} exited with code {rc}; stderr: {stderr}");
+            transfer::check_extract(rc, &stderr)?;
+            Ok(size)
+        }))
+    }
+
+    /// Prefix `cmd` with the sudo command, if `sudo` is enabled
+    fn command<S: Into<String>>(&self, cmd: Vec<S>) -> Vec<String> {
+        sudo::prefix_command(self.sudo.then_some(self.sudo_binary.as_str()), cmd)
+    }
+
+    /// Get the flavor of the tar binary, detecting it on the first call; `None` if tar is unavailable
+    fn tar_flavor(&self) -> Option<TarFlavor> {
+        if let Some(flavor) = self.tar_flavor.get() {
+            return *flavor;
+        }
+        match self.shell_cmd_with_rc(format!("{} --version 2>&1", self.tar_binary)) {
+            Ok((rc, output)) => {
+                // 126 and 127 are the exit codes of the shell for not executable and not found commands
+                let flavor = match rc {
+                    126 | 127 => None,
+                    _ => Some(TarFlavor::from_version(&output)),
+                };
+                debug!("Detected tar flavor: {flavor:?}");
+                *self.tar_flavor.get_or_init(|| flavor)
+            }
+            Err(err) => {
+                debug!("Could not detect tar flavor: {err}; using basic flags");
+                Some(TarFlavor::Busybox)
+            }
+        }
+    }
+
+    /// Whether `ls` supports `--quoting-style=literal`, which is checked on the first call
+    fn ls_literal(&self) -> bool {
+        if let Some(literal) = self.ls_literal.get() {
+            return *literal;
+        }
+        match self.shell_cmd_with_rc("ls --quoting-style=literal -d / > /dev/null 2>&1") {
+            Ok((rc, _)) => {
+                debug!("ls supports literal quoting style: {}", rc == 0);
+                *self.ls_literal.get_or_init(|| rc == 0)
+            }
+            // not cached, so that the check is repeated once the container is reachable
+            Err(_) => false,
+        }
+    }
+
+    /// Make the `ls` command with `args`, printing names literally if supported
+    fn ls_cmd(&self, args: impl std::fmt::Display) -> String {
+        list::ls_cmd(self.ls_literal(), args)
+    }
+
+    /// Get the file name printed by `ls`, which is unquoted only if `ls` may have used a shell quoting style,
+    /// since names are printed as they are with the literal style; e.g. a file may be named `'a b'`.
+    fn ls_name(&self, name: &str) -> String {
+        match self.ls_literal.get() {
+            Some(false) => parser_utils::unquote_ls_name(name),
+            _ => name.to_string(),
+        }
+    }
+
+    /// Whether transfers should be compressed: compression is enabled and gzip is in the container,
+    /// which is checked on the first call
+    fn gzip(&self) -> bool {
+        if !self.compress {
+            return false;
+        }
+        *self.gzip_available.get_or_init(|| {
+            let available = matches!(
+                self.shell_cmd_with_rc("command -v gzip > /dev/null 2>&1"),
+                Ok((0, _))
+            );
+            debug!("gzip available: {available}");
+            available
+        })
+    }
+
+    /// Get the mode of a file created with `mode`, or with the default file mode if unset,
+    /// masked by the umask of the container if `respect_umask`
+    fn file_mode(&self, mode: Option<UnixPex>) -> Option<UnixPex> {
+        let mode = mode.or(self.default_file_mode);
+        if !self.respect_umask {
+            return mode;
+        }
+        Some(self.apply_umask(mode.unwrap_or(UnixPex::from(0o666))))
+    }
+
+    /// Get the mode of a directory created with `mode`, masked by the umask of the container if `respect_umask`
+    fn dir_mode(&self, mode: UnixPex) -> UnixPex {
+        if self.respect_umask {
+            self.apply_umask(mode)
+        } else {
+            mode
+        }
+    }
+
+    /// Mask `mode` with the umask of the container, which is read on the first call
+    fn apply_umask(&self, mode: UnixPex) -> UnixPex {
+        let umask = *self.umask.get_or_init(|| {
+            let umask = match self.shell_cmd_with_rc("umask") {
+                Ok((0, output)) => u32::from_str_radix(output.trim(), 8).ok(),
+                _ => None,
+            };
+            debug!("container umask: {umask:?}");
+            // modes are left untouched if it can't be read
+            umask.unwrap_or(0)
+        });
+        UnixPex::from(u32::from(mode) & !umask)
+    }
+
+    /// Read the stderr of `cmd` to the end, failing with `AuthenticationFailed` if sudo asked for a password.
+    ///
+    /// With sudo, an empty archive may just be sudo asking for a password, so this is checked after reading one.
+    async fn check_exec_sudo(&self, cmd: &mut AttachedProcess) -> RemoteResult<String> {
+        let Some(mut stderr_reader) = cmd.stderr() else {
+            return Ok(String::new());
+        };
+        let stderr = transfer::read_to_string_lossy(&mut stderr_reader)
+            .await
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+        self.check_sudo(&stderr)?;
+
+        Ok(stderr)
+    }
+
+    /// Fail with `AuthenticationFailed` if `sudo` is enabled and `stderr` reports that it asked for a password
+    fn check_sudo(&self, stderr: &str) -> RemoteResult<()> {
+        if self.sudo && sudo::is_password_required(stderr) {
+            return Err(sudo::password_required(&self.sudo_binary));
+        }
+        Ok(())
+    }
+
+    /// Get the file at the absolute `path`
+    fn stat_abs(&self, path: &Path) -> RemoteResult<File> {
+        debug!("Stat {}", path.display());
+        // `-d` lists the entry itself for both files and directories, so no `is_directory` round-trip is needed
+        let cmd = self.ls_cmd(format!("-ld \"{}\"", path.display()));
+        let line = self.shell_cmd(cmd.as_str())?;
+        // Parse ls line
+        let parent: PathBuf = match path.parent() {
+            Some(p) => PathBuf::from(p),
+            None => {
+                return Err(RemoteError::new_ex(
+                    RemoteErrorType::StatFailed,
+                    "Path has no parent",
+                ))
+            }
+        };
+        match self.parse_ls_output(parent.as_path(), line.as_str().trim()) {
+            Ok(entry) => Ok(entry),
+            Err(_) => Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory)),
+        }
+    }
+
+    /// Run the operation `name`, reporting its duration and outcome to the metrics sink, if any
+    fn metered<T>(
+        &mut self,
+        name: &str,
+        op: impl FnOnce(&mut Self) -> RemoteResult<T>,
+    ) -> RemoteResult<T> {
+        // operations run by other operations (e.g. `exists` by `setstat`) are part of them
+        if self.metrics.is_none() || self.metering {
+            return op(self);
+        }
+        self.metering = true;
+        let started = std::time::Instant::now();
+        let result = op(self);
+        self.metering = false;
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.on_operation(name, started.elapsed(), result.is_ok());
+        }
+        result
+    }
+
+    /// Run `op` until it succeeds, fails with a permanent error or has been retried `op_retries` times
+    fn with_retries<T>(
+        &self,
+        name: &str,
+        mut op: impl FnMut() -> RemoteResult<T>,
+    ) -> RemoteResult<T> {
+        let mut attempt = 0;
+        loop {
+            match op() {
+                Err(err) if attempt < self.op_retries && retry::is_transient(&err) => {
+                    let delay = retry::backoff(attempt);
+                    debug!("{name} failed with transient error: {err}; retrying in {delay:?}");
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns whether the absolute `path` exists
+    fn exists_abs(&self, path: &Path) -> RemoteResult<bool> {
+        match self.shell_cmd_with_rc(format!("test -e \"{}\"", path.display()))? {
+            (0, _) => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Returns from a `ls -l` command output file name token, the name of the file and the symbolic link (if there is any)
+    fn get_name_and_link(&self, token: &str) -> (String, Option<PathBuf>) {
+        let tokens: Vec<&str> = token.split(" -> ").collect();
+        let filename: String = self.ls_name(tokens.first().unwrap());
+        let symlink: Option<PathBuf> = tokens.get(1).map(|link| PathBuf::from(self.ls_name(link)));
+        (filename, symlink)
+    }
+
+    /// Execute setstat command and assert result is 0
+    fn assert_stat_command(&self, cmd: String) -> RemoteResult<()> {
+        match self.shell_cmd_with_rc(cmd)? {
+            (0, _) => Ok(()),
+            _ => Err(RemoteError::new(RemoteErrorType::StatFailed)),
+        }
+    }
+
+    /// Classify the error printed by a failed `cd` into `dir`
+    fn cd_error(dir: &Path, output: &str) -> RemoteError {
+        if output.contains("Not a directory") {
+            KubeFsError::NotADirectory
+                .into_remote_with(RemoteErrorType::BadFile, format!("\"{}\"", dir.display()))
+        } else {
+            RemoteError::new_ex(
+                RemoteErrorType::NoSuchFileOrDirectory,
+                format!("\"{}\"", dir.display()),
+            )
+        }
+    }
+
+    /// Resolve the target of a recursive operation, refusing to run it on `/`
+    fn recursive_target(&mut self, path: &Path) -> RemoteResult<PathBuf> {
+        // resolve `..` and symlinks, which could point to the root too
+        let path = self.canonicalize(path)?;
+        if path == Path::new("/") {
+            return Err(RemoteError::new_ex(
+                RemoteErrorType::BadFile,
+                "refusing to run a recursive operation on /",
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Resolve the target of a recursive operation which doesn't follow symlinks, refusing to run it on `/`.
+    ///
+    /// Only the parent directory is canonicalized, so that a symlink given as path is kept, not replaced by its target.
+    fn recursive_link_target(&mut self, path: &Path) -> RemoteResult<PathBuf> {
+        let path = self.resolve(path);
+        match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => Ok(self.canonicalize(parent)?.join(name)),
+            // `/`, or a path ending with `..`, which isn't a symlink
+            _ => self.recursive_target(&path),
+        }
+    }
+
+    /// Collect all the entries under the absolute `root`, keyed by their path relative to it.
+    ///
+    /// The entries are listed with `stat`, which reports exact mtimes, unlike `ls`.
+    fn walk(&mut self, root: &Path) -> RemoteResult<HashMap<PathBuf, Metadata>> {
+        let (output, _) = self.find_output(sync::stat_tree_cmd(root))?;
+
+        Ok(sync::parse_stat_tree(root, &output))
+    }
+
+    /// Returns whether file at `path` is a directory
+    fn is_directory(&mut self, path: &Path) -> RemoteResult<bool> {
+        let path = self.resolve(path);
+        match self.shell_cmd_with_rc(format!("test -d \"{}\"", path.display()))? {
+            (0, _) => Ok(true),
+            _ => Ok(false),
+        }
+    }
+}
+
+impl Drop for KubeContainerFs {
+    fn drop(&mut self) {
+        self.stop_pod_watch();
+    }
+}
+
+impl RemoteFs for KubeContainerFs {
+    fn connect(&mut self) -> RemoteResult<Welcome> {
+        self.metered("connect", |fs| {
+            debug!("Initializing Kube connection...");
+            let (api, pod) = fs.runtime.block_on(fs.with_connect_timeout(async {
+                let client = fs.build_client().await?;
+                let api: Api<Pod> = Api::default_namespaced(client);
+
+                match api.get(&fs.pod_name).await {
+                    Ok(pod) => Ok((api, pod)),
+                    Err(err) => Err(kube_error(
+                        err,
+                        RemoteErrorType::ConnectionError,
+                        "get pods",
+                    )),
+                }
+            }))?;
+
+            debug!("Connection established with pod {}", fs.pod_name);
+            // Set pods
+            fs.pods = Some(api);
+            fs.start_pod_watch(pod.metadata);
+            debug!("Getting working directory...");
+            // Get working directory
+            fs.wrkdir = fs.fetch_wrkdir()?;
+            fs.home = fs.fetch_home()?;
+            info!(
+                "Connection established; working directory: {}",
+                fs.wrkdir.display()
+            );
+            Ok(Welcome::default())
+        })
+    }
+
+    fn disconnect(&mut self) -> RemoteResult<()> {
+        self.metered("disconnect", |fs| {
+            if fs.pods.is_none() {
+                return Err(RemoteError::new(RemoteErrorType::NotConnected));
+            }
+
+            debug!("Disconnecting from remote...");
+            fs.stop_pod_watch();
+            fs.pods = None;
+
+            info!("Disconnected from remote");
+            Ok(())
+        })
+    }
+
+    fn is_connected(&mut self) -> bool {
+        if let Some(pods) = self.pods.as_ref() {
+            self.runtime
+                .block_on(async { pods.get_status(&self.pod_name).await.is_ok() })
+        } else {
+            false
+        }
+    }
+
+    fn pwd(&mut self) -> RemoteResult<PathBuf> {
+        self.metered("pwd", |fs| {
+            fs.check_connection()?;
+            Ok(fs.wrkdir.clone())
+        })
+    }
+
+    fn change_dir(&mut self, dir: &Path) -> RemoteResult<PathBuf> {
+        self.metered("change_dir", |fs| {
+            fs.check_connection()?;
+            let dir = fs.resolve(dir);
+            debug!("Changing working directory to {}", dir.display());
+            match fs.shell_cmd_with_rc(format!(
+                "cd \"{}\" 2>&1 && {}",
+                dir.display(),
+                fs.pwd_cmd()
+            ))? {
+                (0, output) => {
+                    // Set working directory
+                    fs.wrkdir = path_utils::parse_pwd(&output);
+                    debug!("Changed working directory to {}", fs.wrkdir.display());
+                    Ok(fs.wrkdir.clone())
+                }
+                (_, output) => Err(Self::cd_error(dir.as_path(), output.as_str())),
+            }
+        })
+    }
+
+    fn list_dir(&mut self, path: &Path) -> RemoteResult<Vec<File>> {
+        self.metered("list_dir", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            fs.with_retries("list_dir", || {
+                let output = fs.ls(path.as_path(), "-la")?;
+                fs.parse_ls_lines(path.as_path(), output.as_str())
+            })
+        })
+    }
+
+    fn stat(&mut self, path: &Path) -> RemoteResult<File> {
+        self.metered("stat", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            fs.with_retries("stat", || fs.stat_abs(path.as_path()))
+        })
+    }
+
+    fn exists(&mut self, path: &Path) -> RemoteResult<bool> {
+        self.metered("exists", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            fs.with_retries("exists", || fs.exists_abs(path.as_path()))
+        })
+    }
+
+    fn setstat(&mut self, path: &Path, metadata: Metadata) -> RemoteResult<()> {
+        self.metered("setstat", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            debug!("Setting attributes for {}", path.display());
+            if !fs.exists(path.as_path()).ok().unwrap_or(false) {
+                return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+            }
+            // set mode with chmod
+            if let Some(mode) = metadata.mode {
+                fs.assert_stat_command(format!(
+                    "chmod {:o} \"{}\"",
+                    u32::from(mode),
+                    path.display()
+                ))?;
+            }
+            if let Some(user) = metadata.uid {
+                fs.assert_stat_command(format!(
+                    "chown {}{} \"{}\"",
+                    user,
+                    metadata.gid.map(|x| format!(":{x}")).unwrap_or_default(),
+                    path.display()
+                ))?;
+            }
+            // set times
+            if let Some(accessed) = metadata.accessed {
+                fs.assert_stat_command(format!(
+                    "touch -a -t {} \"{}\"",
+                    fmt_utils::fmt_time_utc(accessed, "%Y%m%d%H%M.%S"),
+                    path.display()
+                ))?;
+            }
+            if let Some(modified) = metadata.modified {
+                fs.assert_stat_command(format!(
+                    "touch -m -t {} \"{}\"",
+                    fmt_utils::fmt_time_utc(modified, "%Y%m%d%H%M.%S"),
+                    path.display()
+                ))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_file(&mut self, path: &Path) -> RemoteResult<()> {
+        self.metered("remove_file", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            if !fs.exists(path.as_path()).ok().unwrap_or(false) {
+                return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+            }
+            debug!("Removing file {}", path.display());
+            match fs.shell_cmd_with_rc(format!("rm -f \"{}\"", path.display()))? {
+                (0, _) => Ok(()),
+                _ => Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
+            }
+        })
+    }
+
+    fn remove_dir(&mut self, path: &Path) -> RemoteResult<()> {
+        self.metered("remove_dir", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            if !fs.exists(path.as_path()).ok().unwrap_or(false) {
+                return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+            }
+            debug!("Removing directory {}", path.display());
+            match fs.shell_cmd_with_rc(format!("rmdir \"{}\"", path.display()))? {
+                (0, _) => Ok(()),
+                _ => Err(RemoteError::new(RemoteErrorType::DirectoryNotEmpty)),
+            }
+        })
+    }
+
+    fn remove_dir_all(&mut self, path: &Path) -> RemoteResult<()> {
+        self.metered("remove_dir_all", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            if !fs.exists(path.as_path()).ok().unwrap_or(false) {
+                return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+            }
+            debug!("Removing directory {} recursively", path.display());
+            match fs.shell_cmd_with_rc(format!("rm -rf \"{}\"", path.display()))? {
+                (0, _) => Ok(()),
+                _ => Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
+            }
+        })
+    }
+
+    fn create_dir(&mut self, path: &Path, mode: UnixPex) -> RemoteResult<()> {
+        self.metered("create_dir", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            if fs.exists(path.as_path()).ok().unwrap_or(false) {
+                return Err(RemoteError::new(RemoteErrorType::DirectoryAlreadyExists));
+            }
+            let mode = format!("{:o}", u32::from(fs.dir_mode(mode)));
+            debug!(
+                "Creating directory at {} with mode {}",
+                path.display(),
+                mode
+            );
+            match fs.shell_cmd_with_rc(format!("mkdir -m {} \"{}\"", mode, path.display()))? {
+                (0, _) => Ok(()),
+                _ => Err(RemoteError::new(RemoteErrorType::FileCreateDenied)),
+            }
+        })
+    }
+
+    fn symlink(&mut self, path: &Path, target: &Path) -> RemoteResult<()> {
+        self.metered("symlink", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            debug!(
+                "Creating a symlink at {} pointing at {}",
+                path.display(),
+                target.display()
+            );
+            if !fs.exists(target).ok().unwrap_or(false) {
+                return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+            }
+            if fs.exists(path.as_path()).ok().unwrap_or(false) {
+                return Err(RemoteError::new(RemoteErrorType::FileCreateDenied));
+            }
+            match fs.shell_cmd_with_rc(format!(
+                "ln -s \"{}\" \"{}\"",
+                target.display(),
+                path.display()
+            ))? {
+                (0, _) => Ok(()),
+                _ => Err(RemoteError::new(RemoteErrorType::FileCreateDenied)),
+            }
+        })
+    }
+
+    fn copy(&mut self, src: &Path, dest: &Path) -> RemoteResult<()> {
+        self.metered("copy", |fs| {
+            fs.check_connection()?;
+            let src = fs.resolve(src);
+            // check if file exists
+            if !fs.exists(src.as_path()).ok().unwrap_or(false) {
+                return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+            }
+            let dest = fs.resolve(dest);
+            debug!("Copying {} to {}", src.display(), dest.display());
+            match fs.shell_cmd_with_rc(fs.copy_cmd("cp -rf", &src, &dest)) {
+                Ok((0, _)) => Ok(()),
+                Ok((DEST_EXISTS_RC, _)) if !fs.overwrite => Err(RemoteError::new_ex(
+                    RemoteErrorType::FileCreateDenied,
+                    format!("\"{}\" already exists", dest.display()),
+                )),
+                Ok(_) => Err(RemoteError::new_ex(
+                    // Could not copy file
+                    RemoteErrorType::FileCreateDenied,
+                    format!("\"{}\"", dest.display()),
+                )),
+                Err(err) => Err(RemoteError::new_ex(
+                    RemoteErrorType::ProtocolError,
+                    err.to_string(),
+                )),
+            }
+        })
+    }
+
+    fn mov(&mut self, src: &Path, dest: &Path) -> RemoteResult<()> {
+        self.metered("mov", |fs| {
+            fs.check_connection()?;
+            let src = fs.resolve(src);
+            // check if file exists
+            if !fs.exists(src.as_path()).ok().unwrap_or(false) {
+                return Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory));
+            }
+            let dest = fs.resolve(dest);
+            debug!("Moving {} to {}", src.display(), dest.display());
+            match fs.shell_cmd_with_rc(fs.copy_cmd("mv -f", &src, &dest))? {
+                (0, _) => Ok(()),
+                (DEST_EXISTS_RC, _) if !fs.overwrite => Err(RemoteError::new_ex(
+                    RemoteErrorType::FileCreateDenied,
+                    format!("\"{}\" already exists", dest.display()),
+                )),
+                _ => Err(RemoteError::new_ex(
+                    // Could not copy file
+                    RemoteErrorType::FileCreateDenied,
+                    format!("\"{}\"", dest.display()),
+                )),
+            }
+        })
+    }
+
+    /// The output is decoded as UTF-8 lossily: invalid sequences are replaced with `U+FFFD`.
+    /// Use [`KubeContainerFs::exec_bytes`] to get the raw output instead.
+    fn exec(&mut self, cmd: &str) -> RemoteResult<(u32, String)> {
+        self.metered("exec", |fs| {
+            fs.check_connection()?;
+            debug!(r#"Executing command "{}""#, cmd);
+            fs.shell_cmd_at_with_rc(cmd, fs.wrkdir.as_path())
+        })
+    }
+
+    fn append(&mut self, _path: &Path, _metadata: &Metadata) -> RemoteResult<WriteStream> {
+        Err(RemoteError::new(RemoteErrorType::UnsupportedFeature))
+    }
+
+    fn create(&mut self, _path: &Path, _metadata: &Metadata) -> RemoteResult<WriteStream> {
+        Err(RemoteError::new(RemoteErrorType::UnsupportedFeature))
+    }
+
+    fn open(&mut self, _path: &Path) -> RemoteResult<ReadStream> {
+        Err(RemoteError::new(RemoteErrorType::UnsupportedFeature))
+    }
+
+    fn create_file(
+        &mut self,
+        path: &Path,
+        metadata: &Metadata,
+        reader: Box<dyn std::io::Read + Send>,
+    ) -> RemoteResult<u64> {
+        self.metered("create_file", |fs| {
+            fs.check_connection()?;
+            let path = fs.resolve(path);
+            if fs.atomic {
+                fs.upload_file_atomic(path.as_path(), metadata, reader)
+            } else {
+                fs.upload_file(path.as_path(), metadata, reader)
+            }
+        })
+    }
+
+    #[cfg(feature = "find")]
+    fn find(&mut self, search: &str) -> RemoteResult<Vec<File>> {
+        self.metered("find", |fs| {
+            let wrkdir = fs.wrkdir.clone();
+            fs.find_in(wrkdir.as_path(), search, None)
+        })
+    }
+
+    fn open_file(
+        &mut self,
+        src: &Path,
+        mut dest: Box<dyn std::io::Write + Send>,
+    ) -> RemoteResult<u64> {
+        self.metered("open_file", |fs| {
+            fs.check_connection()?;
+            let src = fs.resolve(src);
+            fs.with_retries("open_file", || {
+                fs.download_file(src.as_path(), dest.as_mut(), None)
+            })
+        })
+    }
+}
+
+/// Build the review asking whether the current user can exec into `pod` in `namespace`
+fn exec_access_review(namespace: &str, pod: &str) -> SelfSubjectAccessReview {
+    SelfSubjectAccessReview {
+        spec: SelfSubjectAccessReviewSpec {
+            resource_attributes: Some(ResourceAttributes {
+                namespace: Some(namespace.to_string()),
+                verb: Some("create".to_string()),
+                group: Some(String::new()),
+                resource: Some("pods".to_string()),
+                subresource: Some("exec".to_string()),
+                name: Some(pod.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Whether the absolute `path` is on a pseudo-filesystem (procfs or sysfs), whose files report a size of 0
+fn is_pseudo_file(path: &Path) -> bool {
+    path.starts_with("/proc") || path.starts_with("/sys")
+}
+
+/// Whether a file archived with `archived_size` bytes may be a pseudo-file, to read again through `cat`.
+///
+/// Pseudo-filesystems may be mounted elsewhere than in [`is_pseudo_file`], and their files are archived empty there too
+fn may_be_pseudo_file(archived_size: u64) -> bool {
+    archived_size == 0
+}
+
+/// Get the shell command renaming `from` to `to`, exiting with `SRC_MISSING_RC` if `from` doesn't exist
+/// and with `DEST_EXISTS_RC` if `to` exists
+fn rename_cmd(from: &Path, to: &Path) -> String {
+    format!(
+        "if [ ! -e \"{from}\" ] && [ ! -L \"{from}\" ]; then (exit {SRC_MISSING_RC}); \
+         elif [ -e \"{to}\" ] || [ -L \"{to}\" ]; then (exit {DEST_EXISTS_RC}); \
+         else mv \"{from}\" \"{to}\"; fi",
+        from = from.display(),
+        to = to.display(),
+    )
+}
+
+/// Wrap the shell `cmd` so that it runs in the directory at `path`.
+///
+/// The path is single-quoted, so the shell never expands anything in it.
+fn cd_command(path: &Path, cmd: impl std::fmt::Display) -> String {
+    format!("cd {} && {cmd}", path_utils::quote(path))
+}
+
+/// Build the command running `argv` in the directory at `path`: the shell only changes directory,
+/// while the arguments are passed as positional parameters, so they're never parsed by the shell
+fn argv_at(path: &Path, argv: &[&str]) -> Vec<String> {
+    let mut cmd = vec![
+        "/bin/sh".to_string(),
+        "-c".to_string(),
+        r#"cd "$0" && exec "$@""#.to_string(),
+        path.display().to_string(),
+    ];
+    cmd.extend(argv.iter().map(|arg| arg.to_string()));
+    cmd
+}
+
+/// Make the marker echoed before the exit code of a shell command; it's random, so that the output can't end with it
+fn exit_code_marker() -> String {
+    format!("<rc:{:016x}>", fastrand::u64(..))
+}
+
+/// Get the exit code of a shell command and its output, without the exit code echoed after `marker`.
+///
+/// The exit code reported by the status of the process is preferred; the echoed one is used when it's missing.
+fn shell_exit_code<'a>(
+    status: Option<&Status>,
+    stdout: &'a [u8],
+    marker: &str,
+) -> Option<(u32, &'a [u8])> {
+    match (
+        transfer::reported_exit_code(status),
+        split_exit_code(stdout, marker),
+    ) {
+        (Some(rc), Some((_, output))) => Some((rc, output)),
+        // the command exited the shell before the echo
+        (Some(rc), None) => Some((rc, stdout)),
+        (None, echoed) => echoed,
+    }
+}
+
+/// Split the output of a shell command at the `marker` and `$?` appended to it,
+/// returning the exit code and the output before it
+fn split_exit_code<'a>(stdout: &'a [u8], marker: &str) -> Option<(u32, &'a [u8])> {
+    let marker = marker.as_bytes();
+    let sep = stdout
+        .windows(marker.len())
+        .rposition(|window| window == marker)?;
+    let rc = std::str::from_utf8(&stdout[sep + marker.len()..])
+        .ok()?
+        .parse()
+        .ok()?;
+
+    Some((rc, &stdout[..sep]))
+}
+
+/// Decode the output of a command as UTF-8, replacing invalid sequences with `U+FFFD`
+fn decode_output(output: &[u8]) -> String {
+    String::from_utf8_lossy(output).into_owned()
+}
+
+/// Error of a transfer requiring tar, in a container without it
+fn tar_unavailable() -> RemoteError {
+    KubeFsError::TarUnavailable.into_remote(RemoteErrorType::UnsupportedFeature)
+}
+
+/// Create an anonymous temporary file in `dir`, or in the system temp directory if unset
+fn tempfile_in(dir: Option<&Path>) -> RemoteResult<std::fs::File> {
+    match dir {
+        Some(dir) => tempfile::tempfile_in(dir),
+        None => tempfile::tempfile(),
+    }
+    .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))
+}
+
+/// Create a named temporary file in `dir`, or in the system temp directory if unset
+fn named_tempfile_in(dir: Option<&Path>) -> RemoteResult<tempfile::NamedTempFile> {
+    match dir {
+        Some(dir) => tempfile::NamedTempFile::new_in(dir),
+        None => tempfile::NamedTempFile::new(),
+    }
+    .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))
+}
+
+/// Write the local file at `path` with `write`, through a temporary file in the same directory,
+/// which replaces `path` only if `write` succeeds; the temporary file is removed otherwise.
+fn replace_local_file<T>(
+    path: &Path,
+    write: impl FnOnce(&mut std::fs::File) -> RemoteResult<T>,
+) -> RemoteResult<T> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut tempfile = named_tempfile_in(Some(dir))?;
+    let result = write(tempfile.as_file_mut())?;
+    tempfile
+        .persist(path)
+        .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err.error))?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::io::Cursor;
+
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{StatusCause, StatusDetails};
+    use pretty_assertions::assert_eq;
+    #[cfg(feature = "integration-tests")]
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    fn should_init_kube_fs() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut client = KubeContainerFs::new("test", "test", &rt);
+        assert!(client.config.is_none());
+        assert_eq!(client.is_connected(), false);
+    }
+
+    #[test]
+    fn should_fail_connection_to_bad_server() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut client = KubeContainerFs::new("aaaaaa", "test", &rt);
+        assert!(client.connect().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_append_to_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.txt");
+        // Append to file
+        let file_data = "Hello, world!\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        assert!(client
+            .append_file(p, &Metadata::default(), Box::new(reader))
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_change_directory() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let pwd = client.pwd().ok().unwrap();
+        assert!(client.change_dir(Path::new("/tmp")).is_ok());
+        assert!(client.change_dir(pwd.as_path()).is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_change_directory_relative() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        assert!(client
+            .create_dir(
+                Path::new("should_change_directory_relative"),
+                UnixPex::from(0o755)
+            )
+            .is_ok());
+        assert!(client
+            .change_dir(Path::new("should_change_directory_relative/"))
+            .is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_change_directory() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        assert!(client
+            .change_dir(Path::new("/tmp/sdfghjuireghiuergh/useghiyuwegh"))
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_store_wrkdir_according_to_physical_paths() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let pwd = client.wrkdir.clone();
+        let mut client = client.physical_paths(false);
+        assert!(client
+            .create_dir(Path::new("real"), UnixPex::from(0o755))
+            .is_ok());
+        assert!(client
+            .symlink(Path::new("link"), pwd.join("real").as_path())
+            .is_ok());
+        // logical
+        assert_eq!(
+            client.change_dir(Path::new("link/")).unwrap(),
+            pwd.join("link")
+        );
+        assert_eq!(client.pwd().unwrap(), pwd.join("link"));
+        // physical
+        let mut client = client.physical_paths(true);
+        assert!(client.change_dir(pwd.as_path()).is_ok());
+        assert_eq!(
+            client.change_dir(Path::new("link")).unwrap(),
+            pwd.join("real")
+        );
+        assert_eq!(client.pwd().unwrap(), pwd.join("real"));
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_list_empty_directory() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("empty");
+        assert!(client.create_dir(p, UnixPex::from(0o755)).is_ok());
+        assert_eq!(client.list_dir(p).unwrap(), vec![]);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_change_directory_into_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let pwd = client.pwd().unwrap();
+        let file_data = "test data\n";
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client
+            .create_file(
+                Path::new("a.txt"),
+                &metadata,
+                Box::new(Cursor::new(file_data.as_bytes()))
+            )
+            .is_ok());
+        assert_eq!(
+            client.change_dir(Path::new("a.txt")).err().unwrap().kind,
+            RemoteErrorType::BadFile
+        );
+        assert_eq!(client.pwd().unwrap(), pwd);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_copy_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        assert!(client.copy(p, Path::new("b.txt")).is_ok());
+        assert!(client.stat(p).is_ok());
+        assert!(client.stat(Path::new("b.txt")).is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_copy_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        assert!(client.copy(p, Path::new("aaa/bbbb/ccc/b.txt")).is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_copy_and_move_file_creating_parents() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let mut client = client.create_parents(true);
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        assert!(client.copy(p, Path::new("aaa/bbbb/ccc/b.txt")).is_ok());
+        assert!(client.stat(Path::new("aaa/bbbb/ccc/b.txt")).is_ok());
+        assert!(client.mov(p, Path::new("ddd/eee/c.txt")).is_ok());
+        assert!(client.stat(Path::new("ddd/eee/c.txt")).is_ok());
+        assert_eq!(client.exists(p).unwrap(), false);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_overwrite_on_copy_and_move() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let mut client = client.overwrite(false);
+        // Create files
+        for name in ["a.txt", "b.txt"] {
+            let file_data = "test data\n";
+            let reader = Cursor::new(file_data.as_bytes());
+            let metadata = Metadata::default().size(file_data.len() as u64);
+            assert!(client
+                .create_file(Path::new(name), &metadata, Box::new(reader))
+                .is_ok());
+        }
+        let a = Path::new("a.txt");
+        let b = Path::new("b.txt");
+        assert_eq!(
+            client.copy(a, b).err().unwrap().kind,
+            RemoteErrorType::FileCreateDenied
+        );
+        assert!(client.mov(a, b).is_err());
+        assert!(client.exists(a).unwrap());
+        // not existing destination
+        assert!(client.copy(a, Path::new("c.txt")).is_ok());
+        assert!(client.mov(a, Path::new("d.txt")).is_ok());
+        // overwrite
+        let mut client = client.overwrite(true);
+        assert!(client.copy(Path::new("c.txt"), b).is_ok());
+        assert!(client.mov(Path::new("d.txt"), b).is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_create_directory() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // create directory
+        assert!(client
+            .create_dir(Path::new("mydir"), UnixPex::from(0o755))
+            .is_ok());
+        let p = PathBuf::from(format!("{}/mydir", client.pwd().unwrap().display()));
+        assert!(client.exists(&p).unwrap());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_create_directory_cause_already_exists() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // create directory
+        assert!(client
+            .create_dir(Path::new("mydir"), UnixPex::from(0o755))
+            .is_ok());
+        assert_eq!(
+            client
+                .create_dir(Path::new("mydir"), UnixPex::from(0o755))
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::DirectoryAlreadyExists
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_create_directory_with_parents() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("a/b/c");
+        assert!(client.create_dir_all(p, UnixPex::from(0o755)).is_ok());
+        assert!(client.exists(Path::new("a")).unwrap());
+        assert!(client.exists(Path::new("a/b")).unwrap());
+        assert!(client.stat(p).unwrap().is_dir());
+        // already exists
+        assert!(client.create_dir_all(p, UnixPex::from(0o755)).is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_create_directory_with_parents_over_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        assert!(client
+            .create_dir_all(Path::new("a.txt/b"), UnixPex::from(0o755))
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_create_directory() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // create directory
+        assert!(client
+            .create_dir(
+                Path::new("/tmp/werfgjwerughjwurih/iwerjghiwgui"),
+                UnixPex::from(0o755)
+            )
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_create_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert_eq!(
+            client.create_file(p, &metadata, Box::new(reader)).unwrap(),
+            10
+        );
+        // Verify size
+        assert_eq!(client.stat(p).ok().unwrap().metadata().size, 10);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_list_devices() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let files = client.list_dir(Path::new("/dev")).unwrap();
+        let null = files.iter().find(|f| f.name() == "null").unwrap();
+        assert!(null.is_file());
+        assert_eq!(null.metadata().size, 0);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_make_fifo() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("fifo");
+        assert!(client.mkfifo(p, UnixPex::from(0o640)).is_ok());
+        let entry = client.stat(p).unwrap();
+        assert_eq!(entry.name(), "fifo");
+        assert!(entry.is_file());
+        assert_eq!(u32::from(entry.metadata().mode.unwrap()), 0o640);
+        // listed too
+        let wrkdir = client.pwd().unwrap();
+        assert_eq!(client.list_dir(wrkdir.as_path()).unwrap().len(), 1);
+        // already exists
+        assert_eq!(
+            client.mkfifo(p, UnixPex::from(0o640)).err().unwrap().kind,
+            RemoteErrorType::FileCreateDenied
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_get_disk_usage() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        assert!(client
+            .create_dir(Path::new("tree"), UnixPex::from(0o755))
+            .is_ok());
+        let empty = client.disk_usage(Path::new("tree")).unwrap();
+        let file_data = vec![1u8; 64 * 1024];
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client
+            .create_file(
+                Path::new("tree/a.bin"),
+                &metadata,
+                Box::new(Cursor::new(file_data))
+            )
+            .is_ok());
+        assert!(client.disk_usage(Path::new("tree/a.bin")).unwrap() >= 64 * 1024);
+        assert!(client.disk_usage(Path::new("tree")).unwrap() >= empty + 64 * 1024);
+        assert_eq!(
+            client
+                .disk_usage(Path::new("tree/missing"))
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_stat_with_security_labels() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let metadata = Metadata::default().size(5);
+        assert!(client
+            .create_file(
+                Path::new("a.txt"),
+                &metadata,
+                Box::new(Cursor::new(b"hello".to_vec()))
+            )
+            .is_ok());
+        // the test image has neither SELinux nor getfattr
+        let (file, labels) = client.stat_ext(Path::new("a.txt")).unwrap();
+        assert_eq!(file.metadata().size, 5);
+        assert_eq!(labels, SecurityLabels::default());
+        assert_eq!(
+            client
+                .stat_ext(Path::new("missing.txt"))
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_respect_container_umask() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let mut client = client.respect_umask(true);
+        // the umask of the alpine image is 0022
+        assert_eq!(client.exec("umask").unwrap().1.trim(), "0022");
+        assert!(client
+            .create_dir(Path::new("dir"), UnixPex::from(0o777))
+            .is_ok());
+        let metadata = Metadata::default().size(2).mode(UnixPex::from(0o666));
+        assert!(client
+            .create_file(
+                Path::new("a.txt"),
+                &metadata,
+                Box::new(Cursor::new(b"a\n".to_vec()))
+            )
+            .is_ok());
+        let mode = |client: &mut KubeContainerFs, path: &str| {
+            u32::from(
+                client
+                    .stat(Path::new(path))
+                    .unwrap()
+                    .metadata()
+                    .mode
+                    .unwrap(),
+            )
+        };
+        assert_eq!(mode(&mut client, "dir"), 0o755);
+        assert_eq!(mode(&mut client, "a.txt"), 0o644);
+        // without the option, the modes are set as they are
+        let mut client = client.respect_umask(false);
+        assert!(client
+            .create_dir(Path::new("dir2"), UnixPex::from(0o777))
+            .is_ok());
+        assert_eq!(mode(&mut client, "dir2"), 0o777);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_chmod_recursive() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let files = make_small_tree(&mut client);
+        assert!(client
+            .chmod_recursive(Path::new("tree"), UnixPex::from(0o700))
+            .is_ok());
+        for file in files {
+            assert_eq!(
+                u32::from(client.stat(&file).unwrap().metadata().mode.unwrap()),
+                0o700
+            );
+        }
+        // never on root
+        assert_eq!(
+            client
+                .chmod_recursive(Path::new("/"), UnixPex::from(0o700))
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::BadFile
+        );
+        let up = client
+            .pwd()
+            .unwrap()
+            .components()
+            .skip(1)
+            .map(|_| "..")
+            .collect::<Vec<_>>();
+        assert_eq!(
+            client
+                .chmod_recursive(&PathBuf::from(up.join("/")), UnixPex::from(0o700))
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::BadFile
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_chown_recursive() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let files = make_small_tree(&mut client);
+        assert!(client
+            .chown_recursive(Path::new("tree"), 1000, 1000)
+            .is_ok());
+        for file in files {
+            let stat = client.stat(&file).unwrap();
+            assert_eq!(stat.metadata().uid, Some(1000));
+            assert_eq!(stat.metadata().gid, Some(1000));
+        }
+        assert_eq!(
+            client
+                .chown_recursive(Path::new("/"), 1000, 1000)
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::BadFile
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_chown_recursive_without_following_symlinks() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        make_small_tree(&mut client);
+        assert!(client
+            .create_dir(Path::new("target"), UnixPex::from(0o755))
+            .is_ok());
+        assert!(client
+            .symlink(Path::new("tree/link"), Path::new("../target"))
+            .is_ok());
+        assert!(client
+            .chown_recursive(Path::new("tree"), 1000, 1000)
+            .is_ok());
+        assert_eq!(
+            client.stat(Path::new("tree/link")).unwrap().metadata().uid,
+            Some(1000)
+        );
+        assert_eq!(
+            client.stat(Path::new("target")).unwrap().metadata().uid,
+            Some(0)
+        );
+        assert!(client
+            .chown_recursive_with(Path::new("tree"), 1001, 1001, SymlinkFollow::Always)
+            .is_ok());
+        assert_eq!(
+            client.stat(Path::new("target")).unwrap().metadata().uid,
+            Some(1001)
+        );
+        // a symlink as argument is changed itself, not its target
+        assert!(client
+            .symlink(Path::new("link"), Path::new("target"))
+            .is_ok());
+        assert!(client
+            .chown_recursive_with(Path::new("link"), 1002, 1002, SymlinkFollow::Never)
+            .is_ok());
+        assert_eq!(
+            client.stat(Path::new("link")).unwrap().metadata().uid,
+            Some(1002)
+        );
+        assert_eq!(
+            client.stat(Path::new("target")).unwrap().metadata().uid,
+            Some(1001)
+        );
+        assert!(client
+            .chown_recursive_with(Path::new("link"), 1003, 1003, SymlinkFollow::CommandLine)
+            .is_ok());
+        assert_eq!(
+            client.stat(Path::new("target")).unwrap().metadata().uid,
+            Some(1003)
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_ensure_dir() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("mydir");
+        let mode = |client: &mut KubeContainerFs| {
+            u32::from(client.stat(p).unwrap().metadata().mode.unwrap())
+        };
+        // absent
+        assert!(client.ensure_dir(p, UnixPex::from(0o750)).is_ok());
+        assert!(client.stat(p).unwrap().is_dir());
+        assert_eq!(mode(&mut client), 0o750);
+        // present, with matching mode
+        assert!(client.ensure_dir(p, UnixPex::from(0o750)).is_ok());
+        assert_eq!(mode(&mut client), 0o750);
+        // present, with another mode
+        assert!(client.ensure_dir(p, UnixPex::from(0o700)).is_ok());
+        assert_eq!(mode(&mut client), 0o700);
+        // a file is in the way
+        let file_data = "test data\n";
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client
+            .create_file(
+                Path::new("a.txt"),
+                &metadata,
+                Box::new(Cursor::new(file_data.as_bytes()))
+            )
+            .is_ok());
+        assert_eq!(
+            client
+                .ensure_dir(Path::new("a.txt"), UnixPex::from(0o755))
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::DirectoryAlreadyExists
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_create_file_with_default_mode() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let mut client = client.default_file_mode(UnixPex::from(0o640));
+        let file_data = "test data\n";
+        // without mode
+        let p = Path::new("a.txt");
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client
+            .create_file(p, &metadata, Box::new(Cursor::new(file_data.as_bytes())))
+            .is_ok());
+        assert_eq!(
+            u32::from(client.stat(p).unwrap().metadata().mode.unwrap()),
+            0o640
+        );
+        // explicit mode wins
+        let p = Path::new("b.sh");
+        let metadata = Metadata::default()
+            .size(file_data.len() as u64)
+            .mode(UnixPex::from(0o755));
+        assert!(client
+            .create_file(p, &metadata, Box::new(Cursor::new(file_data.as_bytes())))
+            .is_ok());
+        assert_eq!(
+            u32::from(client.stat(p).unwrap().metadata().mode.unwrap()),
+            0o755
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_create_file_atomically() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let mut client = client.atomic(true);
+        let wrkdir = client.pwd().unwrap();
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert_eq!(
+            client.create_file(p, &metadata, Box::new(reader)).unwrap(),
+            10
+        );
+        assert_eq!(client.stat(p).unwrap().metadata().size, 10);
+        // no temporary file must be left
+        let files = client
+            .list_dir_opts(
+                wrkdir.as_path(),
+                ListOptions {
+                    show_hidden: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name().as_str(), "a.txt");
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_get_env() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let env = client.env().unwrap();
+        assert!(env.iter().any(|(name, _)| name == "PATH"));
+        assert!(env
+            .iter()
+            .any(|(name, value)| name == "HOSTNAME" && value == client.pod_name.as_str()));
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_tell_whether_path_is_on_read_only_mount() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // service account token is mounted read-only
+        assert!(client
+            .is_read_only_mount(Path::new(
+                "/var/run/secrets/kubernetes.io/serviceaccount/token"
+            ))
+            .unwrap());
+        assert!(!client.is_read_only_mount(Path::new("a.txt")).unwrap());
+        assert!(!client.is_read_only_mount(Path::new("/tmp")).unwrap());
+        assert!(client
+            .is_read_only_mount(Path::new("/this/path/doesnt/exist"))
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_edit_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("a.txt");
+        let file_data = "hello world\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        assert!(client
+            .setstat(p, Metadata::default().mode(UnixPex::from(0o640)))
+            .is_ok());
+
+        assert_eq!(
+            client
+                .edit(p, |content| String::from_utf8_lossy(content)
+                    .replace("world", "kube")
+                    .into_bytes())
+                .unwrap(),
+            11
+        );
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        let dest = Box::new(std::fs::File::create(tempfile.path()).unwrap());
+        assert_eq!(client.open_file(p, dest).unwrap(), 11);
+        assert_eq!(
+            std::fs::read_to_string(tempfile.path()).unwrap(),
+            "hello kube\n"
+        );
+        assert_eq!(
+            client.stat(p).unwrap().metadata().mode,
+            Some(UnixPex::from(0o640))
+        );
+        // directories can't be edited
+        assert!(client
+            .edit(Path::new("/tmp"), |content| content.to_vec())
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_create_file_atomically() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let mut client = client.atomic(true);
+        let p = Path::new("/tmp/ahsufhauiefhuiashf/hfhfhfhf");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_err());
+        assert_eq!(client.exists(p).unwrap(), false);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_create_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("/tmp/ahsufhauiefhuiashf/hfhfhfhf");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_create_multiple_files() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let files = (0..20)
+            .map(|i| {
+                let file_data = format!("file {i}\n");
+                let metadata = Metadata::default().size(file_data.len() as u64);
+                let reader: Box<dyn std::io::Read + Send> =
+                    Box::new(Cursor::new(file_data.into_bytes()));
+                (PathBuf::from(format!("dir/file_{i}.txt")), metadata, reader)
+            })
+            .collect::<Vec<_>>();
+        let sizes = client.create_files(files).unwrap();
+        assert_eq!(sizes.len(), 20);
+        for (i, size) in sizes.into_iter().enumerate() {
+            let p = PathBuf::from(format!("dir/file_{i}.txt"));
+            assert_eq!(client.stat(p.as_path()).unwrap().metadata().size, size);
+        }
+        assert!(client.create_files(vec![]).unwrap().is_empty());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_list_dir_longer_than_stdout_buffer() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let wrkdir = client.pwd().unwrap();
+        let names = (0..40)
+            .map(|i| format!("{}_{i:02}.txt", "a".repeat(120)))
+            .collect::<Vec<_>>();
+        let files = names
+            .iter()
+            .map(|name| {
+                let reader: Box<dyn std::io::Read + Send> = Box::new(Cursor::new(b"test".to_vec()));
+                (PathBuf::from(name), Metadata::default().size(4), reader)
+            })
+            .collect::<Vec<_>>();
+        assert!(client.create_files(files).is_ok());
+        // total listing is way longer than 2048 bytes
+        let mut listed = client
+            .list_dir(wrkdir.as_path())
+            .unwrap()
+            .into_iter()
+            .map(|f| f.name())
+            .collect::<Vec<_>>();
+        listed.sort();
+        assert_eq!(listed, names);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_handle_non_ascii_file_names() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let wrkdir = client.pwd().unwrap();
+        for name in ["café.txt", "文件.txt"] {
+            let p = Path::new(name);
+            let file_data = "test data\n";
+            let reader = Cursor::new(file_data.as_bytes());
+            let metadata = Metadata::default().size(file_data.len() as u64);
+            assert_eq!(
+                client.create_file(p, &metadata, Box::new(reader)).unwrap(),
+                10
+            );
+            let stat = client.stat(p).unwrap();
+            assert_eq!(stat.name().as_str(), name);
+            assert_eq!(stat.metadata().size, 10);
+            let tempfile = tempfile::NamedTempFile::new().unwrap();
+            let dest = Box::new(std::fs::File::create(tempfile.path()).unwrap());
+            assert_eq!(client.open_file(p, dest).unwrap(), 10);
+            assert_eq!(std::fs::read_to_string(tempfile.path()).unwrap(), file_data);
+        }
+        let mut names = client
+            .list_dir(wrkdir.as_path())
+            .unwrap()
+            .into_iter()
+            .map(|f| f.name())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["café.txt", "文件.txt"]);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_sync_up() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let local = tempfile::tempdir().unwrap();
+        std::fs::create_dir(local.path().join("dir")).unwrap();
+        std::fs::write(local.path().join("a.txt"), b"hello\n").unwrap();
+        std::fs::write(local.path().join("dir/b.txt"), b"world\n").unwrap();
+        let remote = Path::new("sync");
+        // first sync uploads everything
+        let report = client
+            .sync_up(local.path(), remote, SyncOptions::default())
+            .unwrap();
+        assert_eq!(report.created_dirs, vec![PathBuf::from("dir")]);
+        assert_eq!(report.uploaded.len(), 2);
+        assert!(client.exists(Path::new("sync/dir/b.txt")).unwrap());
+        // nothing changed
+        let report = client
+            .sync_up(local.path(), remote, SyncOptions::default())
+            .unwrap();
+        assert!(report.uploaded.is_empty());
+        assert_eq!(report.skipped.len(), 2);
+        // change a file and remove another one
+        std::fs::write(local.path().join("a.txt"), b"hello world\n").unwrap();
+        std::fs::remove_file(local.path().join("dir/b.txt")).unwrap();
+        let report = client
+            .sync_up(local.path(), remote, SyncOptions { delete: true })
+            .unwrap();
+        assert_eq!(report.uploaded, vec![PathBuf::from("a.txt")]);
+        assert_eq!(report.deleted, vec![PathBuf::from("dir/b.txt")]);
+        assert_eq!(
+            client
+                .stat(Path::new("sync/a.txt"))
+                .unwrap()
+                .metadata()
+                .size,
+            12
+        );
+        assert!(!client.exists(Path::new("sync/dir/b.txt")).unwrap());
+        // files older than six months, whose mtime ls prints without the time, match too
+        std::fs::File::options()
+            .write(true)
+            .open(local.path().join("a.txt"))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(946684800))
+            .unwrap();
+        let report = client
+            .sync_up(local.path(), remote, SyncOptions::default())
+            .unwrap();
+        assert_eq!(report.uploaded, vec![PathBuf::from("a.txt")]);
+        let report = client
+            .sync_up(local.path(), remote, SyncOptions::default())
+            .unwrap();
+        assert!(report.uploaded.is_empty());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_read_first_and_last_bytes() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("a.bin");
+        let file_data: Vec<u8> = (0..=255).collect();
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client
+            .create_file(p, &metadata, Box::new(Cursor::new(file_data.clone())))
+            .is_ok());
+
+        assert_eq!(client.head(p, 16).unwrap(), file_data[..16]);
+        assert_eq!(client.tail_bytes(p, 16).unwrap(), file_data[240..]);
+        assert!(client.head(p, 0).unwrap().is_empty());
+        // the file is smaller than requested
+        assert_eq!(client.head(p, 1024).unwrap(), file_data);
+        assert_eq!(client.tail_bytes(p, 1024).unwrap(), file_data);
+        assert_eq!(
+            client
+                .head(Path::new("/tmp/ahsufhauiefhuiashf"), 16)
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+        assert!(client
+            .tail_bytes(Path::new("/tmp/ahsufhauiefhuiashf"), 16)
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_tail_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("a.txt");
+        let file_data = (1..=10).map(|i| format!("line {i}\n")).collect::<String>();
+        let reader = Cursor::new(file_data.as_bytes().to_vec());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+
+        let mut lines = Vec::new();
+        assert!(client
+            .tail(p, 5, false, &CancellationToken::new(), |line| lines
+                .push(line.to_string()))
+            .is_ok());
+        assert_eq!(
+            lines,
+            vec!["line 6", "line 7", "line 8", "line 9", "line 10"]
+        );
+        assert!(client
+            .tail(
+                Path::new("/tmp/ahsufhauiefhuiashf"),
+                5,
+                false,
+                &CancellationToken::new(),
+                |_| {}
+            )
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_stop_following_file_when_cancelled() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("a.txt");
+        let file_data = "line 1\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs(2));
+            canceller.cancel();
+        });
+        let mut lines = Vec::new();
+        assert!(client
+            .tail(p, 5, true, &cancel, |line| lines.push(line.to_string()))
+            .is_ok());
+        assert_eq!(lines, vec!["line 1"]);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_cancel_open_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        assert_eq!(
+            client
+                .exec("dd if=/dev/zero of=big.bin bs=1M count=64")
+                .unwrap()
+                .0,
+            0
+        );
+        let token = CancellationToken::new();
+        let mut client = client.cancellation_token(token.clone());
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            token.cancel();
+        });
+        let started = std::time::Instant::now();
+        let err = client
+            .open_file(Path::new("big.bin"), Box::new(std::io::sink()))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::ProtocolError);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let client = client.cancellation_token(CancellationToken::new());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_report_cancellation_of_fs_operations() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let token = CancellationToken::new();
+        let mut client = client.cancellation_token(token.clone());
+        token.cancel();
+        let p = Path::new("/tmp");
+        for err in [
+            client.stat(p).err().unwrap(),
+            client.list_dir(p).err().unwrap(),
+            client.exists(p).err().unwrap(),
+            client.change_dir(p).err().unwrap(),
+            client.canonicalize(p).err().unwrap(),
+            client
+                .create_dir_all(Path::new("/tmp/a/b"), UnixPex::from(0o755))
+                .err()
+                .unwrap(),
+        ] {
+            assert_eq!(err.kind, RemoteErrorType::ProtocolError);
+            assert_eq!(KubeFsError::from_remote(&err), Some(KubeFsError::Cancelled));
+            assert_eq!(err.msg.as_deref(), Some("cancelled"));
+        }
+        let client = client.cancellation_token(CancellationToken::new());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_wait_for_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // the file is created in background, after the exec has returned
+        assert_eq!(
+            client
+                .exec("(sleep 2 && touch ready.txt) > /dev/null 2>&1 &")
+                .unwrap()
+                .0,
+            0
+        );
+        assert_eq!(client.exists(Path::new("ready.txt")).unwrap(), false);
+        assert!(client
+            .wait_for_file(
+                Path::new("ready.txt"),
+                Duration::from_secs(30),
+                Duration::from_millis(200)
+            )
+            .is_ok());
+        assert_eq!(client.exists(Path::new("ready.txt")).unwrap(), true);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_time_out_waiting_for_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let started = std::time::Instant::now();
+        let err = client
+            .wait_for_file(
+                Path::new("never.txt"),
+                Duration::from_secs(1),
+                Duration::from_millis(200),
+            )
+            .err()
+            .unwrap();
+        assert_eq!(
+            KubeFsError::from_remote(&err),
+            Some(KubeFsError::WaitTimeout)
+        );
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_get_pod_info() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let info = client.pod_info().unwrap();
+        assert_eq!(info.phase.as_deref(), Some("Running"));
+        assert!(info.node_name.is_some());
+        assert!(info.image.unwrap().contains("alpine"));
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_disconnect_when_watched_pod_is_deleted() {
+        use kube::api::DeleteParams;
+
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        assert!(client.disconnect().is_ok());
+        let mut client = client.watch_pod(true);
+        assert!(client.connect().is_ok());
+
+        let runtime = client.runtime.clone();
+        runtime
+            .block_on(pods.delete(&client.pod_name, &DeleteParams::default().grace_period(0)))
+            .unwrap();
+        let started = std::time::Instant::now();
+        // the watch flips the client to disconnected
+        while client.pods.is_some() {
+            assert!(started.elapsed() < Duration::from_secs(60));
+            let _ = client.pwd();
+            std::thread::sleep(Duration::from_millis(500));
+        }
+        assert_eq!(
+            client.pwd().err().unwrap().kind,
+            RemoteErrorType::NotConnected
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_exec_command_in_dir() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let pwd = client.pwd().unwrap();
+        assert_eq!(
+            client.exec_in(Path::new("/tmp"), "pwd").unwrap(),
+            (0, String::from("/tmp\n"))
+        );
+        assert!(client
+            .create_dir(Path::new("sub"), UnixPex::from(0o755))
+            .is_ok());
+        // relative to the working directory
+        assert_eq!(
+            client.exec_in(Path::new("sub"), "pwd").unwrap(),
+            (0, format!("{}\n", pwd.join("sub").display()))
+        );
+        assert_eq!(client.pwd().unwrap(), pwd);
+        assert_eq!(
+            client.exec("pwd").unwrap(),
+            (0, format!("{}\n", pwd.display()))
+        );
+        // the directory isn't parsed by the shell
+        let dir = pwd.join("my dir $(touch injected)");
+        assert_eq!(
+            client
+                .exec_raw(vec!["mkdir".to_string(), dir.display().to_string()])
+                .unwrap()
+                .0,
+            0
+        );
+        assert_eq!(
+            client.exec_in(&dir, "pwd").unwrap(),
+            (0, format!("{}\n", dir.display()))
+        );
+        assert!(!client.exists(Path::new("injected")).unwrap());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_exec_checked_command() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        assert_eq!(client.exec_checked("echo 5").unwrap(), "5\n");
+        let err = client
+            .exec_checked("echo 5; ls /this/path/doesnt/exist")
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::ProtocolError);
+        let message = err.to_string();
+        assert!(message.contains("exited with code 1"));
+        assert!(message.contains("/this/path/doesnt/exist"));
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_exec_command() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        assert_eq!(
+            client.exec("echo 5").ok().unwrap(),
+            (0, String::from("5\n"))
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_exec_command_with_binary_output() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let cmd = r"printf 'a;\377b'";
+        assert_eq!(client.exec_bytes(cmd).unwrap(), (0, b"a;\xffb".to_vec()));
+        assert_eq!(client.exec(cmd).unwrap(), (0, String::from("a;\u{FFFD}b")));
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_exec_argv_without_shell_parsing() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let pwd = client.pwd().unwrap();
+        assert_eq!(
+            client
+                .exec_native(&["printf", "%s|", "a b", "$HOME", "'q'", "*"])
+                .unwrap(),
+            (0, String::from("a b|$HOME|'q'|*|"))
+        );
+        assert_eq!(
+            client.exec_native(&["pwd"]).unwrap(),
+            (0, format!("{}\n", pwd.display()))
+        );
+        assert_eq!(client.exec_native(&["false"]).unwrap().0, 1);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_exec_with_tty() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let isatty = [
+            "sh",
+            "-c",
+            "if [ -t 1 ]; then echo tty; else echo no tty; fi",
+        ];
+        assert_eq!(
+            client.exec_tty(&isatty).unwrap(),
+            (0, String::from("tty\n"))
+        );
+        assert_eq!(
+            client.exec_native(&isatty).unwrap(),
+            (0, String::from("no tty\n"))
+        );
+        // stderr is merged into the output
+        let (rc, output) = client
+            .exec_tty(&["sh", "-c", "echo out; echo err >&2; exit 3"])
+            .unwrap();
+        assert_eq!(rc, 3);
+        assert_eq!(output, "out\nerr\n");
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_exec_binary_directly() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let argv = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            client
+                .exec_raw(argv(&["/bin/echo", "a b", "$HOME"]))
+                .unwrap(),
+            (0, String::from("a b $HOME\n"))
+        );
+        // exit code comes from the process status
+        assert_eq!(client.exec_raw(argv(&["false"])).unwrap().0, 1);
+        assert!(client.exec_raw(argv(&["/no/such/binary"])).is_err());
+        assert!(client.exec_raw(Vec::new()).is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_tell_whether_file_exists() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        // Verify size
+        assert_eq!(client.exists(p).ok().unwrap(), true);
+        assert_eq!(client.exists(Path::new("b.txt")).ok().unwrap(), false);
+        assert_eq!(
+            client.exists(Path::new("/tmp/ppppp/bhhrhu")).ok().unwrap(),
+            false
+        );
+        assert_eq!(client.exists(Path::new("/tmp")).ok().unwrap(), true);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_list_dir() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let wrkdir = client.pwd().ok().unwrap();
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        // Verify size
+        let file = client
+            .list_dir(wrkdir.as_path())
+            .ok()
+            .unwrap()
+            .get(0)
+            .unwrap()
+            .clone();
+        assert_eq!(file.name().as_str(), "a.txt");
+        let mut expected_path = wrkdir;
+        expected_path.push(p);
+        assert_eq!(file.path.as_path(), expected_path.as_path());
+        assert_eq!(file.extension().as_deref().unwrap(), "txt");
+        assert_eq!(file.metadata.size, 10);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_list_dir_with_options() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let wrkdir = client.pwd().ok().unwrap();
+        for (name, data) in [("a.txt", "aa\n"), (".hidden", "h\n"), ("b.txt", "bbbbbb\n")] {
+            let reader = Cursor::new(data.as_bytes());
+            let metadata = Metadata::default().size(data.len() as u64);
+            assert!(client
+                .create_file(Path::new(name), &metadata, Box::new(reader))
+                .is_ok());
+        }
+        let names = |files: Vec<File>| files.iter().map(|f| f.name()).collect::<Vec<String>>();
+        let files = client
+            .list_dir_opts(wrkdir.as_path(), ListOptions::default())
+            .unwrap();
+        assert_eq!(names(files), vec!["a.txt", "b.txt"]);
+        let files = client
+            .list_dir_opts(
+                wrkdir.as_path(),
+                ListOptions {
+                    show_hidden: true,
+                    sort_by: SortBy::Size,
+                    reverse: false,
+                },
+            )
+            .unwrap();
+        assert_eq!(names(files), vec!["b.txt", "a.txt", ".hidden"]);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_list_dir() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        assert!(client.list_dir(Path::new("/tmp/auhhfh/hfhjfhf/")).is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_move_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        // Verify size
+        let dest = Path::new("b.txt");
+        assert!(client.mov(p, dest).is_ok());
+        assert_eq!(client.exists(p).ok().unwrap(), false);
+        assert_eq!(client.exists(dest).ok().unwrap(), true);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_move_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        // Verify size
+        let dest = Path::new("/tmp/wuefhiwuerfh/whjhh/b.txt");
+        assert!(client.mov(p, dest).is_err());
+        assert!(client
+            .mov(Path::new("/tmp/wuefhiwuerfh/whjhh/b.txt"), p)
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_tell_same_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let file_data = "test data\n";
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        for p in ["a.txt", "b.txt"] {
+            assert!(client
+                .create_file(
+                    Path::new(p),
+                    &metadata,
+                    Box::new(Cursor::new(file_data.as_bytes()))
+                )
+                .is_ok());
+        }
+        assert_eq!(client.exec("ln a.txt hard.txt").unwrap().0, 0);
+        assert!(client
+            .symlink(Path::new("link.txt"), Path::new("a.txt"))
+            .is_ok());
+        assert!(client
+            .same_file(Path::new("a.txt"), Path::new("a.txt"))
+            .unwrap());
+        assert!(client
+            .same_file(Path::new("a.txt"), Path::new("hard.txt"))
+            .unwrap());
+        assert!(client
+            .same_file(Path::new("link.txt"), Path::new("a.txt"))
+            .unwrap());
+        // same content, different file
+        assert!(!client
+            .same_file(Path::new("a.txt"), Path::new("b.txt"))
+            .unwrap());
+        assert_eq!(
+            client
+                .same_file(Path::new("a.txt"), Path::new("missing.txt"))
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_rename_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let file_data = "test data\n";
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client
+            .create_file(
+                Path::new("a.txt"),
+                &metadata,
+                Box::new(Cursor::new(file_data.as_bytes()))
+            )
+            .is_ok());
+        assert!(client
+            .rename(Path::new("a.txt"), Path::new("b.txt"))
+            .is_ok());
+        assert_eq!(client.exists(Path::new("a.txt")).unwrap(), false);
+        assert_eq!(client.exists(Path::new("b.txt")).unwrap(), true);
+        assert_eq!(
+            client
+                .rename(Path::new("a.txt"), Path::new("c.txt"))
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_rename_file_to_existing_path() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        for (name, data) in [("a.txt", "a\n"), ("b.txt", "b\n")] {
+            let metadata = Metadata::default().size(data.len() as u64);
+            assert!(client
+                .create_file(
+                    Path::new(name),
+                    &metadata,
+                    Box::new(Cursor::new(data.as_bytes()))
+                )
+                .is_ok());
+        }
+        // rename never clobbers, even with overwrite enabled
+        let mut client = client.overwrite(true);
+        let err = client
+            .rename(Path::new("a.txt"), Path::new("b.txt"))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::FileCreateDenied);
+        assert_eq!(
+            KubeFsError::from_remote(&err),
+            Some(KubeFsError::AlreadyExists)
+        );
+        let mut content = String::new();
+        client
+            .open_reader(Path::new("b.txt"))
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "b\n");
+        assert_eq!(client.exists(Path::new("a.txt")).unwrap(), true);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_open_several_files_at_once() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        assert!(client
+            .create_dir(Path::new("dir"), UnixPex::from(0o755))
+            .is_ok());
+        for (name, data) in [("a.txt", "a\n"), ("dir/b.txt", "b\n"), ("empty.txt", "")] {
+            let metadata = Metadata::default().size(data.len() as u64);
+            assert!(client
+                .create_file(
+                    Path::new(name),
+                    &metadata,
+                    Box::new(Cursor::new(data.as_bytes()))
+                )
+                .is_ok());
+        }
+        let b_path = client.pwd().unwrap().join("dir/b.txt");
+        let files = client
+            .open_files(vec![
+                PathBuf::from("a.txt"),
+                PathBuf::from("missing.txt"),
+                b_path.clone(),
+                PathBuf::from("empty.txt"),
+                PathBuf::from("/proc/self/status"),
+                PathBuf::from("dir"),
+            ])
+            .unwrap();
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[0], (PathBuf::from("a.txt"), b"a\n".to_vec()));
+        assert_eq!(files[1], (b_path, b"b\n".to_vec()));
+        assert_eq!(files[2], (PathBuf::from("empty.txt"), Vec::new()));
+        assert_eq!(files[3].0, PathBuf::from("/proc/self/status"));
+        assert!(String::from_utf8_lossy(&files[3].1).contains("Name:"));
+        assert!(client.open_files(vec![]).unwrap().is_empty());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_open_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        // Verify size
+        let buffer: Box<dyn std::io::Write + Send> = Box::new(Vec::with_capacity(512));
+        assert_eq!(client.open_file(p, buffer).ok().unwrap(), 10);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
     fn should_not_open_file() {
         crate::log_init();
         let (pods, mut client) = setup_client();
-        // Verify size
-        let buffer: Box<dyn std::io::Write + Send> = Box::new(Vec::with_capacity(512));
+        // Verify size
+        let buffer: Box<dyn std::io::Write + Send> = Box::new(Vec::with_capacity(512));
+        assert!(client
+            .open_file(Path::new("/tmp/aashafb/hhh"), buffer)
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_stream_file_without_temp_file() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        // nothing can be buffered in a missing directory, as if the local disk were full
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut client = client.temp_dir(temp_dir.path().join("missing"));
+        assert_eq!(
+            client
+                .exec("head -c 8388608 /dev/urandom > big.bin")
+                .unwrap()
+                .0,
+            0
+        );
+        let dest = CountingWriter::default();
+        assert!(client
+            .open_file(Path::new("big.bin"), Box::new(dest.clone()))
+            .is_err());
+        assert_eq!(dest.count(), 0);
+
+        let mut client = client.streaming(true);
+        assert_eq!(
+            client
+                .open_file(Path::new("big.bin"), Box::new(dest.clone()))
+                .unwrap(),
+            8 * 1024 * 1024
+        );
+        assert_eq!(dest.count(), 8 * 1024 * 1024);
+        // the limit is checked before writing anything
+        let dest = CountingWriter::default();
+        let err = client
+            .open_file_limited(Path::new("big.bin"), Box::new(dest.clone()), 1024)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("size limit of 1024 bytes"));
+        assert_eq!(dest.count(), 0);
+
+        // no temp file is created in streaming mode
+        let mut client = client.temp_dir(temp_dir.path().to_path_buf());
+        let dest = CountingWriter::default();
+        assert!(client
+            .open_file(Path::new("big.bin"), Box::new(dest.clone()))
+            .is_ok());
+        assert!(std::fs::read_dir(temp_dir.path()).unwrap().next().is_none());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_open_file_limited() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("big.bin");
+        let file_data = vec![0xcd; 256 * 1024];
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client
+            .create_file(p, &metadata, Box::new(Cursor::new(file_data)))
+            .is_ok());
+        // Within the limit
+        let buffer: Box<dyn std::io::Write + Send> = Box::new(Vec::new());
+        assert_eq!(
+            client.open_file_limited(p, buffer, 256 * 1024).unwrap(),
+            256 * 1024
+        );
+        // Over the limit
+        let dest = SharedBuffer::default();
+        let err = client
+            .open_file_limited(p, Box::new(dest.clone()), 1024)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::IoError);
+        assert!(err.to_string().contains("size limit of 1024 bytes"));
+        // nothing has been written to the destination
+        assert!(dest.0.lock().unwrap().is_empty());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_get_home_dir() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // alpine runs as root
+        assert_eq!(client.home_dir().unwrap(), PathBuf::from("/root"));
+        assert_eq!(client.home_dir().unwrap(), client.home.clone().unwrap());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_expand_home_directory() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // alpine runs as root
+        assert_eq!(
+            client.change_dir(Path::new("~")).unwrap(),
+            PathBuf::from("/root")
+        );
+        let wrkdir = client.pwd().unwrap();
+        assert!(client
+            .create_dir(&wrkdir.join("logs"), UnixPex::from(0o755))
+            .is_ok());
+        assert!(client.change_dir(Path::new("/tmp")).is_ok());
+        assert!(client.list_dir(Path::new("~/logs")).unwrap().is_empty());
+        assert!(client.remove_dir(Path::new("~/logs")).is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_transfer_files_with_tar_binary() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let mut client = client.tar_binary("busybox tar");
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client
+            .create_file(p, &metadata, Box::new(Cursor::new(file_data.as_bytes())))
+            .is_ok());
+        assert_eq!(client.tar_flavor(), Some(TarFlavor::Busybox));
+        let buffer: Box<dyn std::io::Write + Send> = Box::new(Vec::new());
+        assert_eq!(client.open_file(p, buffer).unwrap(), 10);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_transfer_files_without_tar() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let mut client = client.tar_binary("no-such-tar");
+        let p = Path::new("a.bin");
+        let file_data: Vec<u8> = (0..=255).cycle().take(5000).collect();
+        let metadata = Metadata::default()
+            .size(file_data.len() as u64)
+            .mode(UnixPex::from(0o600));
+        assert_eq!(
+            client
+                .create_file(p, &metadata, Box::new(Cursor::new(file_data.clone())))
+                .unwrap(),
+            5000
+        );
+        assert_eq!(client.tar_flavor(), None);
+        assert_eq!(
+            client.stat(p).unwrap().metadata().mode,
+            Some(UnixPex::from(0o600))
+        );
+        let dest = SharedBuffer::default();
+        assert_eq!(client.open_file(p, Box::new(dest.clone())).unwrap(), 5000);
+        assert_eq!(*dest.0.lock().unwrap(), file_data);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_open_files_of_exact_size() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        for size in [0, 1, 511, 512, 513] {
+            let p = PathBuf::from(format!("{size}.bin"));
+            let file_data: Vec<u8> = (0..size).map(|i| (i % 251) as u8 + 1).collect();
+            let metadata = Metadata::default().size(size as u64);
+            assert!(client
+                .create_file(&p, &metadata, Box::new(Cursor::new(file_data.clone())))
+                .is_ok());
+            let dest = SharedBuffer::default();
+            assert_eq!(
+                client.open_file(&p, Box::new(dest.clone())).unwrap(),
+                size as u64
+            );
+            assert_eq!(*dest.0.lock().unwrap(), file_data, "size {size}");
+        }
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_create_empty_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("empty.txt");
+        let metadata = Metadata::default().size(0);
+        assert_eq!(
+            client
+                .create_file(p, &metadata, Box::new(Cursor::new(Vec::new())))
+                .unwrap(),
+            0
+        );
+        let file = client.stat(p).unwrap();
+        assert!(file.is_file());
+        assert_eq!(file.metadata().size, 0);
+        // overwrite a non-empty file with an empty one
+        let file_data = "test data\n";
+        assert!(client
+            .create_file(
+                p,
+                &Metadata::default().size(file_data.len() as u64),
+                Box::new(Cursor::new(file_data.as_bytes()))
+            )
+            .is_ok());
+        assert!(client
+            .create_file(p, &metadata, Box::new(Cursor::new(Vec::new())))
+            .is_ok());
+        assert_eq!(client.stat(p).unwrap().metadata().size, 0);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(all(feature = "integration-tests", feature = "find"))]
+    #[serial]
+    fn should_find_files() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        make_small_tree(&mut client);
+        let wrkdir = client.pwd().unwrap();
+        let mut found = client
+            .find("*.txt")
+            .unwrap()
+            .into_iter()
+            .map(|file| file.path().to_path_buf())
+            .collect::<Vec<PathBuf>>();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![wrkdir.join("tree/a.txt"), wrkdir.join("tree/sub/b.txt")]
+        );
+        let found = client
+            .find_in(Path::new("tree"), "*", Some(FileType::Directory))
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path(), wrkdir.join("tree/sub").as_path());
+        assert!(found[0].is_dir());
+        assert!(client.find_in(Path::new("missing"), "*", None).is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(all(feature = "integration-tests", feature = "find"))]
+    #[serial]
+    fn should_glob_files() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        make_small_tree(&mut client);
+        let wrkdir = client.pwd().unwrap();
+        let mut found = client
+            .glob("tree/**/*.txt")
+            .unwrap()
+            .into_iter()
+            .map(|file| file.path().to_path_buf())
+            .collect::<Vec<PathBuf>>();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![wrkdir.join("tree/a.txt"), wrkdir.join("tree/sub/b.txt")]
+        );
+        let found = client.glob("tree/*/*.txt").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path(), wrkdir.join("tree/sub/b.txt").as_path());
+        assert_eq!(client.glob("tree/a.txt").unwrap().len(), 1);
+        assert!(client.glob("missing/**/*.txt").unwrap().is_empty());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_upload_local_file() {
+        use std::io::Write as _;
+        use std::os::unix::fs::PermissionsExt as _;
+
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let mut local = tempfile::NamedTempFile::new().unwrap();
+        local.write_all(b"test data\n").unwrap();
+        local.flush().unwrap();
+        std::fs::set_permissions(local.path(), std::fs::Permissions::from_mode(0o640)).unwrap();
+
+        let p = Path::new("a.txt");
+        assert_eq!(client.upload(local.path(), p).unwrap(), 10);
+        let file = client.stat(p).unwrap();
+        assert_eq!(file.metadata().size, 10);
+        assert_eq!(file.metadata().mode, Some(UnixPex::from(0o640)));
+        // directories can't be uploaded
+        assert!(client
+            .upload(local.path().parent().unwrap(), Path::new("b.txt"))
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_download_to_local_file() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("a.sh");
+        let file_data = "echo test\n";
+        let metadata = Metadata::default()
+            .size(file_data.len() as u64)
+            .mode(UnixPex::from(0o751));
+        assert!(client
+            .create_file(p, &metadata, Box::new(Cursor::new(file_data.as_bytes())))
+            .is_ok());
+        let remote_mode = client.stat(p).unwrap().metadata().mode.unwrap();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let local = tempdir.path().join("a.sh");
+        assert_eq!(client.download(p, local.as_path()).unwrap(), 10);
+        assert_eq!(std::fs::read_to_string(&local).unwrap(), file_data);
+        let local_mode = std::fs::metadata(&local).unwrap().permissions().mode();
+        assert_eq!(UnixPex::from(local_mode), remote_mode);
+        // nothing is left locally on failure, and existing files are kept
+        let missing = tempdir.path().join("b.sh");
+        assert!(client
+            .download(Path::new("b.sh"), missing.as_path())
+            .is_err());
+        assert!(!missing.exists());
+        assert!(client.download(Path::new("b.sh"), local.as_path()).is_err());
+        assert_eq!(std::fs::read_to_string(&local).unwrap(), file_data);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_pipe_file_through_reader() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("a.bin");
+        let file_data: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client
+            .create_file(p, &metadata, Box::new(Cursor::new(file_data.clone())))
+            .is_ok());
+
+        let mut reader = client.open_reader(p).unwrap();
+        let mut piped = Vec::new();
+        assert_eq!(std::io::copy(&mut reader, &mut piped).unwrap(), 100_000);
+        assert_eq!(piped, file_data);
+        assert!(client.open_reader(Path::new("missing.bin")).is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_transfer_compressed_file() {
+        crate::log_init();
+        let (pods, client) = setup_client();
+        let mut client = client.compress(true);
+        let p = Path::new("a.txt");
+        let file_data = "compress me please\n".repeat(10_000);
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert_eq!(
+            client
+                .create_file(p, &metadata, Box::new(Cursor::new(file_data.clone())))
+                .unwrap(),
+            file_data.len() as u64
+        );
+        assert!(client.gzip());
+        assert_eq!(
+            client.stat(p).unwrap().metadata().size,
+            file_data.len() as u64
+        );
+        let mut downloaded = String::new();
+        client
+            .open_reader(p)
+            .unwrap()
+            .read_to_string(&mut downloaded)
+            .unwrap();
+        assert_eq!(downloaded, file_data);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_read_proc_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("/proc/self/status");
+        let mut status = String::new();
+        client
+            .open_reader(p)
+            .unwrap()
+            .read_to_string(&mut status)
+            .unwrap();
+        assert!(status.starts_with("Name:"));
+        assert!(status.contains("Pid:"));
+        // through open_file too
+        let size = client
+            .open_file_limited(p, Box::new(Vec::new()), 1 << 20)
+            .unwrap();
+        assert!(size > 0);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_print_working_directory() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        assert!(client.pwd().is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_remove_dir_all() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create dir
+        let mut dir_path = client.pwd().ok().unwrap();
+        dir_path.push(Path::new("test/"));
         assert!(client
-            .open_file(Path::new("/tmp/aashafb/hhh"), buffer)
+            .create_dir(dir_path.as_path(), UnixPex::from(0o775))
+            .is_ok());
+        // Create file
+        let mut file_path = dir_path.clone();
+        file_path.push(Path::new("a.txt"));
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client
+            .create_file(file_path.as_path(), &metadata, Box::new(reader))
+            .is_ok());
+        // Remove dir
+        assert!(client.remove_dir_all(dir_path.as_path()).is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_remove_dir_all() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Remove dir
+        assert!(client
+            .remove_dir_all(Path::new("/tmp/aaaaaa/asuhi"))
+            .is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_remove_dir() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create dir
+        let mut dir_path = client.pwd().ok().unwrap();
+        dir_path.push(Path::new("test/"));
+        assert!(client
+            .create_dir(dir_path.as_path(), UnixPex::from(0o775))
+            .is_ok());
+        assert!(client.remove_dir(dir_path.as_path()).is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_remove_dir() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create dir
+        let mut dir_path = client.pwd().ok().unwrap();
+        dir_path.push(Path::new("test/"));
+        assert!(client
+            .create_dir(dir_path.as_path(), UnixPex::from(0o775))
+            .is_ok());
+        // Create file
+        let mut file_path = dir_path.clone();
+        file_path.push(Path::new("a.txt"));
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client
+            .create_file(file_path.as_path(), &metadata, Box::new(reader))
+            .is_ok());
+        // Remove dir
+        assert!(client.remove_dir(dir_path.as_path()).is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_remove_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        assert!(client.remove_file(p).is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_setstat_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.sh");
+        let file_data = "echo 5\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+
+        assert!(client
+            .setstat(
+                p,
+                Metadata {
+                    accessed: Some(SystemTime::UNIX_EPOCH),
+                    created: None,
+                    file_type: FileType::File,
+                    gid: Some(1000),
+                    mode: Some(UnixPex::from(0o755)),
+                    modified: Some(SystemTime::UNIX_EPOCH),
+                    size: 7,
+                    symlink: None,
+                    uid: Some(1000),
+                }
+            )
+            .is_ok());
+        let entry = client.stat(p).ok().unwrap();
+        let stat = entry.metadata();
+        assert_eq!(stat.accessed, None);
+        assert_eq!(stat.created, None);
+        assert_eq!(stat.modified, Some(SystemTime::UNIX_EPOCH));
+        assert_eq!(stat.mode.unwrap(), UnixPex::from(0o755));
+        assert_eq!(stat.size, 7);
+
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_setstat_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("bbbbb/cccc/a.sh");
+        assert!(client
+            .setstat(
+                p,
+                Metadata {
+                    accessed: None,
+                    created: None,
+                    file_type: FileType::File,
+                    gid: Some(1),
+                    mode: Some(UnixPex::from(0o755)),
+                    modified: None,
+                    size: 7,
+                    symlink: None,
+                    uid: Some(1),
+                }
+            )
             .is_err());
         finalize_client(pods, client);
     }
@@ -1222,287 +6119,1103 @@ mod test {
     #[test]
     #[cfg(feature = "integration-tests")]
     #[serial]
-    fn should_print_working_directory() {
+    fn should_stat_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.sh");
+        let file_data = "echo 5\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert_eq!(
+            client
+                .create_file(p, &metadata, Box::new(reader))
+                .ok()
+                .unwrap(),
+            7
+        );
+        let entry = client.stat(p).ok().unwrap();
+        assert_eq!(entry.name(), "a.sh");
+        let mut expected_path = client.pwd().ok().unwrap();
+        expected_path.push("a.sh");
+        assert_eq!(entry.path(), expected_path.as_path());
+        let meta = entry.metadata();
+        assert_eq!(meta.size, 7);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_stat_directory() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let p = Path::new("mydir");
+        assert!(client.create_dir(p, UnixPex::from(0o755)).is_ok());
+        let entry = client.stat(p).ok().unwrap();
+        assert_eq!(entry.name(), "mydir");
+        assert!(entry.is_dir());
+        let mut expected_path = client.pwd().ok().unwrap();
+        expected_path.push("mydir");
+        assert_eq!(entry.path(), expected_path.as_path());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_stat_file() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.sh");
+        assert!(client.stat(p).is_err());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_make_symlink() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        // Create file
+        let p = Path::new("a.sh");
+        let file_data = "echo 5\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let mut metadata = Metadata::default();
+        metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        let symlink = Path::new("b.sh");
+        assert!(client.symlink(symlink, p).is_ok());
+        assert!(client.remove_file(symlink).is_ok());
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_canonicalize_symlink_chain() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let wrkdir = client.pwd().unwrap();
+        // Create file
+        let p = Path::new("a.sh");
+        let file_data = "echo 5\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        // b.sh -> a.sh; c.sh -> b.sh
+        assert!(client.symlink(Path::new("b.sh"), p).is_ok());
+        assert!(client.symlink(Path::new("c.sh"), Path::new("b.sh")).is_ok());
+        let mut expected = wrkdir.clone();
+        expected.push("a.sh");
+        assert_eq!(client.canonicalize(Path::new("c.sh")).unwrap(), expected);
+        assert_eq!(client.canonicalize(Path::new("b.sh")).unwrap(), expected);
+        assert_eq!(client.canonicalize(Path::new("./a.sh")).unwrap(), expected);
+        assert_eq!(client.canonicalize(Path::new(".")).unwrap(), wrkdir);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    #[serial]
+    fn should_not_canonicalize_unexisting_path() {
         crate::log_init();
         let (pods, mut client) = setup_client();
-        assert!(client.pwd().is_ok());
+        assert_eq!(
+            client
+                .canonicalize(Path::new("/tmp/aaaaaa/bbbb"))
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
         finalize_client(pods, client);
     }
 
     #[test]
     #[cfg(feature = "integration-tests")]
     #[serial]
-    fn should_remove_dir_all() {
+    fn should_not_make_symlink() {
         crate::log_init();
         let (pods, mut client) = setup_client();
-        // Create dir
-        let mut dir_path = client.pwd().ok().unwrap();
-        dir_path.push(Path::new("test/"));
-        assert!(client
-            .create_dir(dir_path.as_path(), UnixPex::from(0o775))
-            .is_ok());
         // Create file
-        let mut file_path = dir_path.clone();
-        file_path.push(Path::new("a.txt"));
-        let file_data = "test data\n";
+        let p = Path::new("a.sh");
+        let file_data = "echo 5\n";
         let reader = Cursor::new(file_data.as_bytes());
         let mut metadata = Metadata::default();
         metadata.size = file_data.len() as u64;
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        let symlink = Path::new("b.sh");
+        let file_data = "echo 5\n";
+        let reader = Cursor::new(file_data.as_bytes());
         assert!(client
-            .create_file(file_path.as_path(), &metadata, Box::new(reader))
+            .create_file(symlink, &metadata, Box::new(reader))
             .is_ok());
-        // Remove dir
-        assert!(client.remove_dir_all(dir_path.as_path()).is_ok());
+        assert!(client.symlink(symlink, p).is_err());
+        assert!(client.remove_file(symlink).is_ok());
+        assert!(client.symlink(symlink, Path::new("c.sh")).is_err());
         finalize_client(pods, client);
     }
 
     #[test]
     #[cfg(feature = "integration-tests")]
     #[serial]
-    fn should_not_remove_dir_all() {
+    fn should_switch_container() {
         crate::log_init();
         let (pods, mut client) = setup_client();
-        // Remove dir
-        assert!(client
-            .remove_dir_all(Path::new("/tmp/aaaaaa/asuhi"))
-            .is_err());
+        let pod_name = client.pod_name.clone();
+        assert!(client.switch(&pod_name, "sidecar").is_ok());
+        assert_eq!(client.container.as_str(), "sidecar");
+        assert_eq!(client.pwd().unwrap().as_path(), Path::new("/"));
+        assert_eq!(
+            client.exec("echo 5").ok().unwrap(),
+            (0, String::from("5\n"))
+        );
+        assert!(client.switch(&pod_name, "alpine").is_ok());
+        assert_eq!(client.container.as_str(), "alpine");
         finalize_client(pods, client);
     }
 
     #[test]
     #[cfg(feature = "integration-tests")]
     #[serial]
-    fn should_remove_dir() {
+    fn should_not_switch_to_unexisting_container() {
         crate::log_init();
         let (pods, mut client) = setup_client();
-        // Create dir
-        let mut dir_path = client.pwd().ok().unwrap();
-        dir_path.push(Path::new("test/"));
-        assert!(client
-            .create_dir(dir_path.as_path(), UnixPex::from(0o775))
-            .is_ok());
-        assert!(client.remove_dir(dir_path.as_path()).is_ok());
+        let pod_name = client.pod_name.clone();
+        let wrkdir = client.pwd().unwrap();
+        assert!(client.switch(&pod_name, "unexisting").is_err());
+        assert!(client.switch("unexisting-pod", "alpine").is_err());
+        assert_eq!(client.container.as_str(), "alpine");
+        assert_eq!(client.pwd().unwrap(), wrkdir);
         finalize_client(pods, client);
     }
 
     #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_remove_dir() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create dir
-        let mut dir_path = client.pwd().ok().unwrap();
-        dir_path.push(Path::new("test/"));
-        assert!(client
-            .create_dir(dir_path.as_path(), UnixPex::from(0o775))
-            .is_ok());
-        // Create file
-        let mut file_path = dir_path.clone();
-        file_path.push(Path::new("a.txt"));
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client
-            .create_file(file_path.as_path(), &metadata, Box::new(reader))
-            .is_ok());
-        // Remove dir
-        assert!(client.remove_dir(dir_path.as_path()).is_err());
-        finalize_client(pods, client);
+    fn should_timeout_connection_to_unreachable_server() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let config = Config {
+            accept_invalid_certs: true,
+            ..Config::new("https://10.255.255.1:8443".parse().unwrap())
+        };
+        let mut client = KubeContainerFs::new("test", "test", &rt)
+            .config(config)
+            .connect_timeout(Duration::from_secs(1));
+        let started = std::time::Instant::now();
+        assert_eq!(
+            client.connect().err().unwrap().kind,
+            RemoteErrorType::ConnectionError
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(client.pods.is_none());
+    }
+
+    #[test]
+    fn should_reset_detected_properties_when_switching_container() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut client = KubeContainerFs::new("test", "alpine", &rt);
+        client.tar_flavor.set(Some(TarFlavor::Busybox)).unwrap();
+        client.gzip_available.set(true).unwrap();
+        client.ls_literal.set(true).unwrap();
+        client.umask.set(0o022).unwrap();
+
+        // same target: nothing is reset
+        let prev = client.set_target("test".to_string(), "alpine".to_string());
+        assert_eq!(prev, ("test".to_string(), "alpine".to_string()));
+        assert_eq!(client.tar_flavor.get(), Some(&Some(TarFlavor::Busybox)));
+        assert_eq!(client.gzip_available.get(), Some(&true));
+        assert_eq!(client.ls_literal.get(), Some(&true));
+        assert_eq!(client.umask.get(), Some(&0o022));
+
+        // other container
+        let prev = client.set_target("test".to_string(), "debian".to_string());
+        assert_eq!(prev, ("test".to_string(), "alpine".to_string()));
+        assert_eq!(client.container, "debian");
+        assert!(client.tar_flavor.get().is_none());
+        assert!(client.gzip_available.get().is_none());
+        assert!(client.ls_literal.get().is_none());
+        assert!(client.umask.get().is_none());
+        client.tar_flavor.set(Some(TarFlavor::Gnu)).unwrap();
+        client.gzip_available.set(false).unwrap();
+        client.ls_literal.set(false).unwrap();
+        client.umask.set(0o002).unwrap();
+
+        // back to the first one
+        let prev = client.set_target("test".to_string(), "alpine".to_string());
+        assert_eq!(prev, ("test".to_string(), "debian".to_string()));
+        assert_eq!(client.container, "alpine");
+        assert!(client.tar_flavor.get().is_none());
+        assert!(client.gzip_available.get().is_none());
+        assert!(client.ls_literal.get().is_none());
+        assert!(client.umask.get().is_none());
+    }
+
+    #[test]
+    fn should_cancel_operation_promptly() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        // without a token the operation completes
+        assert_eq!(rt.block_on(client.cancellable(async { Ok(5) })).unwrap(), 5);
+
+        let token = CancellationToken::new();
+        let client = client.cancellation_token(token.clone());
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            token.cancel();
+        });
+        let started = std::time::Instant::now();
+        let err = rt
+            .block_on(client.cancellable(std::future::pending::<RemoteResult<()>>()))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::ProtocolError);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        // following operations fail immediately
+        assert!(rt.block_on(client.cancellable(async { Ok(5) })).is_err());
+    }
+
+    #[test]
+    fn should_replace_local_file_only_on_success() {
+        use std::io::Write as _;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("a.txt");
+        std::fs::write(&path, "old").unwrap();
+        let err = replace_local_file(&path, |file| {
+            file.write_all(b"partial").unwrap();
+            Err::<(), _>(RemoteError::new(RemoteErrorType::ProtocolError))
+        })
+        .unwrap_err();
+        assert_eq!(err.kind, RemoteErrorType::ProtocolError);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 1);
+
+        assert_eq!(
+            replace_local_file(&path, |file| {
+                file.write_all(b"new").unwrap();
+                Ok(3)
+            })
+            .unwrap(),
+            3
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn should_propagate_errors_of_shell_commands() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let token = CancellationToken::new();
+        token.cancel();
+        let client = KubeContainerFs::new("test", "test", &rt).cancellation_token(token);
+        let path = Path::new("/tmp");
+        for err in [
+            client.stat_abs(path).err().unwrap(),
+            client.exists_abs(path).err().unwrap(),
+            client.ls(path, "-l").err().unwrap(),
+            client
+                .assert_stat_command("true".to_string())
+                .err()
+                .unwrap(),
+        ] {
+            assert_eq!(err.kind, RemoteErrorType::ProtocolError);
+            assert_eq!(KubeFsError::from_remote(&err), Some(KubeFsError::Cancelled));
+        }
+    }
+
+    #[test]
+    fn should_load_config_from_kubeconfig_var() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let tempdir = tempfile::tempdir().unwrap();
+        let cluster = tempdir.path().join("cluster.yaml");
+        std::fs::write(
+            &cluster,
+            r#"apiVersion: v1
+kind: Config
+clusters:
+  - name: kind
+    cluster:
+      server: https://127.0.0.1:42000
+      insecure-skip-tls-verify: true
+contexts:
+  - name: kind
+    context:
+      cluster: kind
+      user: kind
+      namespace: integration
+current-context: kind
+"#,
+        )
+        .unwrap();
+        let user = tempdir.path().join("user.yaml");
+        std::fs::write(
+            &user,
+            r#"apiVersion: v1
+kind: Config
+users:
+  - name: kind
+    user:
+      token: my-token
+"#,
+        )
+        .unwrap();
+
+        let var = std::env::join_paths([cluster.as_path(), user.as_path()]).unwrap();
+        let config = rt
+            .block_on(KubeContainerFs::config_from_kubeconfig_var(Some(var)))
+            .unwrap();
+        assert_eq!(config.cluster_url.to_string(), "https://127.0.0.1:42000/");
+        assert_eq!(config.default_namespace.as_str(), "integration");
+        assert!(config.accept_invalid_certs);
+        assert!(config.auth_info.token.is_some());
+
+        // missing file
+        let var = tempdir.path().join("missing.yaml").into_os_string();
+        assert_eq!(
+            rt.block_on(KubeContainerFs::config_from_kubeconfig_var(Some(var)))
+                .unwrap_err()
+                .kind,
+            RemoteErrorType::ConnectionError
+        );
+    }
+
+    #[test]
+    fn should_load_kubeconfig_from_path() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut kubeconfig = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut kubeconfig,
+            br#"apiVersion: v1
+kind: Config
+clusters:
+  - name: test
+    cluster:
+      server: https://10.20.30.40:6443
+      insecure-skip-tls-verify: true
+contexts:
+  - name: test
+    context:
+      cluster: test
+      user: test
+      namespace: my-namespace
+current-context: test
+users:
+  - name: test
+    user:
+      token: my-token
+"#,
+        )
+        .unwrap();
+        let client = KubeContainerFs::new("test", "test", &rt)
+            .kubeconfig_path(kubeconfig.path().to_path_buf());
+        let config = rt.block_on(client.default_config()).unwrap();
+        assert_eq!(config.cluster_url.to_string(), "https://10.20.30.40:6443/");
+        assert_eq!(config.default_namespace.as_str(), "my-namespace");
+        assert!(config.accept_invalid_certs);
+
+        // missing file
+        let mut client = KubeContainerFs::new("test", "test", &rt)
+            .kubeconfig_path(PathBuf::from("/tmp/this/kubeconfig/doesnt/exist"));
+        assert_eq!(
+            client.connect().err().unwrap().kind,
+            RemoteErrorType::ConnectionError
+        );
+        // invalid file
+        let mut invalid = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut invalid, b"clusters: [[[").unwrap();
+        let mut client =
+            KubeContainerFs::new("test", "test", &rt).kubeconfig_path(invalid.path().to_path_buf());
+        assert_eq!(
+            client.connect().err().unwrap().kind,
+            RemoteErrorType::ConnectionError
+        );
+    }
+
+    #[test]
+    fn should_reuse_shared_client() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut config = Config::new("https://10.20.30.40:6443".parse().unwrap());
+        config.default_namespace = "shared-namespace".to_string();
+        let shared = SharedKubeClient::new(config, &rt).unwrap();
+
+        // the clients never load their own configuration, which is missing here
+        let clients: Vec<KubeContainerFs> = ["a", "b"]
+            .into_iter()
+            .map(|pod| {
+                KubeContainerFs::from_shared_client(pod, "alpine", &shared, &rt)
+                    .kubeconfig_path(PathBuf::from("/tmp/this/kubeconfig/doesnt/exist"))
+            })
+            .collect();
+        for client in clients.iter() {
+            let kube_client = rt.block_on(client.build_client()).unwrap();
+            assert_eq!(kube_client.default_namespace(), "shared-namespace");
+        }
+        // without the shared client, the configuration is loaded
+        let client = KubeContainerFs::new("a", "alpine", &rt)
+            .kubeconfig_path(PathBuf::from("/tmp/this/kubeconfig/doesnt/exist"));
+        assert!(rt.block_on(client.build_client()).is_err());
+    }
+
+    #[test]
+    fn should_merge_kubeconfig_files() {
+        let mut first = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut first,
+            br#"apiVersion: v1
+kind: Config
+clusters:
+  - name: dev
+    cluster:
+      server: https://dev.example.com:6443
+contexts:
+  - name: dev
+    context:
+      cluster: dev
+      user: dev
+current-context: dev
+users:
+  - name: dev
+    user:
+      token: dev-token
+"#,
+        )
+        .unwrap();
+        let mut second = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut second,
+            br#"apiVersion: v1
+kind: Config
+clusters:
+  - name: dev
+    cluster:
+      server: https://other.example.com:6443
+  - name: prod
+    cluster:
+      server: https://prod.example.com:6443
+contexts:
+  - name: prod
+    context:
+      cluster: prod
+      user: prod
+current-context: prod
+users:
+  - name: prod
+    user:
+      token: prod-token
+"#,
+        )
+        .unwrap();
+
+        let kubeconfig = KubeContainerFs::read_kubeconfigs(&[
+            first.path().to_path_buf(),
+            second.path().to_path_buf(),
+        ])
+        .unwrap();
+        assert_eq!(
+            kubeconfig
+                .contexts
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["dev", "prod"]
+        );
+        // first file wins
+        assert_eq!(kubeconfig.current_context.as_deref(), Some("dev"));
+        assert_eq!(kubeconfig.clusters.len(), 2);
+        assert_eq!(
+            kubeconfig.clusters[0]
+                .cluster
+                .as_ref()
+                .and_then(|c| c.server.as_deref()),
+            Some("https://dev.example.com:6443")
+        );
+
+        assert!(KubeContainerFs::read_kubeconfigs(&[]).is_err());
+    }
+
+    #[test]
+    fn should_pass_root_cert_to_connector() {
+        use crate::utils::tls::TEST_CERT_PEM;
+
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let addr = spawn_tls_server(&rt);
+        let config = Config::new(format!("https://{addr}").parse().unwrap());
+        let request = || http::Request::get("/version").body(Vec::new()).unwrap();
+        // the server certificate isn't trusted without the root cert
+        let client = KubeContainerFs::new("test", "test", &rt).config(config.clone());
+        let kube_client = rt.block_on(client.build_client()).unwrap();
+        assert!(rt.block_on(kube_client.request_text(request())).is_err());
+
+        let client = KubeContainerFs::new("test", "test", &rt)
+            .config(config)
+            .ca_cert(TEST_CERT_PEM.as_bytes().to_vec())
+            .unwrap();
+        let kube_client = rt.block_on(client.build_client()).unwrap();
+        assert_eq!(
+            rt.block_on(kube_client.request_text(request())).unwrap(),
+            "ok"
+        );
+        // the connector with keepalive trusts it too
+        let client = client.transfer_keepalive(Duration::from_secs(30));
+        let kube_client = rt.block_on(client.build_client()).unwrap();
+        assert_eq!(
+            rt.block_on(kube_client.request_text(request())).unwrap(),
+            "ok"
+        );
+    }
+
+    #[test]
+    fn should_not_build_client_with_invalid_root_cert() {
+        use kube::client::ConfigExt as _;
+
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let config = Config {
+            root_cert: Some(vec![b"not a certificate".to_vec()]),
+            ..Config::new("https://127.0.0.1:8443".parse().unwrap())
+        };
+        assert!(config.rustls_client_config().is_err());
+        let client = KubeContainerFs::new("test", "test", &rt).config(config);
+        let err = rt.block_on(client.build_client()).err().unwrap();
+        assert_eq!(err.kind, RemoteErrorType::ConnectionError);
+    }
+
+    #[test]
+    fn should_set_client_cert() {
+        use crate::utils::tls::{TEST_CERT_PEM, TEST_KEY_PEM};
+
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt)
+            .config(Config::new("https://127.0.0.1:8443".parse().unwrap()))
+            .client_cert(
+                TEST_CERT_PEM.as_bytes().to_vec(),
+                TEST_KEY_PEM.as_bytes().to_vec(),
+            )
+            .unwrap()
+            .ca_cert(TEST_CERT_PEM.as_bytes().to_vec())
+            .unwrap();
+        let config = client.config.as_ref().unwrap();
+        assert_eq!(
+            BASE64_STANDARD
+                .decode(config.auth_info.client_certificate_data.as_ref().unwrap())
+                .unwrap(),
+            TEST_CERT_PEM.as_bytes()
+        );
+        assert!(config.auth_info.client_key_data.is_some());
+        assert_eq!(config.root_cert.as_ref().unwrap().len(), 1);
+        // config must be usable to build the client
+        assert!(rt.block_on(client.build_client()).is_ok());
+    }
+
+    #[test]
+    fn should_override_cluster_url() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut kubeconfig = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut kubeconfig,
+            br#"apiVersion: v1
+kind: Config
+clusters:
+  - name: test
+    cluster:
+      server: https://10.20.30.40:6443
+contexts:
+  - name: test
+    context:
+      cluster: test
+      user: test
+      namespace: my-namespace
+current-context: test
+users:
+  - name: test
+    user:
+      token: my-token
+"#,
+        )
+        .unwrap();
+        let client = KubeContainerFs::new("test", "test", &rt)
+            .kubeconfig_path(kubeconfig.path().to_path_buf())
+            .cluster_url("https://127.0.0.1:8443", true)
+            .unwrap();
+        let config = client.config.as_ref().unwrap();
+        assert_eq!(config.cluster_url.to_string(), "https://127.0.0.1:8443/");
+        assert!(config.accept_invalid_certs);
+        // the rest of the default config is kept
+        assert_eq!(config.default_namespace.as_str(), "my-namespace");
+        assert!(config.auth_info.token.is_some());
+
+        // explicit config
+        let client = KubeContainerFs::new("test", "test", &rt)
+            .config(Config::new("https://10.20.30.40:6443".parse().unwrap()))
+            .cluster_url("https://192.168.49.2:8443", false)
+            .unwrap();
+        let config = client.config.as_ref().unwrap();
+        assert_eq!(config.cluster_url.to_string(), "https://192.168.49.2:8443/");
+        assert!(!config.accept_invalid_certs);
+
+        // invalid url
+        assert_eq!(
+            KubeContainerFs::new("test", "test", &rt)
+                .config(Config::new("https://10.20.30.40:6443".parse().unwrap()))
+                .cluster_url("not a url", true)
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::BadAddress
+        );
+    }
+
+    #[test]
+    fn should_not_set_invalid_client_cert() {
+        use crate::utils::tls::{TEST_CERT_PEM, TEST_KEY_PEM};
+
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let new_client = || {
+            KubeContainerFs::new("test", "test", &rt)
+                .config(Config::new("https://127.0.0.1:8443".parse().unwrap()))
+        };
+        assert_eq!(
+            new_client()
+                .client_cert(b"bad".to_vec(), TEST_KEY_PEM.as_bytes().to_vec())
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::SslError
+        );
+        assert!(new_client()
+            .client_cert(
+                TEST_CERT_PEM.as_bytes().to_vec(),
+                TEST_CERT_PEM.as_bytes().to_vec()
+            )
+            .is_err());
+        assert!(new_client().ca_cert(b"bad".to_vec()).is_err());
+    }
+
+    #[test]
+    fn should_refresh_auth_preserving_session() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut client = KubeContainerFs::new("test", "test", &rt)
+            .config(Config::new("https://127.0.0.1:8443".parse().unwrap()));
+        assert_eq!(
+            client.refresh_auth().err().unwrap().kind,
+            RemoteErrorType::NotConnected
+        );
+        let api =
+            rt.block_on(async { Api::default_namespaced(client.build_client().await.unwrap()) });
+        client.pods = Some(api);
+        client.wrkdir = PathBuf::from("/tmp");
+        assert!(client.refresh_auth().is_ok());
+        assert!(client.pods.is_some());
+        assert_eq!(client.pod_name.as_str(), "test");
+        assert_eq!(client.container.as_str(), "test");
+        assert_eq!(client.wrkdir.as_path(), Path::new("/tmp"));
+    }
+
+    #[test]
+    fn should_make_copy_cmd() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        assert_eq!(
+            client.copy_cmd("cp -rf", Path::new("/tmp/a.txt"), Path::new("/tmp/b/c.txt")),
+            r#"cp -rf "/tmp/a.txt" "/tmp/b/c.txt""#
+        );
+        let client = client.create_parents(true);
+        assert_eq!(
+            client.copy_cmd("mv -f", Path::new("/tmp/a.txt"), Path::new("/tmp/b/c.txt")),
+            r#"mkdir -p "/tmp/b" && mv -f "/tmp/a.txt" "/tmp/b/c.txt""#
+        );
+        let client = client.create_parents(false).overwrite(false);
+        assert_eq!(
+            client.copy_cmd("cp -rf", Path::new("/tmp/a.txt"), Path::new("/tmp/c.txt")),
+            r#"if [ -e "/tmp/c.txt" ] || [ -L "/tmp/c.txt" ]; then (exit 17); else cp -rf "/tmp/a.txt" "/tmp/c.txt"; fi"#
+        );
+    }
+
+    #[test]
+    fn should_set_transfer_block_size() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        assert_eq!(client.transfer_block_size, 65536);
+        assert_eq!(client.transfer_block_size(4096).transfer_block_size, 4096);
+        let client = KubeContainerFs::new("test", "test", &rt);
+        assert_eq!(client.transfer_block_size(1000).transfer_block_size, 1024);
+        let client = KubeContainerFs::new("test", "test", &rt);
+        assert_eq!(client.transfer_block_size(0).transfer_block_size, 512);
     }
 
     #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_remove_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.txt");
-        let file_data = "test data\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        assert!(client.remove_file(p).is_ok());
-        finalize_client(pods, client);
+    fn should_get_name_and_link() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        assert_eq!(
+            client.get_name_and_link("Cargo.toml"),
+            (String::from("Cargo.toml"), None)
+        );
+        assert_eq!(
+            client.get_name_and_link("Cargo -> Cargo.toml"),
+            (String::from("Cargo"), Some(PathBuf::from("Cargo.toml")))
+        );
     }
 
     #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_setstat_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.sh");
-        let file_data = "echo 5\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-
-        assert!(client
-            .setstat(
-                p,
-                Metadata {
-                    accessed: Some(SystemTime::UNIX_EPOCH),
-                    created: None,
-                    file_type: FileType::File,
-                    gid: Some(1000),
-                    mode: Some(UnixPex::from(0o755)),
-                    modified: Some(SystemTime::UNIX_EPOCH),
-                    size: 7,
-                    symlink: None,
-                    uid: Some(1000),
-                }
+    fn should_parse_file_ls_output() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        // File
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "-rw-r--r-- 1 root root  2056 giu 13 21:11 /tmp/Cargo.toml",
             )
-            .is_ok());
-        let entry = client.stat(p).ok().unwrap();
-        let stat = entry.metadata();
-        assert_eq!(stat.accessed, None);
-        assert_eq!(stat.created, None);
-        assert_eq!(stat.modified, Some(SystemTime::UNIX_EPOCH));
-        assert_eq!(stat.mode.unwrap(), UnixPex::from(0o755));
-        assert_eq!(stat.size, 7);
-
-        finalize_client(pods, client);
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "Cargo.toml");
+        assert!(entry.is_file());
+        assert_eq!(entry.path, PathBuf::from("/tmp/Cargo.toml"));
+        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o644_u32);
+        assert_eq!(entry.metadata.size, 2056);
+        assert_eq!(entry.extension().unwrap().as_str(), "toml");
+        assert!(entry.metadata.symlink.is_none());
+        // File (year)
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "-rw-rw-rw- 1 root root  3368 nov  7  2020 CODE_OF_CONDUCT.md",
+            )
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "CODE_OF_CONDUCT.md");
+        assert!(entry.is_file());
+        assert_eq!(entry.path, PathBuf::from("/tmp/CODE_OF_CONDUCT.md"));
+        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o666_u32);
+        assert_eq!(entry.metadata.size, 3368);
+        assert_eq!(entry.extension().unwrap().as_str(), "md");
+        assert!(entry.metadata.symlink.is_none());
     }
 
     #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_setstat_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("bbbbb/cccc/a.sh");
+    fn should_parse_directory_from_ls_output() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        // Directory
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "drwxr-xr-x 1 root root   512 giu 13 21:11 docs",
+            )
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "docs");
+        assert!(entry.is_dir());
+        assert_eq!(entry.path, PathBuf::from("/tmp/docs"));
+        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o755_u32);
+        assert!(entry.metadata.symlink.is_none());
+        // Short metadata
         assert!(client
-            .setstat(
-                p,
-                Metadata {
-                    accessed: None,
-                    created: None,
-                    file_type: FileType::File,
-                    gid: Some(1),
-                    mode: Some(UnixPex::from(0o755)),
-                    modified: None,
-                    size: 7,
-                    symlink: None,
-                    uid: Some(1),
-                }
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "drwxr-xr-x 1 root root   512 giu 13 21:11",
+            )
+            .is_err());
+        // Bad pex
+        assert!(client
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "-rwxr-xr 1 root root   512 giu 13 21:11 ttyS1",
             )
             .is_err());
-        finalize_client(pods, client);
     }
 
     #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_stat_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.sh");
-        let file_data = "echo 5\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert_eq!(
-            client
-                .create_file(p, &metadata, Box::new(reader))
-                .ok()
+    fn should_parse_ls_ld_output() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
                 .unwrap(),
-            7
         );
-        let entry = client.stat(p).ok().unwrap();
-        assert_eq!(entry.name(), "a.sh");
-        let mut expected_path = client.pwd().ok().unwrap();
-        expected_path.push("a.sh");
-        assert_eq!(entry.path(), expected_path.as_path());
-        let meta = entry.metadata();
-        assert_eq!(meta.size, 7);
-        finalize_client(pods, client);
+        let client = KubeContainerFs::new("test", "test", &rt);
+        // `ls -ld` on a file returns the file line
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "-rw-r--r--    1 root     root          7 Nov  5 13:46 /tmp/a.sh",
+            )
+            .ok()
+            .unwrap();
+        assert!(entry.is_file());
+        assert_eq!(entry.path, PathBuf::from("/tmp/a.sh"));
+        assert_eq!(entry.metadata.size, 7);
+        // `ls -ld` on a directory returns the directory itself
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "drwxr-xr-x    2 root     root       4096 Nov  5 13:46 /tmp/docs",
+            )
+            .ok()
+            .unwrap();
+        assert!(entry.is_dir());
+        assert_eq!(entry.path, PathBuf::from("/tmp/docs"));
+        assert_eq!(entry.name().as_str(), "docs");
     }
 
     #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_stat_file() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.sh");
-        assert!(client.stat(p).is_err());
-        finalize_client(pods, client);
+    fn should_parse_special_files_from_ls_output() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        // FIFO
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "prw-r-----    1 root     root           0 Nov  5 13:46 /tmp/fifo",
+            )
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "fifo");
+        assert!(entry.is_file());
+        assert_eq!(entry.path, PathBuf::from("/tmp/fifo"));
+        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o640_u32);
+        assert_eq!(entry.metadata.size, 0);
+        // socket
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/run").as_path(),
+                "srwxrwxrwx    1 root     root           0 Nov  5 13:46 docker.sock",
+            )
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "docker.sock");
+        assert!(entry.is_file());
+        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o777_u32);
+        // block device; busybox prints the major and minor numbers in place of the size
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/dev").as_path(),
+                "brw-rw----    1 root     disk      8,   0 Nov  5 13:46 sda",
+            )
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "sda");
+        assert!(entry.is_file());
+        assert_eq!(entry.metadata.size, 0);
+        // char device
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/dev").as_path(),
+                "crw-rw-rw-    1 root     root      1,   3 Nov  5 13:46 null",
+            )
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "null");
+        assert!(entry.is_file());
+        assert_eq!(entry.metadata.size, 0);
+        assert_eq!(entry.path, PathBuf::from("/dev/null"));
+        // unknown type
+        assert!(client
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "Drwxr-xr-x 1 root root   512 giu 13 21:11 door",
+            )
+            .is_err());
     }
 
     #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_make_symlink() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.sh");
-        let file_data = "echo 5\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        let symlink = Path::new("b.sh");
-        assert!(client.symlink(symlink, p).is_ok());
-        assert!(client.remove_file(symlink).is_ok());
-        finalize_client(pods, client);
+    fn should_parse_symlink_from_ls_output() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        // File
+        let entry = client
+            .parse_ls_output(
+                PathBuf::from("/tmp").as_path(),
+                "lrw-r--r-- 1 root root  2056 giu 13 21:11 Cargo.toml -> Cargo.prod.toml",
+            )
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "Cargo.toml");
+        assert_eq!(entry.path, PathBuf::from("/tmp/Cargo.toml"));
+        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o644_u32);
+        assert_eq!(entry.metadata.size, 2056);
+        assert_eq!(entry.extension().unwrap().as_str(), "toml");
+        assert_eq!(
+            entry.metadata.symlink.as_deref().unwrap(),
+            Path::new("Cargo.prod.toml")
+        );
     }
 
     #[test]
-    #[cfg(feature = "integration-tests")]
-    #[serial]
-    fn should_not_make_symlink() {
-        crate::log_init();
-        let (pods, mut client) = setup_client();
-        // Create file
-        let p = Path::new("a.sh");
-        let file_data = "echo 5\n";
-        let reader = Cursor::new(file_data.as_bytes());
-        let mut metadata = Metadata::default();
-        metadata.size = file_data.len() as u64;
-        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
-        let symlink = Path::new("b.sh");
-        let file_data = "echo 5\n";
-        let reader = Cursor::new(file_data.as_bytes());
+    fn should_skip_total_and_dot_entries_in_ls_output() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        let output = "total 12
+drwxr-xr-x 1 root root  4096 Nov  5 16:32 .
+drwxr-xr-x 1 root root  4096 Nov  5 16:32 ..
+drwxr-xr-x 1 root root  4096 Nov  5 16:32 ./
+drwxr-xr-x 1 root root  4096 Nov  5 16:32 ../
+-rw-r--r-- 1 root root  2056 Nov  5 16:32 Cargo.toml
+drwxr-xr-x 1 root root   512 Nov  5 16:32 .config
+";
+        let entries = client.parse_ls_lines(Path::new("/tmp"), output).unwrap();
+        assert_eq!(
+            entries.iter().map(|f| f.name()).collect::<Vec<String>>(),
+            vec!["Cargo.toml", ".config"]
+        );
         assert!(client
-            .create_file(symlink, &metadata, Box::new(reader))
-            .is_ok());
-        assert!(client.symlink(symlink, p).is_err());
-        assert!(client.remove_file(symlink).is_ok());
-        assert!(client.symlink(symlink, Path::new("c.sh")).is_err());
-        finalize_client(pods, client);
+            .parse_ls_lines(Path::new("/tmp"), "total 0\n")
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn should_get_name_and_link() {
+    fn should_fail_on_unparseable_ls_line_in_strict_mode() {
         let rt = Arc::new(
             tokio::runtime::Builder::new_current_thread()
                 .enable_all()
                 .build()
                 .unwrap(),
         );
+        let output = "total 8
+drwxr-xr-x 1 root root  4096 Nov  5 16:32 .
+drwxr-xr-x 1 root root  4096 Nov  5 16:32 ../
+-rw-r--r-- 1 root root  2056 Nov  5 16:32 Cargo.toml
+?????????? ? ?    ?        ?            ? broken
+";
         let client = KubeContainerFs::new("test", "test", &rt);
         assert_eq!(
-            client.get_name_and_link("Cargo.toml"),
-            (String::from("Cargo.toml"), None)
+            client
+                .parse_ls_lines(Path::new("/tmp"), output)
+                .unwrap()
+                .len(),
+            1
         );
+        let client = client.strict_listing(true);
+        let err = client
+            .parse_ls_lines(Path::new("/tmp"), output)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::ProtocolError);
+        assert!(err.to_string().contains("broken"));
+        // dot entries and the total header are fine
+        let output = "total 8
+drwxr-xr-x 1 root root  4096 Nov  5 16:32 ./
+drwxr-xr-x 1 root root  4096 Nov  5 16:32 ..
+-rw-r--r-- 1 root root  2056 Nov  5 16:32 Cargo.toml
+";
         assert_eq!(
-            client.get_name_and_link("Cargo -> Cargo.toml"),
-            (String::from("Cargo"), Some(PathBuf::from("Cargo.toml")))
+            client
+                .parse_ls_lines(Path::new("/tmp"), output)
+                .unwrap()
+                .len(),
+            1
         );
     }
 
     #[test]
-    fn should_parse_file_ls_output() {
+    fn should_parse_empty_directory_ls_output() {
         let rt = Arc::new(
             tokio::runtime::Builder::new_current_thread()
                 .enable_all()
@@ -1510,40 +7223,68 @@ mod test {
                 .unwrap(),
         );
         let client = KubeContainerFs::new("test", "test", &rt);
-        // File
+        for output in [
+            "total 0\n",
+            "total 0",
+            "total 0\r\n",
+            "",
+            "\n",
+            "total 0
+drwxr-xr-x 2 root root  4096 Nov  5 16:32 .
+drwxr-xr-x 1 root root  4096 Nov  5 16:32 ..
+",
+        ] {
+            assert!(
+                client
+                    .parse_ls_lines(Path::new("/tmp"), output)
+                    .unwrap()
+                    .is_empty(),
+                "{output:?} should be empty"
+            );
+        }
+    }
+
+    #[test]
+    fn should_parse_quoted_names_in_ls_output() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        // ls doesn't support the literal quoting style
+        client.ls_literal.set(false).unwrap();
         let entry = client
             .parse_ls_output(
-                PathBuf::from("/tmp").as_path(),
-                "-rw-r--r-- 1 root root  2056 giu 13 21:11 /tmp/Cargo.toml",
+                Path::new("/tmp"),
+                "-rw-r--r-- 1 root root 10 Nov  5 16:32 'my file.txt'",
             )
-            .ok()
             .unwrap();
-        assert_eq!(entry.name().as_str(), "Cargo.toml");
-        assert!(entry.is_file());
-        assert_eq!(entry.path, PathBuf::from("/tmp/Cargo.toml"));
-        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o644_u32);
-        assert_eq!(entry.metadata.size, 2056);
-        assert_eq!(entry.extension().unwrap().as_str(), "toml");
-        assert!(entry.metadata.symlink.is_none());
-        // File (year)
+        assert_eq!(entry.name().as_str(), "my file.txt");
+        assert_eq!(entry.path(), Path::new("/tmp/my file.txt"));
         let entry = client
             .parse_ls_output(
-                PathBuf::from("/tmp").as_path(),
-                "-rw-rw-rw- 1 root root  3368 nov  7  2020 CODE_OF_CONDUCT.md",
+                Path::new("/tmp"),
+                "-rw-r--r-- 1 root root 10 Nov  5 16:32 \"it's.txt\"",
             )
-            .ok()
             .unwrap();
-        assert_eq!(entry.name().as_str(), "CODE_OF_CONDUCT.md");
-        assert!(entry.is_file());
-        assert_eq!(entry.path, PathBuf::from("/tmp/CODE_OF_CONDUCT.md"));
-        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o666_u32);
-        assert_eq!(entry.metadata.size, 3368);
-        assert_eq!(entry.extension().unwrap().as_str(), "md");
-        assert!(entry.metadata.symlink.is_none());
+        assert_eq!(entry.name().as_str(), "it's.txt");
+        let entry = client
+            .parse_ls_output(
+                Path::new("/tmp"),
+                "lrwxrwxrwx 1 root root 10 Nov  5 16:32 'my link' -> 'my file.txt'",
+            )
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "my link");
+        assert_eq!(
+            entry.metadata().symlink.as_deref(),
+            Some(Path::new("my file.txt"))
+        );
     }
 
     #[test]
-    fn should_parse_directory_from_ls_output() {
+    fn should_not_unquote_names_printed_literally_by_ls() {
         let rt = Arc::new(
             tokio::runtime::Builder::new_current_thread()
                 .enable_all()
@@ -1551,105 +7292,534 @@ mod test {
                 .unwrap(),
         );
         let client = KubeContainerFs::new("test", "test", &rt);
-        // Directory
+        client.ls_literal.set(true).unwrap();
         let entry = client
             .parse_ls_output(
-                PathBuf::from("/tmp").as_path(),
-                "drwxr-xr-x 1 root root   512 giu 13 21:11 docs",
+                Path::new("/tmp"),
+                "-rw-r--r-- 1 root root 10 Nov  5 16:32 'a b'",
             )
-            .ok()
             .unwrap();
-        assert_eq!(entry.name().as_str(), "docs");
-        assert!(entry.is_dir());
-        assert_eq!(entry.path, PathBuf::from("/tmp/docs"));
-        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o755_u32);
-        assert!(entry.metadata.symlink.is_none());
-        // Short metadata
+        assert_eq!(entry.name().as_str(), "'a b'");
+        assert_eq!(entry.path(), Path::new("/tmp/'a b'"));
+        let entry = client
+            .parse_ls_output(
+                Path::new("/tmp"),
+                "lrwxrwxrwx 1 root root 10 Nov  5 16:32 \"link\" -> 'a b'",
+            )
+            .unwrap();
+        assert_eq!(entry.name().as_str(), "\"link\"");
+        assert_eq!(
+            entry.metadata().symlink.as_deref(),
+            Some(Path::new("'a b'"))
+        );
+    }
+
+    #[test]
+    fn test_should_parse_special_permissions_ls_output() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
         assert!(client
             .parse_ls_output(
-                PathBuf::from("/tmp").as_path(),
-                "drwxr-xr-x 1 root root   512 giu 13 21:11",
+                Path::new("/tmp"),
+                "-rw-rwSrw-    1 manufact  manufact    241813 Apr 22 09:31 L9800.SPF",
             )
-            .is_err());
-        // Special file
+            .is_ok());
         assert!(client
             .parse_ls_output(
-                PathBuf::from("/tmp").as_path(),
-                "crwxr-xr-x 1 root root   512 giu 13 21:11 ttyS1",
+                Path::new("/tmp"),
+                "-rw-rwsrw-    1 manufact  manufact    241813 Apr 22 09:31 L9800.SPF",
             )
-            .is_err());
-        // Bad pex
+            .is_ok());
+
         assert!(client
             .parse_ls_output(
-                PathBuf::from("/tmp").as_path(),
-                "-rwxr-xr 1 root root   512 giu 13 21:11 ttyS1",
+                Path::new("/tmp"),
+                "-rw-rwtrw-    1 manufact  manufact    241813 Apr 22 09:31 L9800.SPF",
             )
-            .is_err());
+            .is_ok());
+
+        assert!(client
+            .parse_ls_output(
+                Path::new("/tmp"),
+                "-rw-rwTrw-    1 manufact  manufact    241813 Apr 22 09:31 L9800.SPF",
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn should_resolve_path() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut client = KubeContainerFs::new("test", "test", &rt);
+        assert_eq!(client.resolve(Path::new("a.txt")), PathBuf::from("/a.txt"));
+        client.wrkdir = PathBuf::from("/home/omar");
+        assert_eq!(
+            client.resolve(Path::new("docs/a.txt")),
+            PathBuf::from("/home/omar/docs/a.txt")
+        );
+        assert_eq!(
+            client.resolve(Path::new("/tmp/a.txt")),
+            PathBuf::from("/tmp/a.txt")
+        );
+        // home is expanded once captured
+        assert_eq!(
+            client.resolve(Path::new("~")),
+            PathBuf::from("/home/omar/~")
+        );
+        client.home = Some(PathBuf::from("/root"));
+        assert_eq!(client.resolve(Path::new("~")), PathBuf::from("/root"));
+        assert_eq!(
+            client.resolve(Path::new("~/logs")),
+            PathBuf::from("/root/logs")
+        );
+        assert_eq!(
+            client.resolve(Path::new("a~/b")),
+            PathBuf::from("/home/omar/a~/b")
+        );
+        // a trailing slash doesn't make a different path
+        assert_eq!(
+            client.resolve(Path::new("docs/")).as_os_str(),
+            client.resolve(Path::new("docs")).as_os_str()
+        );
+        assert_eq!(
+            client.resolve(Path::new("~/logs/")).as_os_str(),
+            client.resolve(Path::new("~/logs")).as_os_str()
+        );
+    }
+
+    #[test]
+    fn should_extract_exact_file_size() {
+        for size in [0, 1, 511, 512, 513] {
+            let file_data: Vec<u8> = (0..size).map(|i| (i % 251) as u8 + 1).collect();
+            let data = KubeContainerFs::make_archive(vec![ArchiveEntry {
+                path: PathBuf::from("a.bin"),
+                size: size as u64,
+                mode: None,
+                reader: Box::new(Cursor::new(file_data.clone())),
+            }])
+            .unwrap();
+            let dest = SharedBuffer::default();
+            assert_eq!(
+                KubeContainerFs::extract_file(
+                    Cursor::new(data),
+                    &mut dest.clone(),
+                    Some(size as u64)
+                )
+                .unwrap(),
+                size as u64
+            );
+            assert_eq!(*dest.0.lock().unwrap(), file_data, "size {size}");
+        }
+    }
+
+    #[test]
+    fn should_build_exec_access_review() {
+        let review = exec_access_review("my-namespace", "my-pod");
+        let attributes = review.spec.resource_attributes.unwrap();
+        assert_eq!(attributes.namespace.as_deref(), Some("my-namespace"));
+        assert_eq!(attributes.verb.as_deref(), Some("create"));
+        assert_eq!(attributes.group.as_deref(), Some(""));
+        assert_eq!(attributes.resource.as_deref(), Some("pods"));
+        assert_eq!(attributes.subresource.as_deref(), Some("exec"));
+        assert_eq!(attributes.name.as_deref(), Some("my-pod"));
+        assert!(review.spec.non_resource_attributes.is_none());
+        assert!(review.status.is_none());
+    }
+
+    #[test]
+    fn should_tell_pseudo_files() {
+        assert!(is_pseudo_file(Path::new("/proc/self/status")));
+        assert!(is_pseudo_file(Path::new("/proc")));
+        assert!(is_pseudo_file(Path::new("/sys/fs/cgroup/memory.max")));
+        assert!(!is_pseudo_file(Path::new("/processes/a.txt")));
+        assert!(!is_pseudo_file(Path::new("/system/a.txt")));
+        assert!(!is_pseudo_file(Path::new("/tmp/proc/a.txt")));
+    }
+
+    #[test]
+    fn should_apply_umask_to_modes() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeContainerFs::new("test", "test", &rt);
+        assert_eq!(client.file_mode(None), None);
+        assert_eq!(client.dir_mode(UnixPex::from(0o777)), UnixPex::from(0o777));
+
+        let client = client
+            .default_file_mode(UnixPex::from(0o664))
+            .respect_umask(true);
+        client.umask.set(0o027).unwrap();
+        assert_eq!(client.file_mode(None), Some(UnixPex::from(0o640)));
+        assert_eq!(
+            client.file_mode(Some(UnixPex::from(0o755))),
+            Some(UnixPex::from(0o750))
+        );
+        assert_eq!(client.dir_mode(UnixPex::from(0o777)), UnixPex::from(0o750));
+        let client = KubeContainerFs::new("test", "test", &rt).respect_umask(true);
+        client.umask.set(0o022).unwrap();
+        assert_eq!(client.file_mode(None), Some(UnixPex::from(0o644)));
     }
 
     #[test]
-    fn should_parse_symlink_from_ls_output() {
+    fn should_make_rename_command() {
+        assert_eq!(
+            rename_cmd(Path::new("/tmp/a.txt"), Path::new("/tmp/b.txt")),
+            r#"if [ ! -e "/tmp/a.txt" ] && [ ! -L "/tmp/a.txt" ]; then (exit 2); elif [ -e "/tmp/b.txt" ] || [ -L "/tmp/b.txt" ]; then (exit 17); else mv "/tmp/a.txt" "/tmp/b.txt"; fi"#
+        );
+    }
+
+    #[test]
+    fn should_make_exec_endpoint_request() {
         let rt = Arc::new(
             tokio::runtime::Builder::new_current_thread()
                 .enable_all()
                 .build()
                 .unwrap(),
         );
-        let client = KubeContainerFs::new("test", "test", &rt);
-        // File
-        let entry = client
-            .parse_ls_output(
-                PathBuf::from("/tmp").as_path(),
-                "lrw-r--r-- 1 root root  2056 giu 13 21:11 Cargo.toml -> Cargo.prod.toml",
-            )
-            .ok()
-            .unwrap();
-        assert_eq!(entry.name().as_str(), "Cargo.toml");
-        assert_eq!(entry.path, PathBuf::from("/tmp/Cargo.toml"));
-        assert_eq!(u32::from(entry.metadata.mode.unwrap()), 0o644_u32);
-        assert_eq!(entry.metadata.size, 2056);
-        assert_eq!(entry.extension().unwrap().as_str(), "toml");
+        let _guard = rt.enter();
+        let kube_client =
+            Client::try_from(Config::new("https://127.0.0.1:6443".parse().unwrap())).unwrap();
+        let mut client = KubeContainerFs::new("my-pod", "alpine", &rt);
+        client.pods = Some(Api::namespaced(kube_client, "my-ns"));
+
+        let request = client.exec_endpoint(&["sh", "-c", "echo a b"]).unwrap();
+        assert_eq!(request.method(), http::Method::GET);
         assert_eq!(
-            entry.metadata.symlink.as_deref().unwrap(),
-            Path::new("Cargo.prod.toml")
+            request.uri().to_string(),
+            "/api/v1/namespaces/my-ns/pods/my-pod/exec?&stdin=true&stdout=true&stderr=true&container=alpine&command=sh&command=-c&command=echo+a+b"
         );
+        assert!(request.body().is_empty());
+
+        let client = client.sudo(true);
+        let request = client.exec_endpoint(&["id"]).unwrap();
+        assert!(request
+            .uri()
+            .to_string()
+            .ends_with("&command=sudo&command=-n&command=id"));
     }
 
     #[test]
-    fn test_should_parse_special_permissions_ls_output() {
+    fn should_quote_dir_of_cd_command() {
+        assert_eq!(
+            cd_command(Path::new("/tmp/my dir"), "ls -l"),
+            "cd '/tmp/my dir' && ls -l"
+        );
+        assert_eq!(
+            cd_command(Path::new("/tmp/it's $(id); `id`"), "pwd"),
+            r"cd '/tmp/it'\''s $(id); `id`' && pwd"
+        );
+    }
+
+    #[test]
+    fn should_pass_argv_through_unparsed() {
+        let argv = ["printf", "%s|", "a b", "$HOME", "'q'", "*", "x;y"];
+        let cmd = argv_at(Path::new("/tmp/my dir"), &argv);
+        assert_eq!(cmd[..3], ["/bin/sh", "-c", r#"cd "$0" && exec "$@""#]);
+        assert_eq!(cmd[3], "/tmp/my dir");
+        assert_eq!(cmd[4..], argv);
+        assert_eq!(argv_at(Path::new("/"), &[]).len(), 4);
+    }
+
+    #[test]
+    fn should_prefer_exit_code_from_status() {
+        let failure = Status {
+            status: Some("Failure".to_string()),
+            details: Some(StatusDetails {
+                causes: Some(vec![StatusCause {
+                    reason: Some("ExitCode".to_string()),
+                    message: Some("3".to_string()),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let success = Status {
+            status: Some("Success".to_string()),
+            ..Default::default()
+        };
+        let marker = "<rc:1234>";
+        assert_eq!(
+            shell_exit_code(Some(&failure), b"out\n<rc:1234>3", marker),
+            Some((3, b"out\n".as_slice()))
+        );
+        assert_eq!(
+            shell_exit_code(Some(&success), b"out<rc:1234>0", marker),
+            Some((0, b"out".as_slice()))
+        );
+        // the command exited before the exit code was echoed; its output is kept as is
+        assert_eq!(
+            shell_exit_code(Some(&failure), b"out\n", marker),
+            Some((3, b"out\n".as_slice()))
+        );
+        assert_eq!(
+            shell_exit_code(Some(&failure), b"a;5", marker),
+            Some((3, b"a;5".as_slice()))
+        );
+        // fall back to the echoed exit code
+        assert_eq!(
+            shell_exit_code(None, b"out<rc:1234>2", marker),
+            Some((2, b"out".as_slice()))
+        );
+        let unknown = Status {
+            status: Some("Failure".to_string()),
+            message: Some("error".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            shell_exit_code(Some(&unknown), b"out<rc:1234>4", marker),
+            Some((4, b"out".as_slice()))
+        );
+        assert_eq!(shell_exit_code(None, b"out", marker), None);
+        assert_eq!(shell_exit_code(None, b"out;0", marker), None);
+        assert_eq!(shell_exit_code(Some(&unknown), b"", marker), None);
+    }
+
+    #[test]
+    fn should_split_exit_code_from_output() {
+        let marker = "<rc:1234>";
+        assert_eq!(
+            split_exit_code(b"5\n<rc:1234>0", marker),
+            Some((0, b"5\n".as_slice()))
+        );
+        assert_eq!(
+            split_exit_code(b"a;b;1<rc:1234>127", marker),
+            Some((127, b"a;b;1".as_slice()))
+        );
+        assert_eq!(
+            split_exit_code(b"<rc:1234>1", marker),
+            Some((1, b"".as_slice()))
+        );
+        assert_eq!(split_exit_code(b"", marker), None);
+        assert_eq!(split_exit_code(b"no exit code", marker), None);
+        assert_eq!(split_exit_code(b"a;0", marker), None);
+        assert_eq!(split_exit_code(b"a<rc:1234>\xff", marker), None);
+        assert_ne!(exit_code_marker(), exit_code_marker());
+    }
+
+    #[test]
+    fn should_decode_binary_output_lossily() {
+        let stdout = b"ok: \xff\xfe\x00 caf\xc3\xa9 \xe2\x82<rc:1234>2";
+        let (rc, output) = split_exit_code(stdout, "<rc:1234>").unwrap();
+        assert_eq!(rc, 2);
+        // raw output keeps every byte
+        assert_eq!(output, b"ok: \xff\xfe\x00 caf\xc3\xa9 \xe2\x82");
+        // decoded output keeps ascii and valid sequences, replacing the invalid ones
+        let decoded = decode_output(output);
+        assert_eq!(decoded, "ok: \u{FFFD}\u{FFFD}\0 café \u{FFFD}");
+        assert_ne!(decoded.as_bytes(), output);
+        // valid UTF-8 is the same either way
+        assert_eq!(decode_output(b"5\n").as_bytes(), b"5\n");
+    }
+
+    #[test]
+    fn should_read_archive_entry_without_framing() {
+        for size in [0, 1, 512, 513] {
+            let file_data: Vec<u8> = (0..size).map(|i| (i % 251) as u8 + 1).collect();
+            let data = KubeContainerFs::make_archive(vec![ArchiveEntry {
+                path: PathBuf::from("a.bin"),
+                size: size as u64,
+                mode: None,
+                reader: Box::new(Cursor::new(file_data.clone())),
+            }])
+            .unwrap();
+            let mut archive = tempfile::NamedTempFile::new().unwrap();
+            std::io::Write::write_all(&mut archive, &data).unwrap();
+
+            let mut reader = KubeContainerFs::entry_reader(archive, None).unwrap();
+            let mut piped = Vec::new();
+            assert_eq!(std::io::copy(&mut reader, &mut piped).unwrap(), size as u64);
+            assert_eq!(piped, file_data, "size {size}");
+        }
+    }
+
+    #[test]
+    fn should_create_temp_files_in_temp_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let tempfile = named_tempfile_in(Some(temp_dir.path())).unwrap();
+        assert_eq!(tempfile.path().parent().unwrap(), temp_dir.path());
+        assert!(tempfile_in(Some(temp_dir.path())).is_ok());
+        assert!(tempfile_in(Some(&temp_dir.path().join("missing"))).is_err());
+        assert_eq!(
+            named_tempfile_in(None).unwrap().path().parent().unwrap(),
+            std::env::temp_dir()
+        );
+
         let rt = Arc::new(
             tokio::runtime::Builder::new_current_thread()
                 .enable_all()
                 .build()
                 .unwrap(),
         );
-        let client = KubeContainerFs::new("test", "test", &rt);
-        assert!(client
-            .parse_ls_output(
-                Path::new("/tmp"),
-                "-rw-rwSrw-    1 manufact  manufact    241813 Apr 22 09:31 L9800.SPF",
-            )
-            .is_ok());
-        assert!(client
-            .parse_ls_output(
-                Path::new("/tmp"),
-                "-rw-rwsrw-    1 manufact  manufact    241813 Apr 22 09:31 L9800.SPF",
-            )
-            .is_ok());
+        let client =
+            KubeContainerFs::new("test", "test", &rt).temp_dir(temp_dir.path().to_path_buf());
+        assert_eq!(client.temp_dir.as_deref(), Some(temp_dir.path()));
+    }
 
-        assert!(client
-            .parse_ls_output(
-                Path::new("/tmp"),
-                "-rw-rwtrw-    1 manufact  manufact    241813 Apr 22 09:31 L9800.SPF",
-            )
-            .is_ok());
+    #[test]
+    fn should_read_sparse_archive_entry() {
+        // a 2048 bytes file, with 512 bytes of data at 1024 and holes around
+        let mut header = tar::Header::new_gnu();
+        header.set_path("disk.img").unwrap();
+        header.set_entry_type(tar::EntryType::GNUSparse);
+        header.set_size(512);
+        let gnu = header.as_gnu_mut().unwrap();
+        gnu.sparse[0].set_offset(1024);
+        gnu.sparse[0].set_length(512);
+        // GNU tar ends the map with an empty chunk at the end of the file
+        gnu.sparse[1].set_offset(2048);
+        gnu.sparse[1].set_length(0);
+        gnu.set_real_size(2048);
+        header.set_cksum();
+        let mut data = header.as_bytes().to_vec();
+        data.extend([7; 512]);
+        data.extend([0; 1024]);
+        let mut expected = vec![0; 1024];
+        expected.extend([7; 512]);
+        expected.extend([0; 512]);
+
+        let mut extracted = Vec::new();
+        assert_eq!(
+            KubeContainerFs::extract_file(Cursor::new(&data), &mut extracted, Some(2048)).unwrap(),
+            2048
+        );
+        assert_eq!(extracted, expected);
+
+        let mut archive = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut archive, &data).unwrap();
+        let mut reader = KubeContainerFs::entry_reader(archive, None).unwrap();
+        let mut piped = Vec::new();
+        reader.read_to_end(&mut piped).unwrap();
+        assert_eq!(piped, expected);
+    }
 
-        assert!(client
-            .parse_ls_output(
-                Path::new("/tmp"),
-                "-rw-rwTrw-    1 manufact  manufact    241813 Apr 22 09:31 L9800.SPF",
+    #[test]
+    fn should_not_read_truncated_archive_entry() {
+        let data = KubeContainerFs::make_archive(vec![ArchiveEntry {
+            path: PathBuf::from("a.bin"),
+            size: 1024,
+            mode: None,
+            reader: Box::new(Cursor::new(vec![1; 1024])),
+        }])
+        .unwrap();
+        let mut archive = tempfile::NamedTempFile::new().unwrap();
+        // header and half of the data
+        std::io::Write::write_all(&mut archive, &data[..1024]).unwrap();
+        assert_eq!(
+            KubeContainerFs::entry_reader(archive, None)
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::IoError
+        );
+    }
+
+    #[test]
+    fn should_make_archive_with_empty_entry() {
+        // size is 0, but the reader has data
+        let data = KubeContainerFs::make_archive(vec![ArchiveEntry {
+            path: PathBuf::from("empty.txt"),
+            size: 0,
+            mode: None,
+            reader: Box::new(Cursor::new(b"leftover".to_vec())),
+        }])
+        .unwrap();
+        let mut ar = tar::Archive::new(Cursor::new(data));
+        let entries = ar
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().header().size().unwrap())
+            .collect::<Vec<u64>>();
+        assert_eq!(entries, vec![0]);
+    }
+
+    #[test]
+    fn should_fail_extracting_truncated_archive() {
+        let data = KubeContainerFs::make_archive(vec![ArchiveEntry {
+            path: PathBuf::from("a.bin"),
+            size: 1000,
+            mode: None,
+            reader: Box::new(Cursor::new(vec![1u8; 1000])),
+        }])
+        .unwrap();
+        let err = KubeContainerFs::extract_file(Cursor::new(&data[..1024]), &mut Vec::new(), None)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::IoError);
+    }
+
+    #[test]
+    fn should_classify_cd_errors() {
+        let dir = Path::new("/tmp/a.txt");
+        // busybox
+        assert_eq!(
+            KubeContainerFs::cd_error(
+                dir,
+                "sh: cd: line 1: can't cd to /tmp/a.txt: Not a directory\n"
             )
-            .is_ok());
+            .kind,
+            RemoteErrorType::BadFile
+        );
+        // dash doesn't tell the reason
+        assert_eq!(
+            KubeContainerFs::cd_error(dir, "/bin/sh: 1: cd: can't cd to /tmp/a.txt\n").kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+        // bash
+        let err =
+            KubeContainerFs::cd_error(dir, "/bin/sh: line 1: cd: /tmp/a.txt: Not a directory\n");
+        assert_eq!(err.kind, RemoteErrorType::BadFile);
+        assert_eq!(
+            KubeFsError::from_remote(&err),
+            Some(KubeFsError::NotADirectory)
+        );
+        assert_eq!(
+            KubeContainerFs::cd_error(
+                dir,
+                "sh: cd: line 1: can't cd to /tmp/a.txt: No such file or directory\n"
+            )
+            .kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+    }
+
+    #[test]
+    fn should_set_mode_in_archive() {
+        let data = KubeContainerFs::make_archive(vec![
+            ArchiveEntry {
+                path: PathBuf::from("a.txt"),
+                size: 2,
+                mode: Some(UnixPex::from(0o644)),
+                reader: Box::new(Cursor::new(b"hi".to_vec())),
+            },
+            ArchiveEntry {
+                path: PathBuf::from("b.txt"),
+                size: 0,
+                mode: None,
+                reader: Box::new(Cursor::new(Vec::new())),
+            },
+        ])
+        .unwrap();
+        let mut archive = tar::Archive::new(data.as_slice());
+        // without a mode, the header field is left empty
+        let modes: Vec<Option<u32>> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().header().mode().ok())
+            .collect();
+        assert_eq!(modes, vec![Some(0o644), None]);
     }
 
     #[test]
@@ -1666,6 +7836,89 @@ mod test {
             .copy(Path::new("/nowhere"), PathBuf::from("/culonia").as_path())
             .is_err());
         assert!(client.exec("echo 5").is_err());
+        assert!(client.exec_checked("echo 5").is_err());
+        assert!(client.exec_in(Path::new("/tmp"), "echo 5").is_err());
+        assert!(client.home_dir().is_err());
+        assert!(client
+            .open_file_limited(Path::new("/tmp/a.txt"), Box::new(Vec::new()), 1024)
+            .is_err());
+        assert!(client
+            .upload(Path::new("/nowhere/a.txt"), Path::new("/tmp/a.txt"))
+            .is_err());
+        assert!(client
+            .download(Path::new("/tmp/a.txt"), Path::new("/nowhere/a.txt"))
+            .is_err());
+        assert!(client.open_reader(Path::new("/tmp/a.txt")).is_err());
+        assert!(client.exec_bytes("echo 5").is_err());
+        assert!(client.exec_native(&["echo", "5"]).is_err());
+        assert!(client.check_exec_permission().is_err());
+        assert!(client
+            .exec_raw(vec!["echo".to_string(), "5".to_string()])
+            .is_err());
+        assert!(client.stat_ext(Path::new("/tmp")).is_err());
+        assert!(client
+            .rename(Path::new("/tmp/a.txt"), Path::new("/tmp/b.txt"))
+            .is_err());
+        assert!(client
+            .open_files(vec![PathBuf::from("/tmp/a.txt")])
+            .is_err());
+        assert!(client
+            .wait_for_file(
+                Path::new("/tmp/a.txt"),
+                Duration::from_secs(1),
+                Duration::from_millis(100)
+            )
+            .is_err());
+        assert!(client.exec_endpoint(&["sh"]).is_err());
+        assert!(client.head(Path::new("/tmp/a.txt"), 16).is_err());
+        assert!(client.exec_tty(&["sh"]).is_err());
+        assert!(client.tail_bytes(Path::new("/tmp/a.txt"), 16).is_err());
+        assert!(client
+            .same_file(Path::new("/tmp/a.txt"), Path::new("/tmp/b.txt"))
+            .is_err());
+        assert!(client.switch("test", "test").is_err());
+        assert!(client.refresh_auth().is_err());
+        assert!(client
+            .list_dir_opts(Path::new("/tmp"), ListOptions::default())
+            .is_err());
+        assert!(client.list_dir_with_total(Path::new("/tmp")).is_err());
+        assert!(client.canonicalize(Path::new("/tmp")).is_err());
+        assert!(client
+            .create_dir_all(Path::new("/tmp/a/b"), UnixPex::from(0o755))
+            .is_err());
+        assert!(client
+            .ensure_dir(Path::new("/tmp/a"), UnixPex::from(0o755))
+            .is_err());
+        assert!(client
+            .chmod_recursive(Path::new("/tmp/a"), UnixPex::from(0o755))
+            .is_err());
+        assert!(client.chown_recursive(Path::new("/tmp/a"), 0, 0).is_err());
+        assert!(client
+            .chown_recursive_with(Path::new("/tmp/a"), 0, 0, SymlinkFollow::Always)
+            .is_err());
+        assert!(client.disk_usage(Path::new("/tmp")).is_err());
+        assert!(client
+            .mkfifo(Path::new("/tmp/fifo"), UnixPex::from(0o644))
+            .is_err());
+        assert!(client.create_files(vec![]).is_err());
+        assert!(client.pod_info().is_err());
+        assert!(client.env().is_err());
+        assert!(client.is_read_only_mount(Path::new("/tmp")).is_err());
+        assert!(client
+            .edit(Path::new("/tmp/a.txt"), |content| content.to_vec())
+            .is_err());
+        assert!(client
+            .sync_up(Path::new("/tmp"), Path::new("/tmp"), SyncOptions::default())
+            .is_err());
+        assert!(client
+            .tail(
+                Path::new("/tmp/a.txt"),
+                5,
+                false,
+                &CancellationToken::new(),
+                |_| {}
+            )
+            .is_err());
         assert!(client.disconnect().is_err());
         assert!(client.list_dir(Path::new("/tmp")).is_err());
         assert!(client
@@ -1690,14 +7943,159 @@ mod test {
             .is_err());
     }
 
+    #[test]
+    fn should_pass_transfer_keepalive_to_connector() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut config = Config::new("https://127.0.0.1:6443".parse().unwrap());
+        config.connect_timeout = Some(Duration::from_secs(5));
+        let client = KubeContainerFs::new("test", "test", &rt);
+        assert_eq!(client.connector_options(&config), None);
+        let client = client.transfer_keepalive(Duration::from_secs(30));
+        assert_eq!(
+            client.connector_options(&config),
+            Some(ConnectorOptions {
+                keepalive: Duration::from_secs(30),
+                connect_timeout: Some(Duration::from_secs(5)),
+            })
+        );
+        // the client is built with it
+        let mut client = client.config(config);
+        assert!(rt.block_on(client.build_client()).is_ok());
+        // but not through a proxy
+        client.config.as_mut().unwrap().proxy_url = Some("http://127.0.0.1:3128".parse().unwrap());
+        assert_eq!(
+            rt.block_on(client.build_client()).err().unwrap().kind,
+            RemoteErrorType::UnsupportedFeature
+        );
+    }
+
+    #[test]
+    fn should_record_one_metric_per_operation() {
+        #[derive(Default)]
+        struct MockSink {
+            events: std::sync::Mutex<Vec<(String, bool)>>,
+        }
+
+        impl MetricsSink for MockSink {
+            fn on_operation(&self, name: &str, _duration: Duration, success: bool) {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push((name.to_string(), success));
+            }
+        }
+
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let sink = Arc::new(MockSink::default());
+        let mut client = KubeContainerFs::new("test", "test", &rt).with_metrics(sink.clone());
+        assert!(client.pwd().is_err());
+        assert!(client.stat(Path::new("/tmp")).is_err());
+        assert!(client.exists(Path::new("/tmp")).is_err());
+        assert!(client
+            .setstat(Path::new("/tmp"), Metadata::default())
+            .is_err());
+        assert!(client.disconnect().is_err());
+        // not an operation
+        assert!(!client.is_connected());
+        assert_eq!(
+            sink.events.lock().unwrap().as_slice(),
+            &[
+                ("pwd".to_string(), false),
+                ("stat".to_string(), false),
+                ("exists".to_string(), false),
+                ("setstat".to_string(), false),
+                ("disconnect".to_string(), false),
+            ]
+        );
+    }
+
     // -- test utils
 
+    /// Spawn on `rt` a TLS server with the `127.0.0.1` test certificate, answering `ok` to any request
+    fn spawn_tls_server(rt: &Runtime) -> std::net::SocketAddr {
+        use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+        use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+        use tokio_rustls::rustls::ServerConfig;
+
+        use crate::utils::tls::{TEST_SERVER_CERT_PEM, TEST_SERVER_KEY_PEM};
+
+        let cert = tls_utils::parse_certs(TEST_SERVER_CERT_PEM.as_bytes()).unwrap();
+        let key = pem::parse(TEST_SERVER_KEY_PEM).unwrap().into_contents();
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![CertificateDer::from(cert[0].clone())],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key)),
+            )
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+        let listener = rt
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        rt.spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                // the handshake fails if the client doesn't trust the certificate
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    continue;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok",
+                    )
+                    .await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        addr
+    }
+
+    /// Create `tree/a.txt`, `tree/sub/b.txt`, returning all the paths of the tree
+    #[cfg(feature = "integration-tests")]
+    fn make_small_tree(client: &mut KubeContainerFs) -> Vec<PathBuf> {
+        assert!(client
+            .create_dir_all(Path::new("tree/sub"), UnixPex::from(0o755))
+            .is_ok());
+        for file in ["tree/a.txt", "tree/sub/b.txt"] {
+            let file_data = "test data\n";
+            let metadata = Metadata::default().size(file_data.len() as u64);
+            assert!(client
+                .create_file(
+                    Path::new(file),
+                    &metadata,
+                    Box::new(Cursor::new(file_data.as_bytes()))
+                )
+                .is_ok());
+        }
+        ["tree", "tree/a.txt", "tree/sub", "tree/sub/b.txt"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect()
+    }
+
     #[cfg(feature = "integration-tests")]
     fn setup_client() -> (Api<Pod>, KubeContainerFs) {
         // setup pod with random name
 
         use kube::api::PostParams;
-        use kube::config::AuthInfo;
         use kube::ResourceExt as _;
         let pod_name = generate_pod_name();
         debug!("Pod name: {pod_name}");
@@ -1709,35 +8107,11 @@ mod test {
                 .unwrap(),
         );
 
-        let minikube_ip = std::env::var("MINIKUBE_IP").unwrap();
-
-        // setup pod
+        // setup pod on the cluster of the kubeconfig, as the library would
         debug!("setting up pod");
-        // config for minikube
-        let mut auth_info = AuthInfo::default();
-        auth_info.username = Some("minikube".to_string());
-        // get home
-        let home = std::env::var("HOME").unwrap();
-        auth_info.client_certificate =
-            Some(format!("{home}/.minikube/profiles/minikube/client.crt"));
-        auth_info.client_key = Some(format!("{home}/.minikube/profiles/minikube/client.key"));
-
-        //let root_cert = format!("{home}/.minikube/ca.crt");
-
-        debug!("Auth info: {auth_info:?}");
-
-        let config = Config {
-            cluster_url: format!("https://{minikube_ip}:8443").parse().unwrap(),
-            default_namespace: "default".to_string(),
-            read_timeout: None,
-            root_cert: None,
-            connect_timeout: None,
-            write_timeout: None,
-            accept_invalid_certs: true,
-            auth_info,
-            proxy_url: None,
-            tls_server_name: None,
-        };
+        let config = runtime
+            .block_on(KubeContainerFs::config_from_env())
+            .expect("failed to load kubeconfig");
 
         let pods = runtime.block_on(async {
             let client = Client::try_from(config.clone()).unwrap();
@@ -1752,6 +8126,10 @@ mod test {
                       "name": "alpine",
                       "image": "alpine" ,
                       "command": ["tail", "-f", "/dev/null"],
+                    }, {
+                      "name": "sidecar",
+                      "image": "alpine" ,
+                      "command": ["tail", "-f", "/dev/null"],
                     }],
                 }
             }))
@@ -1832,4 +8210,43 @@ mod test {
             .collect();
         format!("/tmp/temp_{}", name)
     }
+
+    /// Writer into a buffer which can be inspected after being moved into the client
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Writer discarding the data, which only counts the bytes written to it
+    #[cfg(feature = "integration-tests")]
+    #[derive(Clone, Default)]
+    struct CountingWriter(Arc<std::sync::atomic::AtomicU64>);
+
+    #[cfg(feature = "integration-tests")]
+    impl CountingWriter {
+        fn count(&self) -> u64 {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    #[cfg(feature = "integration-tests")]
+    impl std::io::Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.fetch_add(buf.len() as u64, Ordering::Relaxed);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
 }
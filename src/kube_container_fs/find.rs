@@ -0,0 +1,177 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use remotefs::fs::{FileType, Metadata, UnixPex};
+use remotefs::File;
+
+use crate::utils::path as path_utils;
+
+/// `find -printf` format of an entry: type, mode, size, uid, gid, mtime, link target and path, NUL-terminated.
+///
+/// The path is last, so that it may contain tabs.
+const PRINTF_FORMAT: &str = r"%y\t%m\t%s\t%U\t%G\t%T@\t%l\t%p\0";
+
+/// Make the `find` command searching `dir` for the entries selected by `predicates`.
+///
+/// With `printf`, the entries are printed with `PRINTF_FORMAT`; otherwise they're listed with `ls -ld`,
+/// for the `find` implementations without `-printf` (e.g. busybox).
+pub fn find_cmd(dir: &Path, predicates: &str, printf: bool) -> String {
+    let mut cmd = format!("find {} {predicates}", path_utils::quote(dir));
+    if printf {
+        cmd.push_str(&format!(" -printf '{PRINTF_FORMAT}'"));
+    } else {
+        cmd.push_str(" -exec ls -ld {} +");
+    }
+    cmd
+}
+
+/// Get the `find` predicates selecting the entries whose name matches the `name` wildcard,
+/// and whose type is `file_type`, if set
+pub fn name_predicates(name: &str, file_type: Option<FileType>) -> String {
+    let mut predicates = format!("-mindepth 1 -name '{}'", name.replace('\'', r"'\''"));
+    if let Some(file_type) = file_type {
+        predicates.push_str(match file_type {
+            FileType::Directory => " -type d",
+            FileType::File => " -type f",
+            FileType::Symlink => " -type l",
+        });
+    }
+    predicates
+}
+
+/// Returns whether `find` failed because it doesn't support `-printf`, according to its `stderr`
+pub fn is_printf_unsupported(stderr: &str) -> bool {
+    stderr.contains("-printf")
+}
+
+/// Parse the output of `find` printed with `PRINTF_FORMAT`; the malformed entries are skipped
+pub fn parse_printf_output(output: &str) -> Vec<File> {
+    output
+        .split('\0')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let file = parse_printf_entry(entry);
+            if file.is_none() {
+                debug!("Skipping malformed find entry: {entry:?}");
+            }
+            file
+        })
+        .collect()
+}
+
+fn parse_printf_entry(entry: &str) -> Option<File> {
+    let mut fields = entry.splitn(8, '\t');
+    let file_type = match fields.next()? {
+        "d" => FileType::Directory,
+        "l" => FileType::Symlink,
+        // remotefs has no type for FIFOs, sockets and devices; they're reported as files
+        "f" | "p" | "s" | "b" | "c" => FileType::File,
+        _ => return None,
+    };
+    let mode = u32::from_str_radix(fields.next()?, 8).ok()?;
+    let size = fields.next()?.parse::<u64>().ok()?;
+    let uid = fields.next()?.parse::<u32>().ok();
+    let gid = fields.next()?.parse::<u32>().ok();
+    let modified = fields
+        .next()?
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs_f64(secs));
+    let symlink = Some(fields.next()?)
+        .filter(|target| !target.is_empty())
+        .map(PathBuf::from);
+    let path = PathBuf::from(fields.next()?);
+    if !path.is_absolute() {
+        return None;
+    }
+
+    Some(File {
+        path,
+        metadata: Metadata {
+            accessed: None,
+            created: None,
+            file_type,
+            gid,
+            mode: Some(UnixPex::from(mode)),
+            modified,
+            size,
+            symlink,
+            uid,
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_make_find_cmd() {
+        assert_eq!(
+            find_cmd(
+                Path::new("/home/omar"),
+                &name_predicates("*.txt", None),
+                true
+            ),
+            r#"find '/home/omar' -mindepth 1 -name '*.txt' -printf '%y\t%m\t%s\t%U\t%G\t%T@\t%l\t%p\0'"#
+        );
+        assert_eq!(
+            find_cmd(
+                Path::new("/tmp"),
+                &name_predicates("omar's file?", Some(FileType::Directory)),
+                false
+            ),
+            r#"find '/tmp' -mindepth 1 -name 'omar'\''s file?' -type d -exec ls -ld {} +"#
+        );
+    }
+
+    #[test]
+    fn should_tell_whether_printf_is_unsupported() {
+        assert!(is_printf_unsupported("find: unrecognized: -printf\n"));
+        assert!(!is_printf_unsupported(
+            "find: '/root/secret': Permission denied\n"
+        ));
+    }
+
+    #[test]
+    fn should_parse_find_printf_output() {
+        let output = "f\t644\t2056\t0\t0\t1730822400.5000000000\t\t/tmp/Cargo.toml\0\
+            d\t755\t4096\t1000\t1000\t1730822400.0000000000\t\t/tmp/src\0\
+            l\t777\t11\t0\t0\t1730822400.0000000000\t/etc/hosts\t/tmp/hosts\0\
+            f\t600\t0\t0\t0\t1730822400.0000000000\t\t/tmp/with\ttab.txt\0\
+            x\tbad\0";
+        let files = parse_printf_output(output);
+        assert_eq!(files.len(), 4);
+
+        assert_eq!(files[0].path(), Path::new("/tmp/Cargo.toml"));
+        assert!(files[0].is_file());
+        assert_eq!(files[0].metadata().size, 2056);
+        assert_eq!(files[0].metadata().mode, Some(UnixPex::from(0o644)));
+        assert_eq!(files[0].metadata().uid, Some(0));
+        assert_eq!(
+            files[0].metadata().modified,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_millis(1730822400500))
+        );
+
+        assert!(files[1].is_dir());
+        assert_eq!(files[1].metadata().gid, Some(1000));
+
+        assert!(files[2].is_symlink());
+        assert_eq!(
+            files[2].metadata().symlink.as_deref(),
+            Some(Path::new("/etc/hosts"))
+        );
+
+        assert_eq!(files[3].name(), "with\ttab.txt");
+    }
+
+    #[test]
+    fn should_parse_empty_find_printf_output() {
+        assert!(parse_printf_output("").is_empty());
+        assert!(parse_printf_output("\n").is_empty());
+    }
+}
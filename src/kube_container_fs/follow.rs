@@ -0,0 +1,38 @@
+/// How recursive operations, such as `chown_recursive`, handle the symbolic links they meet
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkFollow {
+    /// Change the links themselves, never their targets, and don't traverse linked directories (`-h -P`)
+    #[default]
+    Never,
+    /// Follow the link given as path, if it is one, but none of the links under it (`-H`)
+    CommandLine,
+    /// Follow every link, changing the targets and traversing linked directories (`-L`)
+    Always,
+}
+
+impl SymlinkFollow {
+    /// Get the `chown -R` flags for this follow mode
+    pub fn chown_flags(&self) -> &'static [&'static str] {
+        match self {
+            Self::Never => &["-h", "-P"],
+            Self::CommandLine => &["-H"],
+            Self::Always => &["-L"],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_get_chown_flags() {
+        assert_eq!(SymlinkFollow::default(), SymlinkFollow::Never);
+        assert_eq!(SymlinkFollow::Never.chown_flags(), ["-h", "-P"]);
+        assert_eq!(SymlinkFollow::CommandLine.chown_flags(), ["-H"]);
+        assert_eq!(SymlinkFollow::Always.chown_flags(), ["-L"]);
+    }
+}
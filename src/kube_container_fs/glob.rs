@@ -0,0 +1,204 @@
+use std::path::{Path, PathBuf};
+
+/// A glob pattern, split into the directory to search and the pattern of the paths in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    /// Leading components of the pattern without wildcards
+    pub base: PathBuf,
+    /// Components of the pattern relative to `base`; empty if the pattern has no wildcards
+    pub components: Vec<String>,
+}
+
+impl Glob {
+    /// Split the absolute `pattern` into its base directory and the wildcard components
+    pub fn new(pattern: &Path) -> Self {
+        let mut base = PathBuf::from("/");
+        let mut components = Vec::new();
+        for component in pattern.iter().skip(1) {
+            let component = component.to_string_lossy();
+            if components.is_empty() && !has_wildcards(&component) {
+                base.push(component.as_ref());
+            } else {
+                components.push(component.into_owned());
+            }
+        }
+        Self { base, components }
+    }
+
+    /// Whether the pattern has any wildcard
+    pub fn has_wildcards(&self) -> bool {
+        !self.components.is_empty()
+    }
+
+    /// Get the `find` predicates selecting a superset of the paths matching the pattern.
+    ///
+    /// `*` matches `/` too in `-path`, so `**` is translated to `*`; the results must be checked with `matches`.
+    pub fn find_predicates(&self) -> String {
+        let mut path = self
+            .base
+            .join(self.components.join("/"))
+            .display()
+            .to_string();
+        while path.contains("**") {
+            path = path.replace("**", "*");
+        }
+        let path = path.replace("*/*", "*");
+        let mut predicates = String::new();
+        if !self.components.iter().any(|c| c == "**") {
+            let depth = self.components.len();
+            predicates.push_str(&format!("-mindepth {depth} -maxdepth {depth} "));
+        }
+        predicates.push_str(&format!("-path '{}'", path.replace('\'', r"'\''")));
+        predicates
+    }
+
+    /// Returns whether the absolute `path` matches the pattern.
+    ///
+    /// `*`, `?` and `[...]` match within a single component, while `**` matches any number of components.
+    pub fn matches(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+        let names: Vec<String> = relative
+            .iter()
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let components: Vec<&str> = self.components.iter().map(String::as_str).collect();
+        match_components(&components, &names)
+    }
+}
+
+fn has_wildcards(component: &str) -> bool {
+    component.contains(['*', '?', '['])
+}
+
+fn match_components(pattern: &[&str], names: &[&str]) -> bool {
+    match (pattern.first(), names.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            // match no components, or one more
+            match_components(&pattern[1..], names)
+                || (!names.is_empty() && match_components(pattern, &names[1..]))
+        }
+        (Some(component), Some(name)) => {
+            wildcard_match(component.as_bytes(), name.as_bytes())
+                && match_components(&pattern[1..], &names[1..])
+        }
+        _ => false,
+    }
+}
+
+/// Match `name` against the shell wildcard `pattern`, supporting `*`, `?` and `[...]` classes
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some(b'*') => {
+            wildcard_match(&pattern[1..], name)
+                || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+        }
+        Some(b'?') => !name.is_empty() && wildcard_match(&pattern[1..], &name[1..]),
+        Some(b'[') => match (class_match(&pattern[1..], name.first()), name.first()) {
+            (Some((true, len)), Some(_)) => wildcard_match(&pattern[1 + len..], &name[1..]),
+            (Some((false, _)), _) | (_, None) => false,
+            // unterminated class; `[` is literal
+            (None, Some(c)) => *c == b'[' && wildcard_match(&pattern[1..], &name[1..]),
+        },
+        Some(c) => name.first() == Some(c) && wildcard_match(&pattern[1..], &name[1..]),
+    }
+}
+
+/// Match `c` against the class starting after `[`; returns whether it matches and the length of the class,
+/// or `None` if the class isn't terminated
+fn class_match(class: &[u8], c: Option<&u8>) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some(b'!' | b'^'));
+    let start = usize::from(negated);
+    // `]` right after `[` is a literal
+    let end = class
+        .iter()
+        .skip(start + 1)
+        .position(|b| *b == b']')
+        .map(|pos| pos + start + 1)?;
+    let c = c.copied().unwrap_or_default();
+    let set = &class[start..end];
+    let mut matched = false;
+    let mut i = 0;
+    while i < set.len() {
+        if i + 2 < set.len() && set[i + 1] == b'-' {
+            matched |= (set[i]..=set[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= set[i] == c;
+            i += 1;
+        }
+    }
+    Some((matched != negated, end + 1))
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_split_glob() {
+        let glob = Glob::new(Path::new("/var/log/**/*.gz"));
+        assert_eq!(glob.base, PathBuf::from("/var/log"));
+        assert_eq!(glob.components, vec!["**", "*.gz"]);
+        assert!(glob.has_wildcards());
+
+        let glob = Glob::new(Path::new("/etc/hosts"));
+        assert_eq!(glob.base, PathBuf::from("/etc/hosts"));
+        assert!(!glob.has_wildcards());
+
+        let glob = Glob::new(Path::new("/home/*/logs/a?.txt"));
+        assert_eq!(glob.base, PathBuf::from("/home"));
+        assert_eq!(glob.components, vec!["*", "logs", "a?.txt"]);
+    }
+
+    #[test]
+    fn should_translate_glob_to_find_predicates() {
+        assert_eq!(
+            Glob::new(Path::new("/var/log/**/*.gz")).find_predicates(),
+            "-path '/var/log/*.gz'"
+        );
+        assert_eq!(
+            Glob::new(Path::new("/var/log/**")).find_predicates(),
+            "-path '/var/log/*'"
+        );
+        assert_eq!(
+            Glob::new(Path::new("/home/*/logs/a?.txt")).find_predicates(),
+            "-mindepth 3 -maxdepth 3 -path '/home/*/logs/a?.txt'"
+        );
+        assert_eq!(
+            Glob::new(Path::new("/srv/omar's/*.[ch]")).find_predicates(),
+            r"-mindepth 1 -maxdepth 1 -path '/srv/omar'\''s/*.[ch]'"
+        );
+    }
+
+    #[test]
+    fn should_match_glob() {
+        let glob = Glob::new(Path::new("/var/log/**/*.gz"));
+        assert!(glob.matches(Path::new("/var/log/syslog.1.gz")));
+        assert!(glob.matches(Path::new("/var/log/nginx/access.log.2.gz")));
+        assert!(glob.matches(Path::new("/var/log/a/b/c/d.gz")));
+        assert!(!glob.matches(Path::new("/var/log/nginx/access.log")));
+        assert!(!glob.matches(Path::new("/var/lib/a.gz")));
+
+        let glob = Glob::new(Path::new("/home/*/a?.txt"));
+        assert!(glob.matches(Path::new("/home/omar/ab.txt")));
+        assert!(!glob.matches(Path::new("/home/omar/abc.txt")));
+        assert!(!glob.matches(Path::new("/home/omar/docs/ab.txt")));
+
+        let glob = Glob::new(Path::new("/src/*.[ch]"));
+        assert!(glob.matches(Path::new("/src/main.c")));
+        assert!(glob.matches(Path::new("/src/main.h")));
+        assert!(!glob.matches(Path::new("/src/main.rs")));
+
+        let glob = Glob::new(Path::new("/src/[!a-m]*"));
+        assert!(glob.matches(Path::new("/src/zeta")));
+        assert!(!glob.matches(Path::new("/src/beta")));
+    }
+}
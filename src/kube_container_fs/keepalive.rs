@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use hyper_timeout::TimeoutConnector;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioExecutor;
+use kube::client::ConfigExt as _;
+use kube::{Client, Config};
+use tower::{BoxError, ServiceBuilder};
+
+/// Options of the TCP connections of a kube client with keepalive enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectorOptions {
+    /// Interval of the TCP keepalive probes, sent while the connection is idle
+    pub keepalive: Duration,
+    /// Timeout for establishing the connection
+    pub connect_timeout: Option<Duration>,
+}
+
+impl ConnectorOptions {
+    /// Build the HTTP connector with these options
+    fn http_connector(&self) -> HttpConnector {
+        let mut connector = HttpConnector::new();
+        connector.enforce_http(false);
+        connector.set_keepalive(Some(self.keepalive));
+        connector.set_keepalive_interval(Some(self.keepalive));
+        connector.set_connect_timeout(self.connect_timeout);
+        connector
+    }
+
+    /// Build the kube client from `config`, with the same layers and timeouts kube uses,
+    /// on top of a connector with these options.
+    ///
+    /// The proxy of `config` isn't supported; see `KubeContainerFs::build_client`.
+    pub fn build_client(&self, config: Config) -> kube::Result<Client> {
+        let mut connector = TimeoutConnector::new(
+            config.rustls_https_connector_with_connector(self.http_connector())?,
+        );
+        connector.set_connect_timeout(config.connect_timeout);
+        connector.set_read_timeout(config.read_timeout);
+        connector.set_write_timeout(config.write_timeout);
+        let client =
+            hyper_util::client::legacy::Client::builder(TokioExecutor::new()).build(connector);
+        let service = ServiceBuilder::new()
+            .layer(config.base_uri_layer())
+            .option_layer(config.auth_layer()?)
+            .layer(config.extra_headers_layer()?)
+            .map_err(BoxError::from)
+            .service(client);
+
+        Ok(Client::new(service, config.default_namespace))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_build_client_with_keepalive() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut config = Config::new("https://127.0.0.1:6443".parse().unwrap());
+        config.default_namespace = "my-namespace".to_string();
+        let options = ConnectorOptions {
+            keepalive: Duration::from_secs(30),
+            connect_timeout: Some(Duration::from_secs(5)),
+        };
+        let _guard = rt.enter();
+        let client = options.build_client(config).unwrap();
+        assert_eq!(client.default_namespace(), "my-namespace");
+    }
+
+    #[test]
+    fn should_enable_keepalive_on_connections() {
+        use tower::ServiceExt as _;
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let options = ConnectorOptions {
+            keepalive: Duration::from_secs(30),
+            connect_timeout: None,
+        };
+        let stream = rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let uri = format!("http://{}", listener.local_addr().unwrap());
+            let (stream, accepted) = tokio::join!(
+                options.http_connector().oneshot(uri.parse().unwrap()),
+                listener.accept()
+            );
+            accepted.unwrap();
+            stream.unwrap()
+        });
+        let socket = socket2::SockRef::from(stream.inner());
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(
+            socket.tcp_keepalive_time().unwrap(),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            socket.tcp_keepalive_interval().unwrap(),
+            Duration::from_secs(30)
+        );
+    }
+
+    #[test]
+    fn should_keep_read_timeout_of_config() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let options = ConnectorOptions {
+            keepalive: Duration::from_secs(30),
+            connect_timeout: None,
+        };
+        rt.block_on(async {
+            // the server accepts the connection, but never answers
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let mut config = Config::new(
+                format!("http://{}", listener.local_addr().unwrap())
+                    .parse()
+                    .unwrap(),
+            );
+            config.read_timeout = Some(Duration::from_millis(200));
+            let client = options.build_client(config).unwrap();
+            tokio::spawn(async move {
+                let mut streams = Vec::new();
+                while let Ok((stream, _)) = listener.accept().await {
+                    streams.push(stream);
+                }
+            });
+            let request = http::Request::get("/version").body(Vec::new()).unwrap();
+            let result =
+                tokio::time::timeout(Duration::from_secs(10), client.request_text(request)).await;
+            assert!(result.expect("the read timeout isn't applied").is_err());
+        });
+    }
+}
@@ -0,0 +1,9 @@
+/// Security labels of a file, as reported by `ls -Z` and `getfattr`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SecurityLabels {
+    /// SELinux context (e.g. `system_u:object_r:container_file_t:s0:c1,c2`); `None` if SELinux is disabled
+    /// or `ls` doesn't support `-Z`
+    pub selinux_context: Option<String>,
+    /// Extended attributes as name and value (e.g. `user.origin` => `upload`); empty if `getfattr` is not in the container
+    pub xattrs: Vec<(String, String)>,
+}
@@ -0,0 +1,197 @@
+use std::cmp::Ordering;
+
+use remotefs::File;
+
+/// Options for listing a directory with `list_dir_opts`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ListOptions {
+    /// Include entries starting with `.`
+    pub show_hidden: bool,
+    /// Sort key for the entries
+    pub sort_by: SortBy,
+    /// Reverse the sort order
+    pub reverse: bool,
+}
+
+/// Sort key for directory listings
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    /// Sort by name, in alphabetical order
+    #[default]
+    Name,
+    /// Sort by size, largest first
+    Size,
+    /// Sort by modification time, newest first
+    Mtime,
+}
+
+impl ListOptions {
+    /// Get the `ls` flags for these options
+    pub fn ls_flags(&self) -> String {
+        let mut flags = String::from("-l");
+        if self.show_hidden {
+            flags.push('a');
+        }
+        match self.sort_by {
+            SortBy::Name => {}
+            SortBy::Size => flags.push('S'),
+            SortBy::Mtime => flags.push('t'),
+        }
+        if self.reverse {
+            flags.push('r');
+        }
+        flags
+    }
+
+    /// Compare two entries according to these options
+    pub fn compare(&self, a: &File, b: &File) -> Ordering {
+        let ordering = self.sort_by.compare(a, b);
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// Sort entries according to these options
+    pub fn sort(&self, entries: &mut [File]) {
+        entries.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+impl SortBy {
+    /// Compare two entries by this key; ties are broken by name
+    pub fn compare(&self, a: &File, b: &File) -> Ordering {
+        let by_name = || a.name().cmp(&b.name());
+        match self {
+            SortBy::Name => by_name(),
+            SortBy::Size => b.metadata.size.cmp(&a.metadata.size).then_with(by_name),
+            SortBy::Mtime => b
+                .metadata
+                .modified
+                .cmp(&a.metadata.modified)
+                .then_with(by_name),
+        }
+    }
+}
+
+/// Make the `ls` command with `args`, printing names literally if `literal`, i.e. if `--quoting-style` is supported.
+///
+/// GNU `ls` may quote or escape names (e.g. `'my file.txt'`), while busybox doesn't support the option.
+pub fn ls_cmd(literal: bool, args: impl std::fmt::Display) -> String {
+    match literal {
+        true => format!("ls --quoting-style=literal {args}"),
+        false => format!("ls {args}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    use pretty_assertions::assert_eq;
+    use remotefs::fs::Metadata;
+
+    use super::*;
+
+    #[test]
+    fn should_make_ls_flags() {
+        assert_eq!(ListOptions::default().ls_flags().as_str(), "-l");
+        assert_eq!(
+            ListOptions {
+                show_hidden: true,
+                ..Default::default()
+            }
+            .ls_flags()
+            .as_str(),
+            "-la"
+        );
+        assert_eq!(
+            ListOptions {
+                show_hidden: true,
+                sort_by: SortBy::Size,
+                reverse: true,
+            }
+            .ls_flags()
+            .as_str(),
+            "-laSr"
+        );
+        assert_eq!(
+            ListOptions {
+                show_hidden: false,
+                sort_by: SortBy::Mtime,
+                reverse: false,
+            }
+            .ls_flags()
+            .as_str(),
+            "-lt"
+        );
+    }
+
+    #[test]
+    fn should_make_ls_cmd() {
+        assert_eq!(
+            ls_cmd(true, "-l \"/tmp/\"").as_str(),
+            "ls --quoting-style=literal -l \"/tmp/\""
+        );
+        assert_eq!(ls_cmd(false, "-l \"/tmp/\"").as_str(), "ls -l \"/tmp/\"");
+    }
+
+    #[test]
+    fn should_sort_by_name() {
+        let mut entries = vec![file("b", 1, 1), file("c", 1, 1), file("a", 1, 1)];
+        ListOptions::default().sort(&mut entries);
+        assert_eq!(names(&entries), vec!["a", "b", "c"]);
+
+        ListOptions {
+            reverse: true,
+            ..Default::default()
+        }
+        .sort(&mut entries);
+        assert_eq!(names(&entries), vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn should_sort_by_size() {
+        let mut entries = vec![file("a", 10, 1), file("b", 30, 1), file("c", 10, 1)];
+        let opts = ListOptions {
+            sort_by: SortBy::Size,
+            ..Default::default()
+        };
+        opts.sort(&mut entries);
+        assert_eq!(names(&entries), vec!["b", "a", "c"]);
+
+        ListOptions {
+            reverse: true,
+            ..opts
+        }
+        .sort(&mut entries);
+        assert_eq!(names(&entries), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn should_sort_by_mtime() {
+        let mut entries = vec![file("a", 1, 100), file("b", 1, 300), file("c", 1, 200)];
+        let opts = ListOptions {
+            sort_by: SortBy::Mtime,
+            ..Default::default()
+        };
+        opts.sort(&mut entries);
+        assert_eq!(names(&entries), vec!["b", "c", "a"]);
+    }
+
+    fn file(name: &str, size: u64, mtime: u64) -> File {
+        File {
+            path: PathBuf::from(format!("/tmp/{name}")),
+            metadata: Metadata::default()
+                .size(size)
+                .modified(SystemTime::UNIX_EPOCH + Duration::from_secs(mtime)),
+        }
+    }
+
+    fn names(entries: &[File]) -> Vec<String> {
+        entries.iter().map(|f| f.name()).collect()
+    }
+}
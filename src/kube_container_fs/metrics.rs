@@ -0,0 +1,13 @@
+use std::time::Duration;
+
+/// Receiver of the metrics of the operations run by [`super::KubeContainerFs`],
+/// e.g. to export them as Prometheus counters and histograms.
+///
+/// It's called from the thread running the operation, so it should return quickly.
+pub trait MetricsSink: Send + Sync {
+    /// Called once an operation has completed.
+    ///
+    /// `name` is the name of the [`remotefs::RemoteFs`] method (e.g. `stat` or `create_file`),
+    /// `duration` includes retries and `success` tells whether it returned `Ok`.
+    fn on_operation(&self, name: &str, duration: Duration, success: bool);
+}
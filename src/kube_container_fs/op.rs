@@ -0,0 +1,55 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Id of the next operation
+static NEXT_OP_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Context of an operation on a container, prefixed to its log lines to tell interleaved operations apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OpContext<'a> {
+    id: u64,
+    pod: &'a str,
+    container: &'a str,
+}
+
+impl<'a> OpContext<'a> {
+    /// Make the context of a new operation on `container` in `pod`, with a new unique id
+    pub fn new(pod: &'a str, container: &'a str) -> Self {
+        Self {
+            id: NEXT_OP_ID.fetch_add(1, Ordering::Relaxed),
+            pod,
+            container,
+        }
+    }
+}
+
+impl fmt::Display for OpContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[op #{} {}/{}]", self.id, self.pod, self.container)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_format_op_context() {
+        let op = OpContext {
+            id: 42,
+            pod: "my-pod",
+            container: "alpine",
+        };
+        assert_eq!(op.to_string().as_str(), "[op #42 my-pod/alpine]");
+    }
+
+    #[test]
+    fn should_increase_op_id() {
+        let first = OpContext::new("my-pod", "alpine");
+        let second = OpContext::new("my-pod", "alpine");
+        assert!(second.id > first.id);
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+/// Information about the pod and the container the client is connected to
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PodInfo {
+    /// Name of the node the pod is scheduled on
+    pub node_name: Option<String>,
+    /// Pod phase (e.g. `Running`)
+    pub phase: Option<String>,
+    /// Image of the container
+    pub image: Option<String>,
+    /// Resource requests of the container (e.g. `cpu` => `250m`)
+    pub requests: BTreeMap<String, String>,
+    /// Resource limits of the container (e.g. `memory` => `128Mi`)
+    pub limits: BTreeMap<String, String>,
+}
+
+impl PodInfo {
+    /// Get the info of `container` from `pod`
+    pub(crate) fn from_pod(pod: &Pod, container: &str) -> Self {
+        let spec = pod.spec.as_ref();
+        let container = spec.and_then(|spec| spec.containers.iter().find(|c| c.name == container));
+        let resources = container.and_then(|c| c.resources.as_ref());
+
+        Self {
+            node_name: spec.and_then(|spec| spec.node_name.clone()),
+            phase: pod.status.as_ref().and_then(|status| status.phase.clone()),
+            image: container.and_then(|c| c.image.clone()),
+            requests: quantities(resources.and_then(|r| r.requests.as_ref())),
+            limits: quantities(resources.and_then(|r| r.limits.as_ref())),
+        }
+    }
+}
+
+fn quantities(quantities: Option<&BTreeMap<String, Quantity>>) -> BTreeMap<String, String> {
+    quantities
+        .map(|quantities| {
+            quantities
+                .iter()
+                .map(|(name, quantity)| (name.clone(), quantity.0.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_get_pod_info() {
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": { "name": "test" },
+            "spec": {
+                "nodeName": "node-1",
+                "containers": [
+                    {
+                        "name": "sidecar",
+                        "image": "busybox:1.36"
+                    },
+                    {
+                        "name": "alpine",
+                        "image": "alpine:3",
+                        "resources": {
+                            "requests": { "cpu": "250m", "memory": "64Mi" },
+                            "limits": { "memory": "128Mi" }
+                        }
+                    }
+                ]
+            },
+            "status": { "phase": "Running" }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            PodInfo::from_pod(&pod, "alpine"),
+            PodInfo {
+                node_name: Some("node-1".to_string()),
+                phase: Some("Running".to_string()),
+                image: Some("alpine:3".to_string()),
+                requests: BTreeMap::from([
+                    ("cpu".to_string(), "250m".to_string()),
+                    ("memory".to_string(), "64Mi".to_string()),
+                ]),
+                limits: BTreeMap::from([("memory".to_string(), "128Mi".to_string())]),
+            }
+        );
+
+        let info = PodInfo::from_pod(&pod, "sidecar");
+        assert_eq!(info.image.as_deref(), Some("busybox:1.36"));
+        assert!(info.requests.is_empty());
+        assert!(info.limits.is_empty());
+
+        let info = PodInfo::from_pod(&pod, "missing");
+        assert_eq!(info.node_name.as_deref(), Some("node-1"));
+        assert!(info.image.is_none());
+    }
+}
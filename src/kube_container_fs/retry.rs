@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use remotefs::fs::{RemoteError, RemoteErrorType};
+
+/// Delay before the first retry; it doubles on each further retry
+const BASE_DELAY: Duration = Duration::from_millis(100);
+
+/// Maximum delay between two retries
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// Messages of the transport errors which may not happen again on retry (lowercase)
+const TRANSIENT_MESSAGES: &[&str] = &[
+    "broken pipe",
+    "connection aborted",
+    "connection closed",
+    "connection reset",
+    "service unavailable",
+    "stream reset",
+    "timed out",
+    "too many requests",
+    "unexpected eof",
+];
+
+/// Returns whether `err` is a transient transport error, so that the operation may succeed if retried.
+///
+/// Errors about the filesystem itself (e.g. a missing file) are never transient.
+pub fn is_transient(err: &RemoteError) -> bool {
+    match err.kind {
+        RemoteErrorType::ConnectionError
+        | RemoteErrorType::ProtocolError
+        | RemoteErrorType::StatFailed => {
+            let message = err.to_string().to_lowercase();
+            TRANSIENT_MESSAGES
+                .iter()
+                .any(|transient| message.contains(transient))
+        }
+        _ => false,
+    }
+}
+
+/// Get the delay before the retry number `attempt` (starting from 0)
+pub fn backoff(attempt: u32) -> Duration {
+    BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_DELAY)
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_classify_transient_errors() {
+        assert!(is_transient(&RemoteError::new_ex(
+            RemoteErrorType::ProtocolError,
+            "WebSocket protocol error: Connection reset without closing handshake"
+        )));
+        assert!(is_transient(&RemoteError::new_ex(
+            RemoteErrorType::ProtocolError,
+            "transfer failed after 4096 bytes: unexpected EOF"
+        )));
+        assert!(is_transient(&RemoteError::new_ex(
+            RemoteErrorType::StatFailed,
+            RemoteError::new_ex(RemoteErrorType::ProtocolError, "stream reset by peer")
+        )));
+        assert!(is_transient(&RemoteError::new_ex(
+            RemoteErrorType::ConnectionError,
+            "failed to switch protocol: 503 Service Unavailable"
+        )));
+    }
+
+    #[test]
+    fn should_classify_permanent_errors() {
+        assert!(!is_transient(&RemoteError::new(
+            RemoteErrorType::NoSuchFileOrDirectory
+        )));
+        assert!(!is_transient(&RemoteError::new(
+            RemoteErrorType::NotConnected
+        )));
+        assert!(!is_transient(&RemoteError::new_ex(
+            RemoteErrorType::ProtocolError,
+            "command exited with code 1: permission denied"
+        )));
+        assert!(!is_transient(&RemoteError::new_ex(
+            RemoteErrorType::ProtocolError,
+            "cancelled"
+        )));
+        // the kind wins over the message
+        assert!(!is_transient(&RemoteError::new_ex(
+            RemoteErrorType::AuthenticationFailed,
+            "connection reset"
+        )));
+    }
+
+    #[test]
+    fn should_back_off_exponentially() {
+        assert_eq!(backoff(0), Duration::from_millis(100));
+        assert_eq!(backoff(1), Duration::from_millis(200));
+        assert_eq!(backoff(3), Duration::from_millis(800));
+        assert_eq!(backoff(5), MAX_DELAY);
+        assert_eq!(backoff(u32::MAX), MAX_DELAY);
+    }
+}
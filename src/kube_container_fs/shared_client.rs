@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use kube::{Client, Config};
+use remotefs::fs::{RemoteError, RemoteErrorType, RemoteResult};
+use tokio::runtime::Runtime;
+
+/// A kube client built once and shared by several [`super::KubeContainerFs`],
+/// so that they reuse its connection pool instead of each one authenticating on its own.
+///
+/// Cloning it is cheap and keeps sharing the same connections.
+#[derive(Clone)]
+pub struct SharedKubeClient {
+    client: Client,
+}
+
+impl SharedKubeClient {
+    /// Build the client from `config`
+    pub fn new(config: Config, runtime: &Arc<Runtime>) -> RemoteResult<Self> {
+        // the client spawns its connection pool on the runtime
+        let _guard = runtime.enter();
+        Client::try_from(config)
+            .map(Self::from_client)
+            .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err))
+    }
+
+    /// Share an existing kube client
+    pub fn from_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Get the shared kube client
+    pub fn client(&self) -> Client {
+        self.client.clone()
+    }
+}
@@ -0,0 +1,74 @@
+use remotefs::fs::{RemoteError, RemoteErrorType};
+
+/// Default binary used to run commands with elevated permissions
+pub const DEFAULT_SUDO_BINARY: &str = "sudo";
+
+/// Prefix `cmd` with `binary -n`, if set.
+///
+/// `-n` makes sudo fail instead of prompting for a password, since exec sessions have no tty.
+pub fn prefix_command<S: Into<String>>(binary: Option<&str>, cmd: Vec<S>) -> Vec<String> {
+    binary
+        .map(|binary| vec![binary.to_string(), "-n".to_string()])
+        .unwrap_or_default()
+        .into_iter()
+        .chain(cmd.into_iter().map(Into::into))
+        .collect()
+}
+
+/// Returns whether `stderr` reports that sudo refused to run the command without a password
+pub fn is_password_required(stderr: &str) -> bool {
+    stderr.lines().any(|line| {
+        line.contains("sudo:")
+            && (line.contains("a password is required") || line.contains("a terminal is required"))
+    })
+}
+
+/// Error of sudo requiring a password to run a command with `binary`
+pub fn password_required(binary: &str) -> RemoteError {
+    RemoteError::new_ex(
+        RemoteErrorType::AuthenticationFailed,
+        format!(
+            "{binary} requires a password; passwordless sudo must be allowed for the exec user"
+        ),
+    )
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_prefix_command() {
+        assert_eq!(
+            prefix_command(Some("sudo"), vec!["tar", "cf", "-", "-C", "/root", "a.txt"]),
+            vec!["sudo", "-n", "tar", "cf", "-", "-C", "/root", "a.txt"]
+        );
+        assert_eq!(
+            prefix_command(Some("/usr/bin/doas"), vec!["/bin/sh", "-c", "ls -l"]),
+            vec!["/usr/bin/doas", "-n", "/bin/sh", "-c", "ls -l"]
+        );
+    }
+
+    #[test]
+    fn should_not_prefix_command_without_sudo() {
+        assert_eq!(
+            prefix_command(None, vec!["/bin/sh", "-c", "ls -l"]),
+            vec!["/bin/sh", "-c", "ls -l"]
+        );
+    }
+
+    #[test]
+    fn should_tell_whether_password_is_required() {
+        assert!(is_password_required("sudo: a password is required\n"));
+        assert!(is_password_required(
+            "sudo: a terminal is required to read the password; either use the -S option to read from standard input or configure an askpass helper\n"
+        ));
+        assert!(!is_password_required(""));
+        assert!(!is_password_required(
+            "tar: a.txt: Cannot open: Permission denied\n"
+        ));
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use remotefs::fs::{FileType, Metadata, UnixPex};
+
+use crate::utils::path as path_utils;
+
+/// `stat` reports modification times with seconds precision, which is also the precision `touch -t` sets them with
+const MTIME_PRECISION: Duration = Duration::from_secs(1);
+
+/// Options for `sync_up`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncOptions {
+    /// Remove remote entries which don't exist in the local tree
+    pub delete: bool,
+}
+
+/// Outcome of a `sync_up`; all the paths are relative to the synced roots
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Directories created on the remote
+    pub created_dirs: Vec<PathBuf>,
+    /// Files uploaded, since missing or changed on the remote
+    pub uploaded: Vec<PathBuf>,
+    /// Files whose remote size and mtime already matched, and local symlinks, which aren't synced
+    pub skipped: Vec<PathBuf>,
+    /// Remote entries removed, since missing in the local tree or of another type than the local entry
+    pub deleted: Vec<PathBuf>,
+}
+
+impl SyncReport {
+    /// Plan a sync, comparing the `local` entries with the `remote` ones.
+    ///
+    /// A remote entry of another type than the local one, e.g. a file where a directory is, is always removed,
+    /// since it can't be replaced otherwise.
+    pub(crate) fn plan(
+        local: &[(PathBuf, Metadata)],
+        remote: &HashMap<PathBuf, Metadata>,
+        opts: SyncOptions,
+    ) -> Self {
+        let mut report = Self::default();
+        for (path, metadata) in local {
+            let remote = match remote.get(path) {
+                Some(remote)
+                    if !metadata.is_symlink() && remote.file_type != metadata.file_type =>
+                {
+                    report.deleted.push(path.clone());
+                    None
+                }
+                remote => remote,
+            };
+            if metadata.is_dir() {
+                if !remote.map(|r| r.is_dir()).unwrap_or(false) {
+                    report.created_dirs.push(path.clone());
+                }
+            } else if metadata.is_symlink() {
+                report.skipped.push(path.clone());
+            } else if needs_upload(metadata, remote) {
+                report.uploaded.push(path.clone());
+            } else {
+                report.skipped.push(path.clone());
+            }
+        }
+
+        if opts.delete {
+            let local: HashSet<&Path> = local.iter().map(|(path, _)| path.as_path()).collect();
+            let mut extras: Vec<&PathBuf> = remote
+                .keys()
+                .filter(|path| !local.contains(path.as_path()))
+                .collect();
+            // parents come first, so their children can be skipped
+            extras.sort();
+            for path in extras {
+                if !report.deleted.iter().any(|parent| path.starts_with(parent)) {
+                    report.deleted.push(path.clone());
+                }
+            }
+        }
+
+        report
+    }
+}
+
+/// Whether a local file must be uploaded, given the remote entry at the same path, if any.
+///
+/// Files match if they have the same size and their mtime is the same, within `stat` precision.
+pub(crate) fn needs_upload(local: &Metadata, remote: Option<&Metadata>) -> bool {
+    let Some(remote) = remote else {
+        return true;
+    };
+    if !remote.is_file() || remote.size != local.size {
+        return true;
+    }
+    match (local.modified, remote.modified) {
+        (Some(local), Some(remote)) => !same_mtime(local, remote),
+        _ => true,
+    }
+}
+
+fn same_mtime(a: SystemTime, b: SystemTime) -> bool {
+    let diff = a.duration_since(b).unwrap_or_else(|err| err.duration());
+    diff < MTIME_PRECISION
+}
+
+/// Collect all the entries under `root`, with their path relative to it; parents come before children.
+///
+/// Symlinks aren't followed, as on the remote.
+pub(crate) fn walk_local(root: &Path) -> std::io::Result<Vec<(PathBuf, Metadata)>> {
+    let mut entries = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(root.join(&dir))? {
+            let entry = entry?;
+            let path = dir.join(entry.file_name());
+            let metadata = Metadata::from(std::fs::symlink_metadata(entry.path())?);
+            if metadata.is_dir() {
+                dirs.push(path.clone());
+            }
+            entries.push((path, metadata));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Make the command printing the raw mode, size, mtime and path of all the entries under `root`, one per line.
+///
+/// `stat` reports the mtime in seconds since the epoch, while `ls` omits the year or the time, depending on the age of the file.
+pub(crate) fn stat_tree_cmd(root: &Path) -> String {
+    format!(
+        "find {} -mindepth 1 -exec stat -c '%f %s %Y %n' {{}} +",
+        path_utils::quote(root)
+    )
+}
+
+/// Parse the output of `stat_tree_cmd` for `root`, keyed by the path relative to it; malformed lines are skipped
+pub(crate) fn parse_stat_tree(root: &Path, output: &str) -> HashMap<PathBuf, Metadata> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, ' ');
+            let mode = u32::from_str_radix(fields.next()?, 16).ok()?;
+            let size = fields.next()?.parse::<u64>().ok()?;
+            let mtime = fields.next()?.parse::<u64>().ok()?;
+            let path = Path::new(fields.next()?).strip_prefix(root).ok()?;
+            let file_type = match mode & 0o170000 {
+                0o040000 => FileType::Directory,
+                0o120000 => FileType::Symlink,
+                _ => FileType::File,
+            };
+            let metadata = Metadata::default()
+                .file_type(file_type)
+                .mode(UnixPex::from(mode & 0o7777))
+                .size(size)
+                .modified(SystemTime::UNIX_EPOCH + Duration::from_secs(mtime));
+            Some((path.to_path_buf(), metadata))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_upload_missing_file() {
+        assert!(needs_upload(&file(10, 1000), None));
+    }
+
+    #[test]
+    fn should_not_upload_matching_file() {
+        assert!(!needs_upload(&file(10, 1000), Some(&file(10, 1000))));
+        // stat has seconds precision
+        let local = Metadata::default()
+            .size(10)
+            .modified(SystemTime::UNIX_EPOCH + Duration::from_millis(1_000_500));
+        assert!(!needs_upload(&local, Some(&file(10, 1000))));
+    }
+
+    #[test]
+    fn should_upload_changed_file() {
+        assert!(needs_upload(&file(10, 1000), Some(&file(11, 1000))));
+        assert!(needs_upload(&file(10, 1030), Some(&file(10, 1000))));
+        assert!(needs_upload(&file(10, 1000), Some(&file(10, 2000))));
+        assert!(needs_upload(&file(10, 2000), Some(&file(10, 1000))));
+        assert!(needs_upload(
+            &file(10, 1000),
+            Some(&Metadata::default().size(10))
+        ));
+        assert!(needs_upload(&file(10, 1000), Some(&dir())));
+    }
+
+    #[test]
+    fn should_plan_sync() {
+        let local = vec![
+            (PathBuf::from("a.txt"), file(10, 1000)),
+            (PathBuf::from("b.txt"), file(20, 1000)),
+            (PathBuf::from("dir"), dir()),
+            (PathBuf::from("dir/c.txt"), file(30, 1000)),
+            (PathBuf::from("new"), dir()),
+            (PathBuf::from("new/d.txt"), file(40, 1000)),
+            (
+                PathBuf::from("link"),
+                Metadata::default().file_type(FileType::Symlink),
+            ),
+            (PathBuf::from("was_file"), dir()),
+            (PathBuf::from("was_dir"), file(70, 1000)),
+        ];
+        let remote = HashMap::from([
+            (PathBuf::from("a.txt"), file(10, 1000)),
+            (PathBuf::from("b.txt"), file(25, 1000)),
+            (PathBuf::from("dir"), dir()),
+            (PathBuf::from("dir/c.txt"), file(30, 5000)),
+            (PathBuf::from("old"), dir()),
+            (PathBuf::from("old/e.txt"), file(50, 1000)),
+            (PathBuf::from("z.txt"), file(60, 1000)),
+            (PathBuf::from("was_file"), file(80, 1000)),
+            (PathBuf::from("was_dir"), dir()),
+            (PathBuf::from("was_dir/f.txt"), file(90, 1000)),
+        ]);
+
+        let report = SyncReport::plan(&local, &remote, SyncOptions::default());
+        assert_eq!(
+            report,
+            SyncReport {
+                created_dirs: vec![PathBuf::from("new"), PathBuf::from("was_file")],
+                uploaded: vec![
+                    PathBuf::from("b.txt"),
+                    PathBuf::from("dir/c.txt"),
+                    PathBuf::from("new/d.txt"),
+                    PathBuf::from("was_dir"),
+                ],
+                skipped: vec![PathBuf::from("a.txt"), PathBuf::from("link")],
+                // entries of another type are removed even without `delete`
+                deleted: vec![PathBuf::from("was_file"), PathBuf::from("was_dir")],
+            }
+        );
+
+        let report = SyncReport::plan(&local, &remote, SyncOptions { delete: true });
+        assert_eq!(
+            report.deleted,
+            vec![
+                PathBuf::from("was_file"),
+                PathBuf::from("was_dir"),
+                PathBuf::from("old"),
+                PathBuf::from("z.txt")
+            ]
+        );
+    }
+
+    #[test]
+    fn should_walk_local_tree() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("a/b")).unwrap();
+        std::fs::write(root.path().join("a/b/c.txt"), b"hello").unwrap();
+        std::fs::write(root.path().join("d.txt"), b"hi").unwrap();
+
+        let mut entries = walk_local(root.path()).unwrap();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let paths: Vec<&Path> = entries.iter().map(|(p, _)| p.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("a"),
+                Path::new("a/b"),
+                Path::new("a/b/c.txt"),
+                Path::new("d.txt")
+            ]
+        );
+        assert_eq!(entries[2].1.size, 5);
+        assert!(entries[1].1.is_dir());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn should_not_follow_local_symlinks() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("dir")).unwrap();
+        std::fs::write(root.path().join("dir/a.txt"), b"hello").unwrap();
+        std::os::unix::fs::symlink("dir", root.path().join("link")).unwrap();
+
+        let mut entries = walk_local(root.path()).unwrap();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let paths: Vec<&Path> = entries.iter().map(|(p, _)| p.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![Path::new("dir"), Path::new("dir/a.txt"), Path::new("link")]
+        );
+        assert!(entries[2].1.is_symlink());
+    }
+
+    #[test]
+    fn should_make_stat_tree_cmd() {
+        assert_eq!(
+            stat_tree_cmd(Path::new("/tmp/dest")).as_str(),
+            "find '/tmp/dest' -mindepth 1 -exec stat -c '%f %s %Y %n' {} +"
+        );
+        assert_eq!(
+            stat_tree_cmd(Path::new("/tmp/it's $HOME")).as_str(),
+            r"find '/tmp/it'\''s $HOME' -mindepth 1 -exec stat -c '%f %s %Y %n' {} +"
+        );
+    }
+
+    #[test]
+    fn should_parse_stat_tree() {
+        let output = "41ed 4096 1700000000 /tmp/dest/dir\n81a4 5 946684800 /tmp/dest/dir/my file.txt\na1ff 3 1700000000 /tmp/dest/link\nstat: can't stat '/tmp/dest/x'\n";
+        let entries = parse_stat_tree(Path::new("/tmp/dest"), output);
+        assert_eq!(entries.len(), 3);
+        let dir = &entries[Path::new("dir")];
+        assert!(dir.is_dir());
+        assert_eq!(dir.mode, Some(UnixPex::from(0o755)));
+        // the mtime of old files is exact, unlike the one printed by ls
+        let file = &entries[Path::new("dir/my file.txt")];
+        assert!(file.is_file());
+        assert_eq!(file.size, 5);
+        assert_eq!(file.mode, Some(UnixPex::from(0o644)));
+        assert_eq!(
+            file.modified,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(946684800))
+        );
+        assert!(entries[Path::new("link")].is_symlink());
+    }
+
+    fn file(size: u64, mtime: u64) -> Metadata {
+        Metadata::default()
+            .size(size)
+            .modified(SystemTime::UNIX_EPOCH + Duration::from_secs(mtime))
+    }
+
+    fn dir() -> Metadata {
+        Metadata::default().file_type(FileType::Directory)
+    }
+}
@@ -0,0 +1,147 @@
+/// Default tar binary used for file transfers
+pub const DEFAULT_TAR_BINARY: &str = "tar";
+
+/// Flavor of the tar binary of the container, telling which flags it supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TarFlavor {
+    Gnu,
+    /// Busybox tar, or any other tar supporting only the basic flags
+    Busybox,
+}
+
+impl TarFlavor {
+    /// Get the flavor of tar from the output of `tar --version`
+    pub fn from_version(output: &str) -> Self {
+        if output.contains("GNU tar") {
+            Self::Gnu
+        } else {
+            Self::Busybox
+        }
+    }
+}
+
+/// Get the command archiving the files `names` in the directory `dir` to stdout, compressed with gzip if `gzip`.
+///
+/// If `sparse`, the holes of sparse files are detected and left out of the archive; it requires GNU tar.
+/// `binary` may be a command with arguments, such as `busybox tar`.
+pub fn create_args(
+    binary: &str,
+    dir: &str,
+    names: &[&str],
+    gzip: bool,
+    sparse: bool,
+) -> Vec<String> {
+    let mut args = command(binary);
+    args.push(if gzip { "czf" } else { "cf" }.to_string());
+    args.push("-".to_string());
+    if sparse {
+        args.push("--sparse".to_string());
+    }
+    args.extend(["-C", dir].map(String::from));
+    args.extend(names.iter().map(|name| name.to_string()));
+    args
+}
+
+/// Get the command extracting the archive read from stdin in the directory `dir`, decompressing it with gzip if `gzip`.
+///
+/// `binary` may be a command with arguments, such as `busybox tar`.
+pub fn extract_args(binary: &str, flavor: TarFlavor, dir: &str, gzip: bool) -> Vec<String> {
+    let mut args = command(binary);
+    args.push(if gzip { "xzf" } else { "xf" }.to_string());
+    args.push("-".to_string());
+    if flavor == TarFlavor::Gnu {
+        // the archive mtime may be in the future for the container clock
+        args.push("--warning=no-timestamp".to_string());
+    }
+    args.extend(["-C", dir].map(String::from));
+    args
+}
+
+fn command(binary: &str) -> Vec<String> {
+    binary.split_whitespace().map(String::from).collect()
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_get_tar_flavor() {
+        assert_eq!(
+            TarFlavor::from_version("tar (GNU tar) 1.35\nCopyright (C) 2023"),
+            TarFlavor::Gnu
+        );
+        assert_eq!(
+            TarFlavor::from_version(
+                "tar: unrecognized option '--version'\nBusyBox v1.36.1 (2024-06-10 07:11:47 UTC) multi-call binary."
+            ),
+            TarFlavor::Busybox
+        );
+        assert_eq!(
+            TarFlavor::from_version("bsdtar 3.7.2 - libarchive 3.7.2"),
+            TarFlavor::Busybox
+        );
+    }
+
+    #[test]
+    fn should_make_gnu_tar_extract_args() {
+        assert_eq!(
+            extract_args("/usr/bin/tar", TarFlavor::Gnu, "/tmp", false),
+            vec![
+                "/usr/bin/tar",
+                "xf",
+                "-",
+                "--warning=no-timestamp",
+                "-C",
+                "/tmp"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_make_tar_args() {
+        assert_eq!(
+            create_args("busybox tar", "/tmp", &["a.txt"], false, false),
+            vec!["busybox", "tar", "cf", "-", "-C", "/tmp", "a.txt"]
+        );
+        assert_eq!(
+            extract_args("tar", TarFlavor::Busybox, "/tmp", false),
+            vec!["tar", "xf", "-", "-C", "/tmp"]
+        );
+    }
+
+    #[test]
+    fn should_make_tar_args_for_several_files() {
+        assert_eq!(
+            create_args("tar", "/", &["tmp/a.txt", "etc/hosts"], false, false),
+            vec!["tar", "cf", "-", "-C", "/", "tmp/a.txt", "etc/hosts"]
+        );
+    }
+
+    #[test]
+    fn should_make_gzip_tar_args() {
+        assert_eq!(
+            create_args("tar", "/tmp", &["a.txt"], true, false),
+            vec!["tar", "czf", "-", "-C", "/tmp", "a.txt"]
+        );
+        assert_eq!(
+            extract_args("tar", TarFlavor::Gnu, "/tmp", true),
+            vec!["tar", "xzf", "-", "--warning=no-timestamp", "-C", "/tmp"]
+        );
+    }
+
+    #[test]
+    fn should_make_sparse_tar_args() {
+        assert_eq!(
+            create_args("tar", "/tmp", &["disk.img"], false, true),
+            vec!["tar", "cf", "-", "--sparse", "-C", "/tmp", "disk.img"]
+        );
+        assert_eq!(
+            create_args("tar", "/tmp", &["disk.img"], true, true),
+            vec!["tar", "czf", "-", "--sparse", "-C", "/tmp", "disk.img"]
+        );
+    }
+}
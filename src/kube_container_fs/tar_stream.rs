@@ -0,0 +1,287 @@
+use std::io::Write;
+
+use remotefs::fs::{RemoteError, RemoteErrorType, RemoteResult};
+
+/// Size of the blocks of a tar archive
+const BLOCK_SIZE: usize = 512;
+
+/// Offset and length of the checksum field of a tar header
+const CKSUM_RANGE: std::ops::Range<usize> = 148..156;
+
+/// What the next bytes of the archive are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// A header block, of which some bytes may already be buffered
+    Header,
+    /// Bytes of an extension entry, padding included, to skip
+    Skip(u64),
+    /// Bytes of the data of the entry to write to dest
+    Data(u64),
+    /// Whatever follows the entry, which is discarded
+    Done,
+}
+
+/// Writer of a tar archive, extracting the first entry to `dest` as the archive is written,
+/// so that downloads don't have to buffer the whole archive in a temporary file.
+///
+/// The extension entries describing the next one (PAX headers and GNU long names) are skipped.
+pub struct EntryWriter<'a> {
+    dest: &'a mut (dyn Write + Send),
+    max_bytes: Option<u64>,
+    state: State,
+    header: Vec<u8>,
+    /// Size of the entry, once its header has been read
+    size: Option<u64>,
+    written: u64,
+    limit_exceeded: bool,
+}
+
+impl<'a> EntryWriter<'a> {
+    /// Extract the entry to `dest`, failing if it's larger than `max_bytes`, if set
+    pub fn new(dest: &'a mut (dyn Write + Send), max_bytes: Option<u64>) -> Self {
+        Self {
+            dest,
+            max_bytes,
+            state: State::Header,
+            header: Vec::with_capacity(BLOCK_SIZE),
+            size: None,
+            written: 0,
+            limit_exceeded: false,
+        }
+    }
+
+    /// Bytes of the entry written to dest so far
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Whether the write failed because the entry is larger than `max_bytes`
+    pub fn limit_exceeded(&self) -> bool {
+        self.limit_exceeded
+    }
+
+    /// Check that the whole entry has been written once the archive has ended, returning its size
+    pub fn finish(&self) -> RemoteResult<u64> {
+        match self.size {
+            None => Err(RemoteError::new(RemoteErrorType::NoSuchFileOrDirectory)),
+            Some(size) if self.written < size => Err(RemoteError::new_ex(
+                RemoteErrorType::IoError,
+                format!(
+                    "truncated archive: extracted {} of {size} bytes",
+                    self.written
+                ),
+            )),
+            Some(size) => Ok(size),
+        }
+    }
+
+    /// Parse the buffered header block, returning what follows it
+    fn parse_header(&mut self) -> std::io::Result<State> {
+        // two zero blocks end the archive; one is enough to tell there are no entries
+        if self.header.iter().all(|b| *b == 0) {
+            return Ok(State::Done);
+        }
+        if checksum(&self.header) != parse_octal(&self.header[CKSUM_RANGE]) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid tar header checksum",
+            ));
+        }
+        let header = tar::Header::from_byte_slice(&self.header);
+        let entry_type = header.entry_type();
+        let size = header.entry_size()?;
+        if entry_type.is_pax_local_extensions()
+            || entry_type.is_pax_global_extensions()
+            || entry_type.is_gnu_longname()
+            || entry_type.is_gnu_longlink()
+        {
+            return Ok(State::Skip(
+                size.div_ceil(BLOCK_SIZE as u64) * BLOCK_SIZE as u64,
+            ));
+        }
+        if entry_type.is_gnu_sparse() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "sparse entries can't be extracted while downloading",
+            ));
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            if size > max_bytes {
+                self.limit_exceeded = true;
+                return Err(std::io::Error::other(format!(
+                    "entry of {size} bytes exceeds the limit of {max_bytes} bytes"
+                )));
+            }
+        }
+        self.size = Some(size);
+
+        Ok(match size {
+            0 => State::Done,
+            size => State::Data(size),
+        })
+    }
+}
+
+impl Write for EntryWriter<'_> {
+    fn write(&mut self, mut buf: &[u8]) -> std::io::Result<usize> {
+        let len = buf.len();
+        while !buf.is_empty() {
+            match self.state {
+                State::Header => {
+                    let take = (BLOCK_SIZE - self.header.len()).min(buf.len());
+                    self.header.extend_from_slice(&buf[..take]);
+                    buf = &buf[take..];
+                    if self.header.len() == BLOCK_SIZE {
+                        self.state = self.parse_header()?;
+                        self.header.clear();
+                    }
+                }
+                State::Skip(remaining) => {
+                    let take = remaining.min(buf.len() as u64);
+                    buf = &buf[take as usize..];
+                    self.state = match remaining - take {
+                        0 => State::Header,
+                        remaining => State::Skip(remaining),
+                    };
+                }
+                State::Data(remaining) => {
+                    let take = remaining.min(buf.len() as u64);
+                    self.dest.write_all(&buf[..take as usize])?;
+                    self.written += take;
+                    buf = &buf[take as usize..];
+                    self.state = match remaining - take {
+                        0 => State::Done,
+                        remaining => State::Data(remaining),
+                    };
+                }
+                State::Done => break,
+            }
+        }
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.dest.flush()
+    }
+}
+
+/// Compute the checksum of the tar header `block`, counting its checksum field as spaces
+fn checksum(block: &[u8]) -> u32 {
+    block
+        .iter()
+        .enumerate()
+        .map(|(i, b)| match CKSUM_RANGE.contains(&i) {
+            true => u32::from(b' '),
+            false => u32::from(*b),
+        })
+        .sum()
+}
+
+/// Parse the octal number of a tar header field, padded with spaces and NULs
+fn parse_octal(field: &[u8]) -> u32 {
+    let digits = String::from_utf8_lossy(field);
+    u32::from_str_radix(digits.trim_matches(|c| c == ' ' || c == '\0'), 8).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Make an archive with a file at `path` holding `data`, followed by a second file
+    fn make_archive(path: &str, data: &[u8]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        builder.append_data(&mut header, path, data).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        builder
+            .append_data(&mut header, "other.txt", &b"xyz"[..])
+            .unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn should_extract_entry_in_any_chunk_size() {
+        let data: Vec<u8> = (0..1300).map(|i| (i % 251) as u8).collect();
+        let archive = make_archive("a.bin", &data);
+        for chunk_size in [1, 7, 512, 1000, archive.len()] {
+            let mut dest = Vec::new();
+            let mut writer = EntryWriter::new(&mut dest, None);
+            for chunk in archive.chunks(chunk_size) {
+                writer.write_all(chunk).unwrap();
+            }
+            assert_eq!(writer.finish().unwrap(), 1300);
+            assert_eq!(dest, data, "chunk size {chunk_size}");
+        }
+    }
+
+    #[test]
+    fn should_skip_long_name_entries() {
+        let name = format!("{}/a.txt", "d".repeat(200));
+        let archive = make_archive(&name, b"hello");
+        let mut dest = Vec::new();
+        let mut writer = EntryWriter::new(&mut dest, None);
+        writer.write_all(&archive).unwrap();
+        assert_eq!(writer.finish().unwrap(), 5);
+        assert_eq!(dest, b"hello");
+    }
+
+    #[test]
+    fn should_extract_gzipped_entry() {
+        let archive = make_archive("a.txt", b"hello");
+        let compressed = crate::kube_container_fs::transfer::gzip(&archive).unwrap();
+        let mut dest = Vec::new();
+        let mut writer = EntryWriter::new(&mut dest, None);
+        let mut decoder = flate2::write::GzDecoder::new(&mut writer);
+        decoder.write_all(&compressed).unwrap();
+        decoder.try_finish().unwrap();
+        drop(decoder);
+        assert_eq!(writer.finish().unwrap(), 5);
+        assert_eq!(dest, b"hello");
+    }
+
+    #[test]
+    fn should_not_extract_entry_over_limit() {
+        let archive = make_archive("a.txt", b"hello");
+        let mut dest = Vec::new();
+        let mut writer = EntryWriter::new(&mut dest, Some(4));
+        assert!(writer.write_all(&archive).is_err());
+        assert!(writer.limit_exceeded());
+        assert_eq!(writer.written(), 0);
+        drop(writer);
+        assert!(dest.is_empty());
+    }
+
+    #[test]
+    fn should_report_missing_and_truncated_entries() {
+        let mut dest = Vec::new();
+        let mut writer = EntryWriter::new(&mut dest, None);
+        assert_eq!(
+            writer.finish().unwrap_err().kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+        writer.write_all(&[0; 1024]).unwrap();
+        assert_eq!(
+            writer.finish().unwrap_err().kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+
+        let archive = make_archive("a.txt", &[1; 1024]);
+        let mut writer = EntryWriter::new(&mut dest, None);
+        writer.write_all(&archive[..1024]).unwrap();
+        assert_eq!(writer.written(), 512);
+        assert_eq!(writer.finish().unwrap_err().kind, RemoteErrorType::IoError);
+    }
+
+    #[test]
+    fn should_reject_invalid_header() {
+        let mut dest = Vec::new();
+        let mut writer = EntryWriter::new(&mut dest, None);
+        assert!(writer.write_all(&[b'a'; 512]).is_err());
+    }
+}
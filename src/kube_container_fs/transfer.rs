@@ -0,0 +1,662 @@
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Status;
+use kube::api::AttachedProcess;
+use remotefs::fs::{RemoteError, RemoteErrorType, RemoteResult, UnixPex};
+use thiserror::Error;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt as _, AsyncRead, AsyncReadExt as _, AsyncWrite,
+    AsyncWriteExt as _,
+};
+
+use crate::KubeFsError;
+
+/// A file to append to a tar archive
+pub struct ArchiveEntry {
+    /// Path of the file in the archive
+    pub path: PathBuf,
+    pub size: u64,
+    /// Mode of the file; if unset, the tar header default is kept
+    pub mode: Option<UnixPex>,
+    pub reader: Box<dyn std::io::Read + Send>,
+}
+
+/// Error of a transfer failed partway, reporting the amount of bytes transferred until then
+#[derive(Debug, Error)]
+#[error("transfer failed after {transferred} bytes: {source}")]
+pub struct TransferError {
+    /// Bytes transferred before the failure
+    pub transferred: u64,
+    #[source]
+    pub source: std::io::Error,
+}
+
+impl From<TransferError> for RemoteError {
+    fn from(err: TransferError) -> Self {
+        RemoteError::new_ex(RemoteErrorType::ProtocolError, err)
+    }
+}
+
+/// Write `data` to `writer` in chunks of `chunk_size` bytes, returning the bytes written
+pub async fn write_chunks<W>(
+    writer: &mut W,
+    data: &[u8],
+    chunk_size: usize,
+) -> Result<u64, TransferError>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut transferred = 0;
+    for chunk in data.chunks(chunk_size) {
+        writer
+            .write_all(chunk)
+            .await
+            .map_err(|source| TransferError {
+                transferred,
+                source,
+            })?;
+        transferred += chunk.len() as u64;
+    }
+
+    Ok(transferred)
+}
+
+/// Copy `reader` to `writer` until EOF, returning the bytes copied
+pub async fn copy_buf<R, W>(reader: &mut R, writer: &mut W) -> Result<u64, TransferError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut transferred = 0;
+    let failed = |transferred, source| TransferError {
+        transferred,
+        source,
+    };
+    loop {
+        let buf = reader
+            .fill_buf()
+            .await
+            .map_err(|err| failed(transferred, err))?;
+        if buf.is_empty() {
+            break;
+        }
+        let len = buf.len();
+        writer
+            .write_all(buf)
+            .await
+            .map_err(|err| failed(transferred, err))?;
+        reader.consume(len);
+        transferred += len as u64;
+    }
+    writer
+        .flush()
+        .await
+        .map_err(|err| failed(transferred, err))?;
+
+    Ok(transferred)
+}
+
+/// Copy `reader` to the blocking `writer` until EOF, returning the bytes copied
+pub async fn copy_buf_to_sync<R>(
+    reader: &mut R,
+    writer: &mut impl std::io::Write,
+) -> Result<u64, TransferError>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut transferred = 0;
+    let failed = |transferred, source| TransferError {
+        transferred,
+        source,
+    };
+    loop {
+        let buf = reader
+            .fill_buf()
+            .await
+            .map_err(|err| failed(transferred, err))?;
+        if buf.is_empty() {
+            break;
+        }
+        let len = buf.len();
+        writer
+            .write_all(buf)
+            .map_err(|err| failed(transferred, err))?;
+        reader.consume(len);
+        transferred += len as u64;
+    }
+    writer.flush().map_err(|err| failed(transferred, err))?;
+
+    Ok(transferred)
+}
+
+/// Copy `reader` to `writer` like `copy_buf`, but stop reading and fail once more than `limit` bytes are read
+pub async fn copy_buf_limited<R, W>(reader: &mut R, writer: &mut W, limit: u64) -> RemoteResult<u64>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = reader.take(limit.saturating_add(1));
+    let transferred = copy_buf(&mut reader, writer).await?;
+    if transferred > limit {
+        return Err(size_limit_exceeded(limit));
+    }
+
+    Ok(transferred)
+}
+
+/// Error of a download exceeding its size limit
+pub fn size_limit_exceeded(limit: u64) -> RemoteError {
+    RemoteError::new_ex(
+        RemoteErrorType::IoError,
+        format!("file exceeds the size limit of {limit} bytes"),
+    )
+}
+
+/// Read `reader` until EOF, then decode it as UTF-8, replacing invalid sequences
+pub async fn read_to_string_lossy<R>(reader: &mut R) -> std::io::Result<String>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output).await?;
+
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Read the stdout and the stderr of `process` until EOF, decoding stderr lossily.
+///
+/// Both streams are read at once, so that neither blocks the other when full.
+/// Stderr is empty if it isn't attached, e.g. with a TTY, but must be if `stderr_required`.
+pub async fn read_output(
+    process: &mut AttachedProcess,
+    stderr_required: bool,
+) -> RemoteResult<(Vec<u8>, String)> {
+    let mut stdout_reader = process.stdout().ok_or_else(|| {
+        RemoteError::new_ex(RemoteErrorType::ProtocolError, "failed to read stdout")
+    })?;
+    let mut stderr_reader = process.stderr();
+    if stderr_reader.is_none() && stderr_required {
+        return Err(RemoteError::new_ex(
+            RemoteErrorType::ProtocolError,
+            "failed to read stderr",
+        ));
+    }
+    let mut stdout = Vec::new();
+    let (stdout_res, stderr) = tokio::join!(stdout_reader.read_to_end(&mut stdout), async {
+        match stderr_reader.as_mut() {
+            Some(reader) => read_to_string_lossy(reader).await,
+            None => Ok(String::new()),
+        }
+    });
+    stdout_res.map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+    let stderr = stderr.map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
+
+    Ok((stdout, stderr))
+}
+
+/// Get the exit code of an exec'd process from its final status.
+///
+/// A missing status fails, since whether the command succeeded is unknown, e.g. if the connection dropped.
+pub fn exit_code(status: Option<&Status>) -> RemoteResult<u32> {
+    match status {
+        Some(status) => Ok(reported_exit_code(Some(status)).unwrap_or(1)),
+        None => Err(RemoteError::new_ex(
+            RemoteErrorType::ProtocolError,
+            "the exit status of the command is unknown",
+        )),
+    }
+}
+
+/// Get the exit code reported by the final status of an exec'd process, if any
+pub fn reported_exit_code(status: Option<&Status>) -> Option<u32> {
+    let status = status?;
+    if status.status.as_deref() == Some("Success") {
+        return Some(0);
+    }
+    status
+        .details
+        .as_ref()
+        .and_then(|details| details.causes.as_ref())
+        .and_then(|causes| {
+            causes
+                .iter()
+                .find(|cause| cause.reason.as_deref() == Some("ExitCode"))
+        })
+        .and_then(|cause| cause.message.as_deref())
+        .and_then(|rc| rc.parse().ok())
+}
+
+/// Get the message of the final status of an exec'd process, if it failed because the command doesn't exist
+pub fn command_not_found(status: Option<&Status>) -> Option<&str> {
+    let status = status?;
+    if status.status.as_deref() == Some("Success") {
+        return None;
+    }
+    status.message.as_deref().filter(|message| {
+        message.contains("executable file not found")
+            || message.contains("no such file or directory")
+    })
+}
+
+/// Length of the lines of the `base64` tool output
+const BASE64_LINE_LEN: usize = 76;
+
+/// Encode `data` as base64, wrapped in lines like the `base64` tool output
+pub fn encode_base64(data: &[u8]) -> String {
+    let encoded = BASE64_STANDARD.encode(data);
+    let mut output = String::with_capacity(encoded.len() + encoded.len() / BASE64_LINE_LEN + 1);
+    for line in encoded.as_bytes().chunks(BASE64_LINE_LEN) {
+        // base64 is ascii
+        output.push_str(std::str::from_utf8(line).unwrap_or_default());
+        output.push('\n');
+    }
+    output
+}
+
+/// Decode the output of the `base64` tool, ignoring line breaks and whitespace
+pub fn decode_base64(output: &str) -> RemoteResult<Vec<u8>> {
+    let encoded: String = output.chars().filter(|c| !c.is_whitespace()).collect();
+    BASE64_STANDARD
+        .decode(encoded)
+        .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))
+}
+
+/// Compress `data` with gzip, as read by `tar xzf`
+pub fn gzip(data: &[u8]) -> RemoteResult<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .map_err(|err| RemoteError::new_ex(RemoteErrorType::IoError, err))
+}
+
+/// Decompress the gzip stream read from `reader`, as written by `tar czf`, to `writer`
+pub fn gunzip(reader: impl std::io::Read, writer: &mut impl std::io::Write) -> RemoteResult<u64> {
+    std::io::copy(&mut GzDecoder::new(reader), writer)
+        .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))
+}
+
+/// Check the outcome of a `tar xf` extraction from its exit code and stderr
+pub fn check_extract(rc: u32, stderr: &str) -> RemoteResult<()> {
+    check_write("tar", rc, stderr)
+}
+
+/// Check the outcome of the command `name` writing files, from its exit code and stderr
+pub fn check_write(name: &str, rc: u32, stderr: &str) -> RemoteResult<()> {
+    if stderr.contains("No space left on device") {
+        return Err(KubeFsError::NoSpace.into_remote(RemoteErrorType::IoError));
+    }
+    let message = format!("{name} exited with code {rc}: {}", stderr.trim());
+    match rc {
+        0 => Ok(()),
+        _ if stderr.contains("Permission denied") => Err(KubeFsError::PermissionDenied
+            .into_remote_with(RemoteErrorType::FileCreateDenied, message)),
+        _ => Err(RemoteError::new_ex(
+            RemoteErrorType::FileCreateDenied,
+            message,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{StatusCause, StatusDetails};
+    use pretty_assertions::assert_eq;
+    use tokio::io::{BufReader, ReadBuf};
+
+    use super::*;
+
+    #[test]
+    fn should_write_chunks() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut writer = Vec::new();
+        let data = vec![1u8; 1000];
+        assert_eq!(
+            rt.block_on(write_chunks(&mut writer, &data, 512)).unwrap(),
+            1000
+        );
+        assert_eq!(writer, data);
+    }
+
+    #[test]
+    fn should_report_progress_on_write_failure() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut writer = FailingWriter { capacity: 1024 };
+        let data = vec![1u8; 4096];
+        let err = rt
+            .block_on(write_chunks(&mut writer, &data, 512))
+            .err()
+            .unwrap();
+        assert_eq!(err.transferred, 1024);
+        let err = RemoteError::from(err);
+        assert_eq!(err.kind, RemoteErrorType::ProtocolError);
+        assert!(err.to_string().contains("after 1024 bytes"));
+    }
+
+    #[test]
+    fn should_copy_buf() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let data = vec![1u8; 3000];
+        let mut reader = BufReader::with_capacity(512, data.as_slice());
+        let mut writer = Vec::new();
+        assert_eq!(
+            rt.block_on(copy_buf(&mut reader, &mut writer)).unwrap(),
+            3000
+        );
+        assert_eq!(writer, data);
+    }
+
+    #[test]
+    fn should_report_progress_on_read_failure() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut reader = BufReader::with_capacity(512, FailingReader { available: 2048 });
+        let mut writer = Vec::new();
+        let err = rt
+            .block_on(copy_buf(&mut reader, &mut writer))
+            .err()
+            .unwrap();
+        assert_eq!(err.transferred, 2048);
+        assert_eq!(writer.len(), 2048);
+    }
+
+    #[test]
+    fn should_copy_buf_within_limit() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let data = vec![1u8; 3000];
+        let mut reader = BufReader::with_capacity(512, data.as_slice());
+        let mut writer = Vec::new();
+        assert_eq!(
+            rt.block_on(copy_buf_limited(&mut reader, &mut writer, 3000))
+                .unwrap(),
+            3000
+        );
+        assert_eq!(writer, data);
+    }
+
+    #[test]
+    fn should_stop_copy_buf_over_limit() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let data = vec![1u8; 1024 * 1024];
+        let mut reader = BufReader::with_capacity(512, data.as_slice());
+        let mut writer = Vec::new();
+        let err = rt
+            .block_on(copy_buf_limited(&mut reader, &mut writer, 2048))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::IoError);
+        assert!(err.to_string().contains("size limit of 2048 bytes"));
+        // the transfer stopped right after the limit
+        assert_eq!(writer.len(), 2049);
+    }
+
+    #[test]
+    fn should_read_output_split_across_chunks() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        // a long listing with multi-byte characters, split in small chunks
+        let output = (0..100)
+            .map(|i| format!("-rw-r--r--    1 root     root   5 Nov  5 13:46 café_{i}.txt\n"))
+            .collect::<String>();
+        assert!(output.len() > 2048);
+        let chunks = output
+            .as_bytes()
+            .chunks(7)
+            .map(Ok::<_, io::Error>)
+            .collect::<Vec<_>>();
+        let mut reader = tokio_util::io::StreamReader::new(futures_util::stream::iter(chunks));
+        assert_eq!(
+            rt.block_on(read_to_string_lossy(&mut reader)).unwrap(),
+            output
+        );
+    }
+
+    #[test]
+    fn should_get_exit_code() {
+        assert_eq!(
+            exit_code(None).unwrap_err().kind,
+            RemoteErrorType::ProtocolError
+        );
+        let success = Status {
+            status: Some("Success".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(exit_code(Some(&success)).unwrap(), 0);
+        let failure = Status {
+            status: Some("Failure".to_string()),
+            details: Some(StatusDetails {
+                causes: Some(vec![StatusCause {
+                    reason: Some("ExitCode".to_string()),
+                    message: Some("2".to_string()),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(exit_code(Some(&failure)).unwrap(), 2);
+        let failure = Status {
+            status: Some("Failure".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(exit_code(Some(&failure)).unwrap(), 1);
+    }
+
+    #[test]
+    fn should_get_reported_exit_code() {
+        assert_eq!(reported_exit_code(None), None);
+        let success = Status {
+            status: Some("Success".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(reported_exit_code(Some(&success)), Some(0));
+        let failure = Status {
+            status: Some("Failure".to_string()),
+            details: Some(StatusDetails {
+                causes: Some(vec![StatusCause {
+                    reason: Some("ExitCode".to_string()),
+                    message: Some("127".to_string()),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(reported_exit_code(Some(&failure)), Some(127));
+        let failure = Status {
+            status: Some("Failure".to_string()),
+            message: Some("executable file not found in $PATH".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(reported_exit_code(Some(&failure)), None);
+    }
+
+    #[test]
+    fn should_classify_extract_errors() {
+        assert!(check_extract(0, "").is_ok());
+        let err = check_extract(
+            2,
+            "tar: a.txt: Wrote only 512 of 10240 bytes\ntar: a.txt: No space left on device\n",
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::IoError);
+        assert!(err.to_string().contains("no space left on device"));
+        assert_eq!(KubeFsError::from_remote(&err), Some(KubeFsError::NoSpace));
+        // reported even if tar exits with 0
+        assert_eq!(
+            check_extract(0, "tar: write error: No space left on device")
+                .err()
+                .unwrap()
+                .kind,
+            RemoteErrorType::IoError
+        );
+        let err = check_extract(2, "tar: a.txt: Cannot open: Permission denied\n")
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, RemoteErrorType::FileCreateDenied);
+        assert!(err.to_string().contains("Permission denied"));
+        assert_eq!(
+            KubeFsError::from_remote(&err),
+            Some(KubeFsError::PermissionDenied)
+        );
+        let err = check_extract(
+            2,
+            "tar: invalid magic
+",
+        )
+        .err()
+        .unwrap();
+        assert_eq!(KubeFsError::from_remote(&err), None);
+    }
+
+    #[test]
+    fn should_tell_whether_command_was_not_found() {
+        let not_found = Status {
+            status: Some("Failure".to_string()),
+            message: Some(
+                r#"OCI runtime exec failed: exec failed: unable to start container process: exec: "/bin/sh": stat /bin/sh: no such file or directory: unknown"#
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        assert!(command_not_found(Some(&not_found)).is_some());
+        let not_found = Status {
+            status: Some("Failure".to_string()),
+            message: Some(
+                r#"exec: "sudo": executable file not found in $PATH: unknown"#.to_string(),
+            ),
+            ..Default::default()
+        };
+        assert!(command_not_found(Some(&not_found)).is_some());
+        let exited = Status {
+            status: Some("Failure".to_string()),
+            message: Some("command terminated with non-zero exit code: error executing command [/bin/sh -c ls], exit code 2".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(command_not_found(Some(&exited)), None);
+        assert_eq!(command_not_found(None), None);
+    }
+
+    #[test]
+    fn should_encode_base64_in_lines() {
+        let data: Vec<u8> = (0..=255).collect();
+        let encoded = encode_base64(&data);
+        assert!(encoded.ends_with('\n'));
+        let lines: Vec<&str> = encoded.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[..4].iter().all(|line| line.len() == BASE64_LINE_LEN));
+        assert_eq!(decode_base64(&encoded).unwrap(), data);
+        assert_eq!(encode_base64(&[]), "");
+    }
+
+    #[test]
+    fn should_round_trip_gzip() {
+        let data = "compressible ".repeat(4096).into_bytes();
+        let compressed = gzip(&data).unwrap();
+        assert!(compressed.len() < data.len() / 10);
+        let mut decompressed = Vec::new();
+        assert_eq!(
+            gunzip(compressed.as_slice(), &mut decompressed).unwrap(),
+            data.len() as u64
+        );
+        assert_eq!(decompressed, data);
+        // not a gzip stream
+        assert!(gunzip(data.as_slice(), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn should_decode_base64_output() {
+        // output of `printf 'hello\0\377' | base64`
+        assert_eq!(
+            decode_base64("aGVsbG8A/w==\n").unwrap(),
+            b"hello\0\xff".to_vec()
+        );
+        assert_eq!(decode_base64("").unwrap(), Vec::<u8>::new());
+        let data: Vec<u8> = (0..4096).map(|i| (i * 7 % 256) as u8).collect();
+        let wrapped = encode_base64(&data).replace('\n', "\r\n");
+        assert_eq!(decode_base64(&wrapped).unwrap(), data);
+        assert_eq!(
+            decode_base64("not base64!").err().unwrap().kind,
+            RemoteErrorType::ProtocolError
+        );
+    }
+
+    /// Writer accepting `capacity` bytes, then failing
+    struct FailingWriter {
+        capacity: usize,
+    }
+
+    impl AsyncWrite for FailingWriter {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if self.capacity == 0 {
+                return Poll::Ready(Err(io::Error::from(io::ErrorKind::BrokenPipe)));
+            }
+            let len = buf.len().min(self.capacity);
+            self.capacity -= len;
+            Poll::Ready(Ok(len))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Reader yielding `available` bytes, then failing
+    struct FailingReader {
+        available: usize,
+    }
+
+    impl AsyncRead for FailingReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if self.available == 0 {
+                return Poll::Ready(Err(io::Error::from(io::ErrorKind::ConnectionReset)));
+            }
+            let len = buf.remaining().min(self.available);
+            buf.put_slice(&vec![1u8; len]);
+            self.available -= len;
+            Poll::Ready(Ok(()))
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use futures_util::StreamExt as _;
+use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::runtime::watcher::{self, Event};
+use kube::Api;
+
+/// State of the watch over the pod the client is connected to
+#[derive(Debug)]
+pub(crate) struct PodWatch {
+    /// UID of the watched pod; a pod with the same name but another UID replaced it
+    uid: Option<String>,
+    /// Whether the pod has been listed since the last watch restart
+    seen: bool,
+    deleted: bool,
+}
+
+impl PodWatch {
+    pub fn new(uid: Option<String>) -> Self {
+        Self {
+            uid,
+            seen: true,
+            deleted: false,
+        }
+    }
+
+    /// Update the state with a watch event on the pod; returns whether the pod has been deleted
+    pub fn on_event(&mut self, event: &Event<Pod>) -> bool {
+        match event {
+            Event::Apply(pod) if !self.is_watched(pod) => self.deleted = true,
+            Event::Apply(_) => {}
+            Event::Delete(pod) if self.is_watched(pod) => self.deleted = true,
+            Event::Delete(_) => {}
+            Event::Init => self.seen = false,
+            Event::InitApply(pod) if self.is_watched(pod) => self.seen = true,
+            Event::InitApply(_) => self.deleted = true,
+            Event::InitDone if !self.seen => self.deleted = true,
+            Event::InitDone => {}
+        }
+        self.deleted
+    }
+
+    fn is_watched(&self, pod: &Pod) -> bool {
+        self.uid.is_none() || pod.metadata.uid == self.uid
+    }
+}
+
+/// Watch the pod with `metadata` until it is deleted, then set `deleted`
+pub(crate) async fn watch_pod(api: Api<Pod>, metadata: ObjectMeta, deleted: Arc<AtomicBool>) {
+    let Some(name) = metadata.name else {
+        return;
+    };
+    let mut state = PodWatch::new(metadata.uid);
+    let config = watcher::Config::default().fields(&format!("metadata.name={name}"));
+    let mut events = watcher::watcher(api, config).boxed();
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(event) if state.on_event(&event) => {
+                debug!("pod {name} has been deleted");
+                deleted.store(true, Ordering::Relaxed);
+                return;
+            }
+            Ok(_) => {}
+            Err(err) => debug!("error watching pod {name}: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_not_be_deleted_on_updates() {
+        let mut watch = PodWatch::new(Some("uid-1".to_string()));
+        assert!(!watch.on_event(&Event::Apply(pod("uid-1"))));
+        assert!(!watch.on_event(&Event::Init));
+        assert!(!watch.on_event(&Event::InitApply(pod("uid-1"))));
+        assert!(!watch.on_event(&Event::InitDone));
+    }
+
+    #[test]
+    fn should_be_deleted_on_delete_event() {
+        let mut watch = PodWatch::new(Some("uid-1".to_string()));
+        assert!(watch.on_event(&Event::Delete(pod("uid-1"))));
+        // deletion is permanent
+        assert!(watch.on_event(&Event::Apply(pod("uid-1"))));
+    }
+
+    #[test]
+    fn should_be_deleted_when_missing_after_restart() {
+        let mut watch = PodWatch::new(Some("uid-1".to_string()));
+        assert!(!watch.on_event(&Event::Init));
+        assert!(watch.on_event(&Event::InitDone));
+    }
+
+    #[test]
+    fn should_be_deleted_when_replaced() {
+        let mut watch = PodWatch::new(Some("uid-1".to_string()));
+        assert!(watch.on_event(&Event::Apply(pod("uid-2"))));
+
+        let mut watch = PodWatch::new(Some("uid-1".to_string()));
+        assert!(!watch.on_event(&Event::Init));
+        assert!(watch.on_event(&Event::InitApply(pod("uid-2"))));
+    }
+
+    #[test]
+    fn should_ignore_deletion_of_other_pods() {
+        let mut watch = PodWatch::new(Some("uid-1".to_string()));
+        assert!(!watch.on_event(&Event::Delete(pod("uid-2"))));
+    }
+
+    fn pod(uid: &str) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some("test".to_string()),
+                uid: Some(uid.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
@@ -2,22 +2,34 @@
 //!
 //! The `KubeMultiPodFs` client is a client that allows you to interact with multiple pods in a Kubernetes cluster.
 
+mod container;
 mod path;
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-use k8s_openapi::api::core::v1::Pod;
-use kube::{Api, Client, Config};
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec,
+};
+use k8s_openapi::api::core::v1::{Namespace, Pod};
+use kube::api::{ListParams, PostParams};
+use kube::{Api, Config};
 use remotefs::fs::{
     FileType, Metadata, ReadStream, RemoteError, RemoteErrorType, RemoteFs, RemoteResult, UnixPex,
     Welcome, WriteStream,
 };
 use remotefs::File;
 use tokio::runtime::Runtime;
+use tokio_util::sync::CancellationToken;
+
+pub use self::container::ContainerEntry;
+use self::path::{KubePath, NAMESPACE_PREFIX};
+use crate::error::kube_error;
+use crate::{KubeContainerFs, MetricsSink};
 
-use self::path::KubePath;
-use crate::KubeContainerFs;
+/// Result of a command executed in a pod: the pod name, along with the exit code and output of the command
+pub type PodExecResult = (String, RemoteResult<(u32, String)>);
 
 /// Kube MultiPod FS
 ///
@@ -31,6 +43,10 @@ use crate::KubeContainerFs;
 /// /pod-name/container-name/path/to/file
 pub struct KubeMultiPodFs {
     kube: KubeContainerFs,
+    /// Namespace of the pods API of `kube`, entered through a `/ns:<namespace>` path; the default one if `None`
+    namespace: Option<String>,
+    /// Label selector of the pods listed in the root directory
+    pod_selector: Option<String>,
     runtime: Arc<Runtime>,
 }
 
@@ -39,6 +55,8 @@ impl KubeMultiPodFs {
     pub fn new(runtime: &Arc<Runtime>) -> Self {
         Self {
             kube: KubeContainerFs::new("", "", runtime),
+            namespace: None,
+            pod_selector: None,
             runtime: runtime.clone(),
         }
     }
@@ -49,6 +67,367 @@ impl KubeMultiPodFs {
         self
     }
 
+    /// Load the configuration from the kubeconfig file at `path`, instead of the default one.
+    ///
+    /// See [`KubeContainerFs::kubeconfig_path`]
+    pub fn kubeconfig_path(mut self, path: PathBuf) -> Self {
+        self.kube = self.kube.kubeconfig_path(path);
+        self
+    }
+
+    /// Load the configuration merging the kubeconfig files at `paths`.
+    ///
+    /// See [`KubeContainerFs::kubeconfig_paths`]
+    pub fn kubeconfig_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.kube = self.kube.kubeconfig_paths(paths);
+        self
+    }
+
+    /// Set the timeout for establishing the connection to the cluster.
+    ///
+    /// See [`KubeContainerFs::connect_timeout`]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.kube = self.kube.connect_timeout(timeout);
+        self
+    }
+
+    /// Set whether `copy` and `mov` should create the missing parent directories of the destination.
+    ///
+    /// See [`KubeContainerFs::create_parents`]
+    pub fn create_parents(mut self, create_parents: bool) -> Self {
+        self.kube = self.kube.create_parents(create_parents);
+        self
+    }
+
+    /// Set whether `copy` and `mov` may overwrite an existing destination.
+    ///
+    /// See [`KubeContainerFs::overwrite`]
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.kube = self.kube.overwrite(overwrite);
+        self
+    }
+
+    /// Set whether `create_file` should write files atomically.
+    ///
+    /// See [`KubeContainerFs::atomic`]
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.kube = self.kube.atomic(atomic);
+        self
+    }
+
+    /// Set how many times the idempotent operations are retried on transient transport errors.
+    ///
+    /// See [`KubeContainerFs::op_retries`]
+    pub fn op_retries(mut self, count: u32) -> Self {
+        self.kube = self.kube.op_retries(count);
+        self
+    }
+
+    /// Set whether the working directory is stored with symlinks resolved.
+    ///
+    /// See [`KubeContainerFs::physical_paths`]
+    pub fn physical_paths(mut self, physical_paths: bool) -> Self {
+        self.kube = self.kube.physical_paths(physical_paths);
+        self
+    }
+
+    /// Set whether listing a directory fails if a line of the `ls` output can't be parsed.
+    ///
+    /// See [`KubeContainerFs::strict_listing`]
+    pub fn strict_listing(mut self, strict: bool) -> Self {
+        self.kube = self.kube.strict_listing(strict);
+        self
+    }
+
+    /// Set the tar binary used for file transfers.
+    ///
+    /// See [`KubeContainerFs::tar_binary`]
+    pub fn tar_binary(mut self, binary: impl ToString) -> Self {
+        self.kube = self.kube.tar_binary(binary);
+        self
+    }
+
+    /// Set whether commands and file transfers run with elevated permissions, through `sudo -n`.
+    ///
+    /// See [`KubeContainerFs::sudo`]
+    pub fn sudo(mut self, sudo: bool) -> Self {
+        self.kube = self.kube.sudo(sudo);
+        self
+    }
+
+    /// Set the binary used to elevate permissions when `sudo` is enabled.
+    ///
+    /// See [`KubeContainerFs::sudo_binary`]
+    pub fn sudo_binary(mut self, binary: impl ToString) -> Self {
+        self.kube = self.kube.sudo_binary(binary);
+        self
+    }
+
+    /// Set the mode of the uploaded files when `metadata.mode` is unset.
+    ///
+    /// See [`KubeContainerFs::default_file_mode`]
+    pub fn default_file_mode(mut self, mode: UnixPex) -> Self {
+        self.kube = self.kube.default_file_mode(mode);
+        self
+    }
+
+    /// Set whether the umask of the container should be applied to the modes of the files and directories created.
+    ///
+    /// See [`KubeContainerFs::respect_umask`]
+    pub fn respect_umask(mut self, respect_umask: bool) -> Self {
+        self.kube = self.kube.respect_umask(respect_umask);
+        self
+    }
+
+    /// Set the token used to cancel file transfers and commands.
+    ///
+    /// See [`KubeContainerFs::cancellation_token`]
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.kube = self.kube.cancellation_token(token);
+        self
+    }
+
+    /// Set the size of the chunks used to stream the tar archives in file transfers.
+    ///
+    /// See [`KubeContainerFs::transfer_block_size`]
+    pub fn transfer_block_size(mut self, size: usize) -> Self {
+        self.kube = self.kube.transfer_block_size(size);
+        self
+    }
+
+    /// Set whether file transfers should be compressed with gzip, when it's in the container.
+    ///
+    /// See [`KubeContainerFs::compress`]
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.kube = self.kube.compress(compress);
+        self
+    }
+
+    /// Set whether downloads should skip the holes of sparse files, when the container has GNU tar.
+    ///
+    /// See [`KubeContainerFs::sparse`]
+    pub fn sparse(mut self, sparse: bool) -> Self {
+        self.kube = self.kube.sparse(sparse);
+        self
+    }
+
+    /// Set whether downloads should extract files while they're received, without a local temporary file.
+    ///
+    /// See [`KubeContainerFs::streaming`]
+    pub fn streaming(mut self, streaming: bool) -> Self {
+        self.kube = self.kube.streaming(streaming);
+        self
+    }
+
+    /// Set the local directory where downloads are buffered.
+    ///
+    /// See [`KubeContainerFs::temp_dir`]
+    pub fn temp_dir(mut self, dir: PathBuf) -> Self {
+        self.kube = self.kube.temp_dir(dir);
+        self
+    }
+
+    /// Enable TCP keepalive on the connections to the cluster, sending a probe every `interval` while they're idle.
+    ///
+    /// See [`KubeContainerFs::transfer_keepalive`]
+    pub fn transfer_keepalive(mut self, interval: Duration) -> Self {
+        self.kube = self.kube.transfer_keepalive(interval);
+        self
+    }
+
+    /// Set the sink receiving the metrics of the operations run in the containers.
+    ///
+    /// See [`KubeContainerFs::with_metrics`]
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.kube = self.kube.with_metrics(sink);
+        self
+    }
+
+    /// Show only the pods matching the label `selector` (e.g. `app=nginx`) in the root directory.
+    ///
+    /// The other pods are reported as missing by `exists` and `stat` too.
+    pub fn pod_selector(mut self, selector: impl ToString) -> Self {
+        self.pod_selector = Some(selector.to_string());
+        self
+    }
+
+    /// Set the client certificate and private key, in PEM format, used for mutual TLS authentication.
+    ///
+    /// See [`KubeContainerFs::client_cert`]
+    pub fn client_cert(mut self, cert_pem: Vec<u8>, key_pem: Vec<u8>) -> RemoteResult<Self> {
+        self.kube = self.kube.client_cert(cert_pem, key_pem)?;
+        Ok(self)
+    }
+
+    /// Set the CA certificates, in PEM format, used to verify the cluster certificate.
+    ///
+    /// See [`KubeContainerFs::ca_cert`]
+    pub fn ca_cert(mut self, pem: Vec<u8>) -> RemoteResult<Self> {
+        self.kube = self.kube.ca_cert(pem)?;
+        Ok(self)
+    }
+
+    /// Set the URL of the cluster and whether to accept its certificate even if invalid.
+    ///
+    /// See [`KubeContainerFs::cluster_url`]
+    pub fn cluster_url(mut self, url: &str, accept_invalid_certs: bool) -> RemoteResult<Self> {
+        self.kube = self.kube.cluster_url(url, accept_invalid_certs)?;
+        Ok(self)
+    }
+
+    /// Rebuild the kube client, reloading the credentials, while preserving the current path.
+    ///
+    /// See [`KubeContainerFs::refresh_auth`]
+    pub fn refresh_auth(&mut self) -> RemoteResult<()> {
+        self.kube.refresh_auth()
+    }
+
+    /// List the containers of `pod`, along with their image, readiness and restart count
+    pub fn list_containers_detailed(&self, pod: &str) -> RemoteResult<Vec<ContainerEntry>> {
+        let pod = self.get_pod(pod)?;
+        Ok(ContainerEntry::from_pod(&pod))
+    }
+
+    /// List the namespaces in which the client can list pods, i.e. those which can be entered with `/ns:<name>` paths.
+    ///
+    /// If the client isn't allowed to list namespaces, only the default namespace is returned.
+    pub fn list_namespaces(&self) -> RemoteResult<Vec<String>> {
+        let client = self.pods_api()?.clone().into_client();
+        let default_namespace = client.default_namespace().to_string();
+        let namespaces: Api<Namespace> = Api::all(client.clone());
+        let namespaces =
+            match self.block_on(async { namespaces.list(&ListParams::default()).await }) {
+                Ok(list) => namespace_names(list.items),
+                Err(kube::Error::Api(response)) if response.code == 403 => {
+                    debug!("Not allowed to list namespaces: {}", response.message);
+                    return Ok(vec![default_namespace]);
+                }
+                Err(err) => {
+                    return Err(kube_error(
+                        err,
+                        RemoteErrorType::ProtocolError,
+                        "list namespaces",
+                    ))
+                }
+            };
+
+        let reviews: Api<SelfSubjectAccessReview> = Api::all(client);
+        let mut accessible = Vec::with_capacity(namespaces.len());
+        for namespace in namespaces {
+            let review = self
+                .block_on(async {
+                    reviews
+                        .create(&PostParams::default(), &list_pods_review(&namespace))
+                        .await
+                })
+                .map_err(|err| {
+                    kube_error(
+                        err,
+                        RemoteErrorType::ProtocolError,
+                        "create selfsubjectaccessreviews",
+                    )
+                })?;
+            if review.status.is_some_and(|status| status.allowed) {
+                accessible.push(namespace);
+            }
+        }
+
+        Ok(accessible)
+    }
+
+    /// Execute `cmd` in every container of `pod` concurrently, returning the container name, exit code and output
+    /// of each run, in the order of the pod spec.
+    ///
+    /// Commands run in the working directory of each container.
+    pub fn exec_all(&mut self, pod: &str, cmd: &str) -> RemoteResult<Vec<(String, u32, String)>> {
+        let containers: Vec<String> = self
+            .get_pod(pod)?
+            .spec
+            .map(|spec| spec.containers.into_iter().map(|c| c.name).collect())
+            .unwrap_or_default();
+        debug!(
+            "Executing \"{cmd}\" in {} containers of {pod}",
+            containers.len()
+        );
+
+        let results = self.block_on(futures_util::future::join_all(
+            containers
+                .iter()
+                .map(|container| self.kube.shell_cmd_in(pod, container, cmd)),
+        ));
+
+        containers
+            .into_iter()
+            .zip(results)
+            .map(|(container, result)| result.map(|(rc, output)| (container, rc, output)))
+            .collect()
+    }
+
+    /// Execute `cmd` in `container` of every pod matching the label `selector` (e.g. `app=nginx`) concurrently.
+    ///
+    /// Returns the exit code and output of each pod, sorted by pod name; a failure on a pod doesn't prevent
+    /// getting the results of the others.
+    pub fn exec_matching(
+        &mut self,
+        selector: &str,
+        container: &str,
+        cmd: &str,
+    ) -> RemoteResult<Vec<PodExecResult>> {
+        let api = self.pods_api()?;
+        let params = ListParams::default().labels(selector);
+        let pods: Vec<String> = self
+            .block_on(async { api.list(&params).await })
+            .map_err(|err| kube_error(err, RemoteErrorType::ProtocolError, "list pods"))?
+            .into_iter()
+            .filter_map(|pod| pod.metadata.name)
+            .collect();
+        debug!(
+            "Executing \"{cmd}\" in {} pods matching {selector}",
+            pods.len()
+        );
+
+        let results = self.block_on(futures_util::future::join_all(
+            pods.iter()
+                .map(|pod| self.kube.shell_cmd_in(pod, container, cmd)),
+        ));
+
+        Ok(pod_results(pods, results))
+    }
+
+    /// Copy the file or directory at `src` to `dest`, which may be in another pod or container.
+    ///
+    /// Across containers, `tar cf -` run in the source container is streamed straight into `tar xf -` run in the
+    /// destination one, so the data never touches the local disk; both containers need tar. The configured tar
+    /// binary and sudo apply to the source only: the destination runs `tar` from its `PATH`, without sudo.
+    /// Within a single container, this is the same as [`RemoteFs::copy`].
+    pub fn copy_between(&mut self, src: &Path, dest: &Path) -> RemoteResult<()> {
+        let (namespace, src_pod, src_container, src_path) = self.container_path(src)?;
+        let (dest_namespace, dest_pod, dest_container, dest_path) = self.container_path(dest)?;
+        if namespace != dest_namespace {
+            return Err(cross_namespace_unsupported());
+        }
+        if src_pod == dest_pod && src_container == dest_container {
+            return self.in_namespace(namespace, |fs| {
+                fs.in_container(src_pod, src_container, |fs| {
+                    fs.kube.copy(&src_path, &dest_path)
+                })
+            });
+        }
+
+        debug!(
+            "Copying {} from {src_pod}/{src_container} to {} in {dest_pod}/{dest_container}",
+            src_path.display(),
+            dest_path.display()
+        );
+        self.in_namespace(namespace, |fs| {
+            fs.in_container(src_pod, src_container, |fs| {
+                fs.kube
+                    .copy_to_container(&src_path, &dest_pod, &dest_container, &dest_path)
+                    .map(|_| ())
+            })
+        })
+    }
+
     /// Get the current pod name
     fn pod_name(&self) -> Option<&str> {
         if self.kube.pod_name.is_empty() {
@@ -71,9 +450,29 @@ impl KubeMultiPodFs {
         }
     }
 
-    /// Get the kube path from a path
-    fn kube_path(&self, path: &Path) -> KubePath {
-        KubePath::from_path(self.pod_name(), self.container_name(), path)
+    /// Get the kube path from a path, failing with `ProtocolError` if it's malformed
+    fn kube_path(&self, path: &Path) -> RemoteResult<KubePath> {
+        let mut kube_path = KubePath::from_path(self.pod_name(), self.container_name(), path)
+            .map_err(|err| {
+                RemoteError::new_ex(
+                    RemoteErrorType::ProtocolError,
+                    format!("malformed path {}: {err}", path.display()),
+                )
+            })?;
+        // relative paths are in the current namespace
+        if !path.is_absolute() && kube_path.namespace.is_none() {
+            kube_path.namespace = self.namespace.clone();
+        }
+        Ok(kube_path)
+    }
+
+    /// Get the path of the root of the current namespace: `/`, or `/ns:<namespace>` out of the default one
+    fn root_path(&self) -> PathBuf {
+        let mut p = PathBuf::from("/");
+        if let Some(namespace) = &self.namespace {
+            p.push(format!("{NAMESPACE_PREFIX}{namespace}"));
+        }
+        p
     }
 
     /// Dispatch operations based on the path
@@ -82,7 +481,8 @@ impl KubeMultiPodFs {
     /// The `on_pod` closure is called when the path is `/pod-name`
     /// The `on_container` closure is called when the path is `/pod-name/container-name` or `/pod-name/container-name/path/to/file`
     ///
-    /// In any case, the current pod and container are set accordingly.
+    /// The namespace of the path is temporarily set as the current one;
+    /// in the last two cases, so are the pod and container of the path.
     fn path_dispatch<T, FR, FP, FC, FPP>(
         &mut self,
         path: KubePath,
@@ -90,12 +490,33 @@ impl KubeMultiPodFs {
         on_pod: FP,
         on_container: FC,
         on_path: FPP,
-    ) -> T
+    ) -> RemoteResult<T>
+    where
+        FR: FnOnce(&mut Self) -> RemoteResult<T>,
+        FP: FnOnce(&mut Self, &str) -> RemoteResult<T>,
+        FC: FnOnce(&mut Self, &str) -> RemoteResult<T>,
+        FPP: FnOnce(&mut Self, &Path) -> RemoteResult<T>,
+    {
+        let namespace = path.namespace.clone();
+        self.in_namespace(namespace, |fs| {
+            fs.dispatch_in_namespace(path, on_root, on_pod, on_container, on_path)
+        })
+    }
+
+    /// Dispatch operations based on the path, in the current namespace; see [`KubeMultiPodFs::path_dispatch`]
+    fn dispatch_in_namespace<T, FR, FP, FC, FPP>(
+        &mut self,
+        path: KubePath,
+        on_root: FR,
+        on_pod: FP,
+        on_container: FC,
+        on_path: FPP,
+    ) -> RemoteResult<T>
     where
-        FR: FnOnce(&mut Self) -> T,
-        FP: FnOnce(&mut Self, &str) -> T,
-        FC: FnOnce(&mut Self, &str) -> T,
-        FPP: FnOnce(&mut Self, &Path) -> T,
+        FR: FnOnce(&mut Self) -> RemoteResult<T>,
+        FP: FnOnce(&mut Self, &str) -> RemoteResult<T>,
+        FC: FnOnce(&mut Self, &str) -> RemoteResult<T>,
+        FPP: FnOnce(&mut Self, &Path) -> RemoteResult<T>,
     {
         if path.pod.is_none() {
             return on_root(self);
@@ -104,21 +525,160 @@ impl KubeMultiPodFs {
             return on_pod(self, path.pod.as_deref().unwrap());
         }
 
-        // temporary set pod and container
+        let pod = path.pod.unwrap();
+        let container = path.container.unwrap();
         if let Some(p) = path.path {
-            let prev_pod = self.kube.pod_name.clone();
-            let prev_container = self.kube.container.clone();
-            self.kube.pod_name = path.pod.unwrap();
-            self.kube.container = path.container.unwrap();
-            let res = on_path(self, &p);
+            self.in_container(pod, container.clone(), |fs| on_path(fs, &p))
+        } else {
+            self.in_container(pod, container.clone(), |fs| on_container(fs, &container))
+        }
+    }
+
+    /// Run the mutation `on_path` on the file at `path`, in its namespace, pod and container.
+    ///
+    /// The root, the pods and the containers are virtual directories, so mutating them fails with `UnsupportedFeature`.
+    fn mutate_path<T>(
+        &mut self,
+        path: KubePath,
+        on_path: impl FnOnce(&mut Self, &Path) -> RemoteResult<T>,
+    ) -> RemoteResult<T> {
+        let KubePath {
+            namespace,
+            pod: Some(pod),
+            container: Some(container),
+            path: Some(path),
+        } = path
+        else {
+            return Err(virtual_dir_unsupported(&path));
+        };
+        self.in_namespace(namespace, |fs| {
+            fs.in_container(pod, container, |fs| on_path(fs, &path))
+        })
+    }
 
-            // restore pod and container
-            self.kube.pod_name = prev_pod;
-            self.kube.container = prev_container;
+    /// Call `f` with the pods API of `namespace` (the default one if `None`) temporarily set as the current one.
+    ///
+    /// Out of the current namespace, the working directory is temporarily set to `/`.
+    fn in_namespace<T>(
+        &mut self,
+        namespace: Option<String>,
+        f: impl FnOnce(&mut Self) -> RemoteResult<T>,
+    ) -> RemoteResult<T> {
+        if namespace == self.namespace {
+            return f(self);
+        }
+        let api = self.namespaced_api(namespace.as_deref())?;
+        let prev_api = self.kube.pods.replace(api);
+        let prev_namespace = std::mem::replace(&mut self.namespace, namespace);
+        let prev_wrkdir = std::mem::replace(&mut self.kube.wrkdir, PathBuf::from("/"));
+        let res = f(self);
+
+        // restore api, namespace and working directory
+        self.kube.pods = prev_api;
+        self.namespace = prev_namespace;
+        self.kube.wrkdir = prev_wrkdir;
+
+        res
+    }
+
+    /// Get the pods API of `namespace`, or of the default namespace if `None`
+    fn namespaced_api(&self, namespace: Option<&str>) -> RemoteResult<Api<Pod>> {
+        let client = self.pods_api()?.clone().into_client();
+        Ok(match namespace {
+            Some(namespace) => Api::namespaced(client, namespace),
+            None => Api::default_namespaced(client),
+        })
+    }
 
-            res
+    /// Call `f` with `pod` and `container` temporarily set as the current ones.
+    ///
+    /// The working directory only exists in the current container, so it's temporarily set to `/` in any other.
+    fn in_container<T>(
+        &mut self,
+        pod: String,
+        container: String,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let prev_wrkdir = if pod != self.kube.pod_name || container != self.kube.container {
+            Some(std::mem::replace(&mut self.kube.wrkdir, PathBuf::from("/")))
         } else {
-            on_container(self, path.container.as_deref().unwrap())
+            None
+        };
+        let (prev_pod, prev_container) = self.kube.set_target(pod, container);
+        let res = f(self);
+
+        // restore pod, container and working directory
+        self.kube.set_target(prev_pod, prev_container);
+        if let Some(wrkdir) = prev_wrkdir {
+            self.kube.wrkdir = wrkdir;
+        }
+
+        res
+    }
+
+    /// Change directory to `path`, in the current namespace
+    fn change_dir_in_namespace(&mut self, path: KubePath) -> RemoteResult<PathBuf> {
+        let pod = match path.pod {
+            Some(pod) if !self.exists_pod(&pod)? => {
+                return Err(RemoteError::new_ex(
+                    RemoteErrorType::NoSuchFileOrDirectory,
+                    format!("Pod {} does not exist", pod),
+                ));
+            }
+            pod => pod.unwrap_or_default(),
+        };
+        let (prev_pod, prev_container) = self
+            .kube
+            .set_target(pod, self.container_name().unwrap_or("").to_string());
+
+        let container = match path.container {
+            Some(container) if !self.exists_container(&container)? => {
+                // restore previous pod
+                self.kube.set_target(prev_pod, prev_container);
+                return Err(RemoteError::new_ex(
+                    RemoteErrorType::NoSuchFileOrDirectory,
+                    format!("Container {} does not exist", container),
+                ));
+            }
+            container => container.unwrap_or_default(),
+        };
+        let pod = self.kube.pod_name.clone();
+        self.kube.set_target(pod, container);
+
+        let res = if let Some(path) = path.path {
+            self.kube.change_dir(&path)
+        } else {
+            self.kube.wrkdir = PathBuf::from("/");
+            Ok(PathBuf::from("/"))
+        };
+
+        // restore previous pod and container
+        if let Err(err) = res {
+            self.kube.set_target(prev_pod, prev_container);
+
+            return Err(err);
+        }
+
+        self.pwd()
+    }
+
+    /// Split `path` into its namespace, pod, container and absolute path in the container
+    fn container_path(
+        &self,
+        path: &Path,
+    ) -> RemoteResult<(Option<String>, String, String, PathBuf)> {
+        match self.kube_path(path)? {
+            KubePath {
+                namespace,
+                pod: Some(pod),
+                container: Some(container),
+                path: Some(path),
+            } => {
+                // relative paths are in the current container
+                let path = self.kube.resolve(&path);
+                Ok((namespace, pod, container, path))
+            }
+            path => Err(virtual_dir_unsupported(&path)),
         }
     }
 
@@ -130,7 +690,7 @@ impl KubeMultiPodFs {
             return f;
         }
 
-        let mut p = PathBuf::from("/");
+        let mut p = self.root_path();
         p.push(self.pod_name().unwrap());
         p.push(self.container_name().unwrap());
 
@@ -143,62 +703,28 @@ impl KubeMultiPodFs {
 
     /// List pods
     fn list_pods(&self) -> RemoteResult<Vec<File>> {
-        let api = self.kube.pods.as_ref().ok_or_else(|| {
-            RemoteError::new_ex(
-                RemoteErrorType::NotConnected,
-                "Not connected to a Kubernetes cluster",
-            )
-        })?;
+        let api = self.pods_api()?;
         let pods = self
-            .runtime
-            .block_on(async { api.list(&Default::default()).await })
-            .map_err(|err| RemoteError::new_ex(RemoteErrorType::ProtocolError, err))?;
-
-        Ok(pods
-            .into_iter()
-            .map(|pod| File {
-                path: {
-                    let mut p = PathBuf::from("/");
-                    p.push(pod.metadata.name.unwrap_or_default());
-                    p
-                },
-                metadata: Metadata::default().file_type(FileType::Directory),
+            .block_on(async {
+                api.list(&pod_list_params(self.pod_selector.as_deref(), None))
+                    .await
             })
-            .collect())
+            .map_err(|err| kube_error(err, RemoteErrorType::ProtocolError, "list pods"))?;
+
+        Ok(pod_files(&self.root_path(), pods))
+    }
+
+    /// Get pod
+    fn get_pod(&self, pod_name: &str) -> RemoteResult<Pod> {
+        let api = self.pods_api()?;
+        self.block_on(async { api.get(pod_name).await })
+            .map_err(|err| kube_error(err, RemoteErrorType::NoSuchFileOrDirectory, "get pods"))
     }
 
     /// List containers
     fn list_containers(&self, pod_name: &str) -> RemoteResult<Vec<File>> {
-        let api = self.kube.pods.as_ref().ok_or_else(|| {
-            RemoteError::new_ex(
-                RemoteErrorType::NotConnected,
-                "Not connected to a Kubernetes cluster",
-            )
-        })?;
-        let pod = self
-            .runtime
-            .block_on(async { api.get(pod_name).await })
-            .map_err(|err| RemoteError::new_ex(RemoteErrorType::NoSuchFileOrDirectory, err))?;
-
-        let pod_spec = pod.spec.ok_or_else(|| {
-            RemoteError::new_ex(RemoteErrorType::NoSuchFileOrDirectory, "Pod spec not found")
-        })?;
-
-        Ok(pod_spec
-            .containers
-            .into_iter()
-            .map(|container| File {
-                path: {
-                    let mut p = PathBuf::from("/");
-                    p.push(pod_name);
-                    p.push(&container.name);
-                    debug!("found container {} -> {}", container.name, p.display());
-
-                    p
-                },
-                metadata: Metadata::default().file_type(FileType::Directory),
-            })
-            .collect())
+        let pod = self.get_pod(pod_name)?;
+        container_files(&self.root_path().join(pod_name), pod)
     }
 
     /// Stat root
@@ -243,16 +769,15 @@ impl KubeMultiPodFs {
             })
     }
 
-    /// Check whether pod exists
+    /// Check whether pod exists, listing it as `list_pods` does, so that the pods it skips don't exist
     fn exists_pod(&self, pod: &str) -> RemoteResult<bool> {
-        let api = self.kube.pods.as_ref().ok_or_else(|| {
-            RemoteError::new_ex(
-                RemoteErrorType::NotConnected,
-                "Not connected to a Kubernetes cluster",
-            )
-        })?;
+        let api = self.pods_api()?;
+        let params = pod_list_params(self.pod_selector.as_deref(), Some(pod));
+        let pods = self
+            .block_on(async { api.list(&params).await })
+            .map_err(|err| kube_error(err, RemoteErrorType::ProtocolError, "list pods"))?;
 
-        Ok(self.runtime.block_on(async { api.get(pod).await.is_ok() }))
+        Ok(!pods.items.is_empty())
     }
 
     /// Check whether container exists
@@ -264,17 +789,7 @@ impl KubeMultiPodFs {
             )
         })?;
 
-        let api = self.kube.pods.as_ref().ok_or_else(|| {
-            RemoteError::new_ex(
-                RemoteErrorType::NotConnected,
-                "Not connected to a Kubernetes cluster",
-            )
-        })?;
-
-        let pod = self
-            .runtime
-            .block_on(async { api.get(pod_name).await })
-            .map_err(|err| RemoteError::new_ex(RemoteErrorType::NoSuchFileOrDirectory, err))?;
+        let pod = self.get_pod(pod_name)?;
 
         let pod_spec = pod.spec.ok_or_else(|| {
             RemoteError::new_ex(RemoteErrorType::NoSuchFileOrDirectory, "Pod spec not found")
@@ -282,23 +797,35 @@ impl KubeMultiPodFs {
 
         Ok(pod_spec.containers.iter().any(|c| c.name == container))
     }
+
+    /// Get the pods API, failing if not connected
+    fn pods_api(&self) -> RemoteResult<&Api<Pod>> {
+        self.kube.pods.as_ref().ok_or_else(|| {
+            RemoteError::new_ex(
+                RemoteErrorType::NotConnected,
+                "Not connected to a Kubernetes cluster",
+            )
+        })
+    }
+
+    /// Run `fut` to completion on the runtime shared with the container client.
+    ///
+    /// `block_on` panics when nested, so `fut` must only await async helpers and never call a blocking method,
+    /// either of this client or of the container client.
+    fn block_on<F: std::future::Future>(&self, fut: F) -> F::Output {
+        self.runtime.block_on(fut)
+    }
 }
 
 impl RemoteFs for KubeMultiPodFs {
     fn connect(&mut self) -> RemoteResult<Welcome> {
         debug!("Initializing Kube connection...");
-        let api = self.runtime.block_on(async {
-            let client = match self.kube.config.as_ref() {
-                Some(config) => Client::try_from(config.clone())
-                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err)),
-                None => Client::try_default()
-                    .await
-                    .map_err(|err| RemoteError::new_ex(RemoteErrorType::ConnectionError, err)),
-            }?;
+        let api = self.block_on(self.kube.with_connect_timeout(async {
+            let client = self.kube.build_client().await?;
             let api: Api<Pod> = Api::default_namespaced(client);
 
             Ok(api)
-        })?;
+        }))?;
 
         // Set pods
         self.kube.pods = Some(api);
@@ -319,7 +846,7 @@ impl RemoteFs for KubeMultiPodFs {
     }
 
     fn pwd(&mut self) -> RemoteResult<PathBuf> {
-        let mut p = PathBuf::from("/");
+        let mut p = self.root_path();
 
         // compose path in format /pod-name/container-name/pwd
         if let Some(pod_name) = self.pod_name() {
@@ -343,60 +870,26 @@ impl RemoteFs for KubeMultiPodFs {
     }
 
     fn change_dir(&mut self, dir: &Path) -> RemoteResult<PathBuf> {
-        let path = self.kube_path(dir);
+        let path = self.kube_path(dir)?;
         debug!("Changing directory to {path}");
-
-        let prev_pod = self.pod_name().unwrap_or("").to_string();
-        let prev_container = self.container_name().unwrap_or("").to_string();
-
-        if let Some(pod) = path.pod {
-            if self.exists_pod(&pod)? {
-                self.kube.pod_name = pod.to_string();
-            } else {
-                return Err(RemoteError::new_ex(
-                    RemoteErrorType::NoSuchFileOrDirectory,
-                    format!("Pod {} does not exist", pod),
-                ));
-            }
-        } else {
-            self.kube.pod_name = "".to_string();
+        if path.namespace == self.namespace {
+            return self.change_dir_in_namespace(path);
         }
 
-        if let Some(container) = path.container {
-            if self.exists_container(&container)? {
-                self.kube.container = container.to_string();
-            } else {
-                // restore previous pod
-                self.kube.pod_name = prev_pod;
-                return Err(RemoteError::new_ex(
-                    RemoteErrorType::NoSuchFileOrDirectory,
-                    format!("Container {} does not exist", container),
-                ));
-            }
-        } else {
-            self.kube.container = "".to_string();
+        // enter the namespace, restoring the previous one if the path can't be entered
+        let api = self.namespaced_api(path.namespace.as_deref())?;
+        let prev_api = self.kube.pods.replace(api);
+        let prev_namespace = std::mem::replace(&mut self.namespace, path.namespace.clone());
+        let res = self.change_dir_in_namespace(path);
+        if res.is_err() {
+            self.kube.pods = prev_api;
+            self.namespace = prev_namespace;
         }
-
-        let res = if let Some(path) = path.path {
-            self.kube.change_dir(&path)
-        } else {
-            self.kube.wrkdir = PathBuf::from("/");
-            Ok(PathBuf::from("/"))
-        };
-
-        // restore previous pod and container
-        if let Err(err) = res {
-            self.kube.pod_name = prev_pod;
-            self.kube.container = prev_container;
-
-            return Err(err);
-        }
-
-        self.pwd()
+        res
     }
 
     fn list_dir(&mut self, path: &Path) -> RemoteResult<Vec<File>> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
         self.path_dispatch(
             path,
@@ -416,34 +909,25 @@ impl RemoteFs for KubeMultiPodFs {
     }
 
     fn stat(&mut self, path: &Path) -> RemoteResult<File> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
         self.path_dispatch(
             path,
             |fs| fs.stat_root(),
             |fs, pod| fs.stat_pod(pod),
-            |fs, container| {
-                fs.stat_container(container)
-                    .map(|f| fs.fix_absolute_path(f))
-            },
+            |fs, container| fs.stat_container(container),
             |fs, path| fs.kube.stat(path).map(|f| fs.fix_absolute_path(f)),
         )
     }
 
     fn setstat(&mut self, path: &Path, metadata: Metadata) -> RemoteResult<()> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
-        self.path_dispatch(
-            path,
-            |_| Ok(()),
-            |_, _| Ok(()),
-            |_, _| Ok(()),
-            |fs, path| fs.kube.setstat(path, metadata),
-        )
+        self.mutate_path(path, |fs, path| fs.kube.setstat(path, metadata))
     }
 
     fn exists(&mut self, path: &Path) -> RemoteResult<bool> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
         self.path_dispatch(
             path,
@@ -455,147 +939,68 @@ impl RemoteFs for KubeMultiPodFs {
     }
 
     fn remove_file(&mut self, path: &Path) -> RemoteResult<()> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
-        self.path_dispatch(
-            path,
-            |_| Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            |_, _| Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            |_, _| Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            |fs, path| fs.kube.remove_file(path),
-        )
+        self.mutate_path(path, |fs, path| fs.kube.remove_file(path))
     }
 
     fn remove_dir(&mut self, path: &Path) -> RemoteResult<()> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
-        self.path_dispatch(
-            path,
-            |_| Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            |_, _| Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            |_, _| Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            |fs, path| fs.kube.remove_dir(path),
-        )
+        self.mutate_path(path, |fs, path| fs.kube.remove_dir(path))
     }
 
     fn remove_dir_all(&mut self, path: &Path) -> RemoteResult<()> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
-        self.path_dispatch(
-            path,
-            |_| Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            |_, _| Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            |_, _| Err(RemoteError::new(RemoteErrorType::CouldNotRemoveFile)),
-            |fs, path| fs.kube.remove_dir_all(path),
-        )
+        self.mutate_path(path, |fs, path| fs.kube.remove_dir_all(path))
     }
 
     fn create_dir(&mut self, path: &Path, mode: UnixPex) -> RemoteResult<()> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
-        self.path_dispatch(
-            path,
-            |_| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |fs, path| fs.kube.create_dir(path, mode),
-        )
+        self.mutate_path(path, |fs, path| fs.kube.create_dir(path, mode))
     }
 
     fn symlink(&mut self, path: &Path, target: &Path) -> RemoteResult<()> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
-        self.path_dispatch(
-            path,
-            |_| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |fs, path| fs.kube.symlink(path, target),
-        )
+        self.mutate_path(path, |fs, path| fs.kube.symlink(path, target))
     }
 
+    /// Copy `src` to `dest`, which may be in another pod or container, as [`KubeMultiPodFs::copy_between`] does
     fn copy(&mut self, src: &Path, dest: &Path) -> RemoteResult<()> {
-        let path = self.kube_path(src);
-
-        self.path_dispatch(
-            path,
-            |_| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |fs, path| fs.kube.copy(path, dest),
-        )
+        self.copy_between(src, dest)
     }
 
+    /// Move `src` to `dest`, which may be in another pod or container.
+    ///
+    /// Across containers, `src` is copied with [`KubeMultiPodFs::copy_between`] and then removed: the move is not
+    /// atomic, and if the removal fails, both copies are left in place.
     fn mov(&mut self, src: &Path, dest: &Path) -> RemoteResult<()> {
-        let path = self.kube_path(src);
-
-        self.path_dispatch(
-            path,
-            |_| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |fs, path| fs.kube.mov(path, dest),
-        )
+        let (namespace, src_pod, src_container, src_path) = self.container_path(src)?;
+        let (dest_namespace, dest_pod, dest_container, dest_path) = self.container_path(dest)?;
+        if namespace != dest_namespace {
+            return Err(cross_namespace_unsupported());
+        }
+        if src_pod == dest_pod && src_container == dest_container {
+            return self.in_namespace(namespace, |fs| {
+                fs.in_container(src_pod, src_container, |fs| {
+                    fs.kube.mov(&src_path, &dest_path)
+                })
+            });
+        }
+
+        self.copy_between(src, dest)?;
+        debug!(
+            "Removing {} from {src_pod}/{src_container} after copy",
+            src_path.display()
+        );
+        self.in_namespace(namespace, |fs| {
+            fs.in_container(src_pod, src_container, |fs| {
+                fs.kube.remove_dir_all(&src_path)
+            })
+        })
     }
 
     fn exec(&mut self, cmd: &str) -> RemoteResult<(u32, String)> {
@@ -627,30 +1032,9 @@ impl RemoteFs for KubeMultiPodFs {
         metadata: &Metadata,
         reader: Box<dyn std::io::Read + Send>,
     ) -> RemoteResult<u64> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
-        self.path_dispatch(
-            path,
-            |_| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |fs, path| fs.kube.create_file(path, metadata, reader),
-        )
+        self.mutate_path(path, |fs, path| fs.kube.create_file(path, metadata, reader))
     }
 
     fn append_file(
@@ -659,34 +1043,13 @@ impl RemoteFs for KubeMultiPodFs {
         metadata: &Metadata,
         reader: Box<dyn std::io::Read + Send>,
     ) -> RemoteResult<u64> {
-        let path = self.kube_path(path);
+        let path = self.kube_path(path)?;
 
-        self.path_dispatch(
-            path,
-            |_| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |_, _| {
-                Err(RemoteError::new_ex(
-                    RemoteErrorType::CouldNotOpenFile,
-                    "This operation requires a pod and a container",
-                ))
-            },
-            |fs, path| fs.kube.append_file(path, metadata, reader),
-        )
+        self.mutate_path(path, |fs, path| fs.kube.append_file(path, metadata, reader))
     }
 
     fn open_file(&mut self, src: &Path, dest: Box<dyn std::io::Write + Send>) -> RemoteResult<u64> {
-        let path = self.kube_path(src);
+        let path = self.kube_path(src)?;
 
         self.path_dispatch(
             path,
@@ -713,18 +1076,384 @@ impl RemoteFs for KubeMultiPodFs {
     }
 }
 
+/// Get the names of the `namespaces`, sorted, skipping those being deleted
+fn namespace_names(namespaces: Vec<Namespace>) -> Vec<String> {
+    let mut names: Vec<String> = namespaces
+        .into_iter()
+        .filter(|namespace| {
+            namespace
+                .status
+                .as_ref()
+                .and_then(|status| status.phase.as_deref())
+                != Some("Terminating")
+        })
+        .filter_map(|namespace| namespace.metadata.name)
+        .collect();
+    names.sort();
+    names
+}
+
+/// Make the entries of `pods` in the directory `root`, sorted by name, since the API order isn't stable
+fn pod_files(root: &Path, pods: impl IntoIterator<Item = Pod>) -> Vec<File> {
+    let mut files: Vec<File> = pods
+        .into_iter()
+        .map(|pod| File {
+            metadata: pod_metadata(&pod),
+            path: root.join(pod.metadata.name.unwrap_or_default()),
+        })
+        .collect();
+    files.sort_by_key(File::name);
+    files
+}
+
+/// Make the entries of the containers of `pod` in the pod directory `pod_dir`, sorted by name
+fn container_files(pod_dir: &Path, pod: Pod) -> RemoteResult<Vec<File>> {
+    let metadata = pod_metadata(&pod);
+    let pod_spec = pod.spec.ok_or_else(|| {
+        RemoteError::new_ex(RemoteErrorType::NoSuchFileOrDirectory, "Pod spec not found")
+    })?;
+
+    let mut files: Vec<File> = pod_spec
+        .containers
+        .into_iter()
+        .map(|container| {
+            let path = pod_dir.join(&container.name);
+            debug!("found container {} -> {}", container.name, path.display());
+            File {
+                path,
+                metadata: metadata.clone(),
+            }
+        })
+        .collect();
+    files.sort_by_key(File::name);
+    Ok(files)
+}
+
+/// Build the review asking whether the current user can list pods in `namespace`
+fn list_pods_review(namespace: &str) -> SelfSubjectAccessReview {
+    SelfSubjectAccessReview {
+        spec: SelfSubjectAccessReviewSpec {
+            resource_attributes: Some(ResourceAttributes {
+                namespace: Some(namespace.to_string()),
+                verb: Some("list".to_string()),
+                resource: Some("pods".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Get the params listing the pods matching the label `selector`, if any, restricted to the pod `name`, if any
+fn pod_list_params(selector: Option<&str>, name: Option<&str>) -> ListParams {
+    let mut params = ListParams::default();
+    if let Some(selector) = selector {
+        params = params.labels(selector);
+    }
+    if let Some(name) = name {
+        params = params.fields(&format!("metadata.name={name}"));
+    }
+    params
+}
+
+/// Error of the mutations of `path`, which is the root, a pod or a container
+fn virtual_dir_unsupported(path: &KubePath) -> RemoteError {
+    RemoteError::new_ex(
+        RemoteErrorType::UnsupportedFeature,
+        format!("cannot modify virtual directory {path}"),
+    )
+}
+
+/// Error of an operation between two namespaces
+fn cross_namespace_unsupported() -> RemoteError {
+    RemoteError::new_ex(
+        RemoteErrorType::UnsupportedFeature,
+        "This operation is not supported between namespaces",
+    )
+}
+
+/// Pair each pod with its result, sorting them by pod name
+fn pod_results<T>(pods: Vec<String>, results: Vec<T>) -> Vec<(String, T)> {
+    let mut results: Vec<(String, T)> = pods.into_iter().zip(results).collect();
+    results.sort_by(|(a, _), (b, _)| a.cmp(b));
+    results
+}
+
+/// Metadata of the directories representing `pod` and its containers, dated by the pod creation
+fn pod_metadata(pod: &Pod) -> Metadata {
+    let metadata = Metadata::default()
+        .file_type(FileType::Directory)
+        .mode(UnixPex::from(0o755));
+    match pod.metadata.creation_timestamp.as_ref() {
+        Some(created) => {
+            let created = SystemTime::from(created.0);
+            metadata.created(created).modified(created)
+        }
+        None => metadata,
+    }
+}
+
 #[cfg(test)]
 mod test {
 
     #[cfg(feature = "integration-tests")]
     use std::io::Cursor;
 
-    #[cfg(feature = "integration-tests")]
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+    use kube::api::ObjectList;
     use pretty_assertions::assert_eq;
 
-    #[cfg(feature = "integration-tests")]
     use super::*;
 
+    #[test]
+    fn should_sort_pods_and_containers_by_name() {
+        let pods: Vec<Pod> = ["pod-c", "pod-a", "pod-b"]
+            .into_iter()
+            .map(|name| {
+                serde_json::from_value(serde_json::json!({
+                    "metadata": { "name": name },
+                    "spec": {
+                        "containers": [
+                            { "name": "sidecar" },
+                            { "name": "app" },
+                            { "name": "init" },
+                        ]
+                    }
+                }))
+                .unwrap()
+            })
+            .collect();
+        let root = PathBuf::from("/ns:default");
+
+        let files = pod_files(&root, pods.clone());
+        assert_eq!(
+            files
+                .iter()
+                .map(|f| f.path().to_path_buf())
+                .collect::<Vec<_>>(),
+            vec![root.join("pod-a"), root.join("pod-b"), root.join("pod-c")]
+        );
+        let files = container_files(&root.join("pod-c"), pods[0].clone()).unwrap();
+        assert_eq!(
+            files.iter().map(|f| f.name()).collect::<Vec<_>>(),
+            vec!["app", "init", "sidecar"]
+        );
+        assert!(files
+            .iter()
+            .all(|f| f.path().starts_with("/ns:default/pod-c")));
+    }
+
+    #[test]
+    fn should_aggregate_pod_results() {
+        let results: Vec<RemoteResult<(u32, String)>> = vec![
+            Ok((0, String::from("b\n"))),
+            Err(RemoteError::new(RemoteErrorType::ProtocolError)),
+            Ok((1, String::new())),
+        ];
+        let results = pod_results(
+            vec![
+                String::from("pod-b"),
+                String::from("pod-c"),
+                String::from("pod-a"),
+            ],
+            results,
+        );
+        let pods: Vec<&str> = results.iter().map(|(pod, _)| pod.as_str()).collect();
+        assert_eq!(pods, vec!["pod-a", "pod-b", "pod-c"]);
+        assert_eq!(results[0].1.as_ref().unwrap(), &(1, String::new()));
+        assert_eq!(results[1].1.as_ref().unwrap(), &(0, String::from("b\n")));
+        assert_eq!(
+            results[2].1.as_ref().err().unwrap().kind,
+            RemoteErrorType::ProtocolError
+        );
+    }
+
+    #[test]
+    fn should_date_pods_by_creation() {
+        let created = chrono::DateTime::parse_from_rfc3339("2024-11-05T13:46:00Z")
+            .unwrap()
+            .to_utc();
+        let pod = Pod {
+            metadata: ObjectMeta {
+                name: Some("test".to_string()),
+                creation_timestamp: Some(Time(created)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let metadata = pod_metadata(&pod);
+        assert!(metadata.is_dir());
+        assert_eq!(u32::from(metadata.mode.unwrap()), 0o755);
+        assert_eq!(
+            metadata.created.unwrap(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1730814360)
+        );
+        assert_eq!(metadata.modified, metadata.created);
+
+        // not created yet
+        let metadata = pod_metadata(&Pod::default());
+        assert!(metadata.is_dir());
+        assert!(metadata.created.is_none());
+    }
+
+    #[test]
+    fn should_get_namespace_names() {
+        let list: ObjectList<Namespace> = serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "NamespaceList",
+            "metadata": {},
+            "items": [
+                { "metadata": { "name": "kube-system" }, "status": { "phase": "Active" } },
+                { "metadata": { "name": "default" }, "status": { "phase": "Active" } },
+                { "metadata": { "name": "old" }, "status": { "phase": "Terminating" } },
+                { "metadata": { "name": "new" } },
+                { "metadata": {} }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            namespace_names(list.items),
+            vec!["default", "kube-system", "new"]
+        );
+        assert!(namespace_names(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn should_build_list_pods_review() {
+        let attributes = list_pods_review("my-namespace")
+            .spec
+            .resource_attributes
+            .unwrap();
+        assert_eq!(attributes.namespace.as_deref(), Some("my-namespace"));
+        assert_eq!(attributes.verb.as_deref(), Some("list"));
+        assert_eq!(attributes.resource.as_deref(), Some("pods"));
+        assert!(attributes.name.is_none());
+    }
+
+    #[test]
+    fn should_not_list_namespaces_when_disconnected() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let client = KubeMultiPodFs::new(&rt);
+        assert_eq!(
+            client.list_namespaces().unwrap_err().kind,
+            RemoteErrorType::NotConnected
+        );
+    }
+
+    #[test]
+    fn should_make_pod_list_params() {
+        let params = pod_list_params(None, None);
+        assert_eq!(params.label_selector, None);
+        assert_eq!(params.field_selector, None);
+        let params = pod_list_params(Some("app=nginx"), Some("nginx-0"));
+        assert_eq!(params.label_selector.as_deref(), Some("app=nginx"));
+        assert_eq!(
+            params.field_selector.as_deref(),
+            Some("metadata.name=nginx-0")
+        );
+    }
+
+    #[test]
+    fn should_reject_malformed_paths() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut client = KubeMultiPodFs::new(&rt);
+        for (path, component) in [
+            ("/..", r#"invalid pod name "..""#),
+            ("..", r#"invalid pod name "..""#),
+            ("/pod/../tmp", r#"invalid container name "..""#),
+            ("/ns:../pod", r#"invalid namespace "..""#),
+        ] {
+            let path = Path::new(path);
+            let errors = [
+                client.change_dir(path).map(|_| ()),
+                client.list_dir(path).map(|_| ()),
+                client.stat(path).map(|_| ()),
+                client.exists(path).map(|_| ()),
+            ];
+            for err in errors {
+                let err = err.unwrap_err();
+                assert_eq!(err.kind, RemoteErrorType::ProtocolError);
+                assert_eq!(
+                    err.msg.unwrap(),
+                    format!("malformed path {}: {component}", path.display())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_not_modify_virtual_directories() {
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+        );
+        let mut client = KubeMultiPodFs::new(&rt);
+        let file = Path::new("/pod/container/tmp/a.txt");
+        for dir in [
+            "/",
+            "/pod",
+            "/pod/container",
+            "/ns:dev",
+            "/ns:dev/pod/container",
+        ] {
+            let dir = Path::new(dir);
+            let errors = [
+                client.setstat(dir, Metadata::default()),
+                client.remove_file(dir),
+                client.remove_dir(dir),
+                client.remove_dir_all(dir),
+                client.create_dir(dir, UnixPex::from(0o755)),
+                client.symlink(dir, file),
+                client.copy(dir, file),
+                client.mov(dir, file),
+                client.mov(file, dir),
+                client
+                    .create_file(dir, &Metadata::default(), Box::new(std::io::empty()))
+                    .map(|_| ()),
+                client
+                    .append_file(dir, &Metadata::default(), Box::new(std::io::empty()))
+                    .map(|_| ()),
+            ];
+            for (i, err) in errors.into_iter().enumerate() {
+                let err = err.unwrap_err();
+                assert_eq!(
+                    err.kind,
+                    RemoteErrorType::UnsupportedFeature,
+                    "mutator {i} at {}",
+                    dir.display()
+                );
+                assert!(err
+                    .msg
+                    .unwrap()
+                    .starts_with("cannot modify virtual directory /"));
+            }
+        }
+        // the root can be read without a pod
+        assert!(client.stat(Path::new("/")).unwrap().is_dir());
+        assert!(client.exists(Path::new("/")).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    fn should_list_namespaces() {
+        let (api, client) = setup_client();
+        let namespaces = client.list_namespaces().unwrap();
+        assert!(namespaces.contains(&api.into_client().default_namespace().to_string()));
+    }
+
     #[test]
     #[cfg(feature = "integration-tests")]
     fn should_not_append_to_file() {
@@ -806,6 +1535,55 @@ mod test {
 
         assert!(client.stat(p).is_ok());
         assert!(client.stat(Path::new("b.txt")).is_ok());
+        // the destination is a path of the multipod tree, not of the container
+        let dest = client.pwd().unwrap().join("c.txt");
+        assert!(client.copy(p, &dest).is_ok());
+        assert!(client.stat(&dest).is_ok());
+
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    fn should_copy_file_between_pods() {
+        crate::log_init();
+        let (pods, mut client, pod_names) = setup_client_with_pods();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        // Create destination directory in the other pod
+        let dest_dir = PathBuf::from(format!("/{}/alpine", pod_names[1])).join(generate_tempdir());
+        assert!(client
+            .create_dir(dest_dir.as_path(), UnixPex::from(0o775))
+            .is_ok());
+
+        // copy into directory
+        assert!(client.copy_between(p, dest_dir.as_path()).is_ok());
+        assert_eq!(
+            client
+                .stat(&dest_dir.join("a.txt"))
+                .unwrap()
+                .metadata()
+                .size,
+            10
+        );
+        // copy with another name
+        assert!(client.copy_between(p, &dest_dir.join("b.txt")).is_ok());
+        assert_eq!(
+            client
+                .stat(&dest_dir.join("b.txt"))
+                .unwrap()
+                .metadata()
+                .size,
+            10
+        );
+        // no temporary directory is left
+        assert_eq!(client.list_dir(dest_dir.as_path()).unwrap().len(), 2);
+        // source is kept
+        assert!(client.stat(p).is_ok());
 
         finalize_client(pods, client);
     }
@@ -923,6 +1701,25 @@ mod test {
         finalize_client(pods, client);
     }
 
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    fn should_exec_command_in_all_containers() {
+        crate::log_init();
+        let (pods, mut client) = setup_client();
+        let pod = client.pod_name().unwrap().to_string();
+        assert_eq!(
+            client.exec_all(&pod, "echo $((2 + 3))").unwrap(),
+            vec![(String::from("alpine"), 0, String::from("5\n"))]
+        );
+        // exit codes are collected per container
+        assert_eq!(
+            client.exec_all(&pod, "exit 3").unwrap(),
+            vec![(String::from("alpine"), 3, String::new())]
+        );
+        assert!(client.exec_all("this-pod-does-not-exist", "true").is_err());
+        finalize_client(pods, client);
+    }
+
     #[test]
     #[cfg(feature = "integration-tests")]
     fn should_tell_whether_file_exists() {
@@ -944,6 +1741,28 @@ mod test {
         finalize_client(pods, client);
     }
 
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    fn should_not_find_pods_excluded_by_selector() {
+        crate::log_init();
+        let (pods, mut client, pod_names) = setup_client_with_pods();
+        let pod_dir = PathBuf::from("/").join(&pod_names[1]);
+        assert_eq!(client.exists(pod_dir.as_path()).unwrap(), true);
+        // no pod has the label
+        let mut client = client.pod_selector("remotefs-test=excluded");
+        assert_eq!(client.exists(pod_dir.as_path()).unwrap(), false);
+        assert_eq!(
+            client.stat(pod_dir.as_path()).unwrap_err().kind,
+            RemoteErrorType::NoSuchFileOrDirectory
+        );
+        assert!(client
+            .list_dir(Path::new("/"))
+            .unwrap()
+            .iter()
+            .all(|f| f.name() != pod_names[1]));
+        finalize_client(pods, client);
+    }
+
     #[test]
     #[cfg(feature = "integration-tests")]
     fn should_list_dir() {
@@ -980,6 +1799,68 @@ mod test {
         finalize_client(pods, client);
     }
 
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    fn should_list_dir_at_every_level() {
+        crate::log_init();
+        let (pods, mut client, pod_names) = setup_client_with_pods();
+        let wrkdir = client.pwd().unwrap();
+        // pods, listed through the api
+        let files = client.list_dir(Path::new("/")).unwrap();
+        assert!(pod_names
+            .iter()
+            .all(|pod| files.iter().any(|f| f.name() == *pod)));
+        // containers of another pod
+        let pod_dir = PathBuf::from("/").join(&pod_names[1]);
+        let files = client.list_dir(pod_dir.as_path()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path(), pod_dir.join("alpine").as_path());
+        // root of a container of another pod, listed through the container client
+        let container_dir = pod_dir.join("alpine");
+        let files = client.list_dir(container_dir.as_path()).unwrap();
+        assert!(files
+            .iter()
+            .any(|f| f.path() == container_dir.join("tmp").as_path()));
+        let files = client.list_dir(&container_dir.join("tmp")).unwrap();
+        assert!(files
+            .iter()
+            .all(|f| f.path().starts_with(container_dir.join("tmp"))));
+        // the working directory is kept
+        assert_eq!(client.pwd().unwrap(), wrkdir);
+        finalize_client(pods, client);
+    }
+
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    fn should_not_modify_virtual_directories_of_existing_pods() {
+        crate::log_init();
+        let (pods, mut client, pod_names) = setup_client_with_pods();
+        let pod_dir = PathBuf::from("/").join(&pod_names[1]);
+        let container_dir = pod_dir.join("alpine");
+        for dir in [Path::new("/"), pod_dir.as_path(), container_dir.as_path()] {
+            assert!(client.exists(dir).unwrap());
+            assert!(client.stat(dir).unwrap().is_dir());
+            assert!(client.list_dir(dir).is_ok());
+            assert_eq!(
+                client.remove_dir_all(dir).unwrap_err().kind,
+                RemoteErrorType::UnsupportedFeature
+            );
+            assert_eq!(
+                client
+                    .create_dir(dir, UnixPex::from(0o755))
+                    .unwrap_err()
+                    .kind,
+                RemoteErrorType::UnsupportedFeature
+            );
+        }
+        // the pods are still there
+        let files = client.list_dir(Path::new("/")).unwrap();
+        assert!(pod_names
+            .iter()
+            .all(|pod| files.iter().any(|f| f.name() == *pod)));
+        finalize_client(pods, client);
+    }
+
     #[test]
     #[cfg(feature = "integration-tests")]
     fn should_not_list_dir() {
@@ -1010,6 +1891,31 @@ mod test {
         finalize_client(pods, client);
     }
 
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    fn should_move_file_between_pods() {
+        crate::log_init();
+        let (pods, mut client, pod_names) = setup_client_with_pods();
+        // Create file
+        let p = Path::new("a.txt");
+        let file_data = "test data\n";
+        let reader = Cursor::new(file_data.as_bytes());
+        let metadata = Metadata::default().size(file_data.len() as u64);
+        assert!(client.create_file(p, &metadata, Box::new(reader)).is_ok());
+        // Create destination directory in the other pod
+        let dest_dir = PathBuf::from(format!("/{}/alpine", pod_names[1])).join(generate_tempdir());
+        assert!(client
+            .create_dir(dest_dir.as_path(), UnixPex::from(0o775))
+            .is_ok());
+
+        let dest = dest_dir.join("b.txt");
+        assert!(client.mov(p, dest.as_path()).is_ok());
+        assert_eq!(client.exists(p).ok().unwrap(), false);
+        assert_eq!(client.stat(dest.as_path()).unwrap().metadata().size, 10);
+
+        finalize_client(pods, client);
+    }
+
     #[test]
     #[cfg(feature = "integration-tests")]
     fn should_not_move_file() {
@@ -1349,6 +2255,25 @@ mod test {
         finalize_client(api, client);
     }
 
+    #[test]
+    #[cfg(feature = "integration-tests")]
+    fn test_should_list_containers_detailed() {
+        let (api, mut client) = setup_client();
+
+        let pods = client.list_dir(Path::new("/")).unwrap();
+        let pod_name = pods.get(0).unwrap().name();
+
+        let containers = client.list_containers_detailed(&pod_name).unwrap();
+        assert_eq!(containers.len(), 1);
+        let container = containers.get(0).unwrap();
+        assert_eq!(container.name, "alpine");
+        assert_eq!(container.image.as_deref(), Some("alpine"));
+        assert_eq!(container.state.as_deref(), Some("Running"));
+        assert_eq!(container.restart_count, 0);
+
+        finalize_client(api, client);
+    }
+
     #[test]
     #[cfg(feature = "integration-tests")]
     fn test_should_enter_pod() {
@@ -1406,12 +2331,18 @@ mod test {
 
     #[cfg(feature = "integration-tests")]
     fn setup_client() -> (Api<Pod>, KubeMultiPodFs) {
+        let (pods, client, _) = setup_client_with_pods();
+        (pods, client)
+    }
+
+    /// Setup the client, also returning the names of the pods
+    #[cfg(feature = "integration-tests")]
+    fn setup_client_with_pods() -> (Api<Pod>, KubeMultiPodFs, Vec<String>) {
         crate::log_init();
         // setup pod with random name
 
         use kube::api::PostParams;
-        use kube::config::AuthInfo;
-        use kube::ResourceExt as _;
+        use kube::{Client, ResourceExt as _};
 
         let runtime = Arc::new(
             tokio::runtime::Builder::new_current_thread()
@@ -1420,33 +2351,11 @@ mod test {
                 .unwrap(),
         );
 
-        let minikube_ip = std::env::var("MINIKUBE_IP").unwrap();
-
-        // setup pod
+        // setup pods on the cluster of the kubeconfig, as the library would
         debug!("setting up pod");
-        // config for minikube
-        let mut auth_info = AuthInfo::default();
-        auth_info.username = Some("minikube".to_string());
-        // get home
-        let home = std::env::var("HOME").unwrap();
-        auth_info.client_certificate =
-            Some(format!("{home}/.minikube/profiles/minikube/client.crt"));
-        auth_info.client_key = Some(format!("{home}/.minikube/profiles/minikube/client.key"));
-
-        debug!("Auth info: {auth_info:?}");
-
-        let config = Config {
-            cluster_url: format!("https://{minikube_ip}:8443").parse().unwrap(),
-            default_namespace: "default".to_string(),
-            read_timeout: None,
-            root_cert: None,
-            connect_timeout: None,
-            write_timeout: None,
-            accept_invalid_certs: true,
-            auth_info,
-            proxy_url: None,
-            tls_server_name: None,
-        };
+        let config = runtime
+            .block_on(KubeContainerFs::config_from_env())
+            .expect("failed to load kubeconfig");
 
         let pod_names = (0..2)
             .into_iter()
@@ -1519,7 +2428,7 @@ mod test {
         client
             .change_dir(tempdir.as_path())
             .expect("failed to enter tempdir");
-        (pods, client)
+        (pods, client, pod_names)
     }
 
     #[cfg(feature = "integration-tests")]
@@ -0,0 +1,140 @@
+use k8s_openapi::api::core::v1::{ContainerState, Pod};
+
+/// A container of a pod, along with its status
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContainerEntry {
+    /// Name of the container
+    pub name: String,
+    /// Image of the container
+    pub image: Option<String>,
+    /// Whether the container passed its readiness probe
+    pub ready: bool,
+    /// Number of times the container has been restarted
+    pub restart_count: u32,
+    /// State of the container (`Running`, `Waiting` or `Terminated`), if reported
+    pub state: Option<String>,
+    /// Reason of the state, if any (e.g. `CrashLoopBackOff`)
+    pub reason: Option<String>,
+}
+
+impl ContainerEntry {
+    /// Get the entries of the containers of `pod`, in the order of its spec
+    pub(crate) fn from_pod(pod: &Pod) -> Vec<Self> {
+        let statuses = pod
+            .status
+            .as_ref()
+            .and_then(|status| status.container_statuses.as_deref())
+            .unwrap_or_default();
+        let Some(spec) = pod.spec.as_ref() else {
+            return Vec::new();
+        };
+
+        spec.containers
+            .iter()
+            .map(|container| {
+                let status = statuses.iter().find(|s| s.name == container.name);
+                let (state, reason) = status
+                    .and_then(|s| s.state.as_ref())
+                    .map(state)
+                    .unwrap_or_default();
+                Self {
+                    name: container.name.clone(),
+                    image: container
+                        .image
+                        .clone()
+                        .or_else(|| status.map(|s| s.image.clone())),
+                    ready: status.map(|s| s.ready).unwrap_or_default(),
+                    restart_count: status
+                        .map(|s| s.restart_count.max(0) as u32)
+                        .unwrap_or_default(),
+                    state,
+                    reason,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Get the name and the reason of a container state
+fn state(state: &ContainerState) -> (Option<String>, Option<String>) {
+    if state.running.is_some() {
+        (Some("Running".to_string()), None)
+    } else if let Some(waiting) = state.waiting.as_ref() {
+        (Some("Waiting".to_string()), waiting.reason.clone())
+    } else if let Some(terminated) = state.terminated.as_ref() {
+        (Some("Terminated".to_string()), terminated.reason.clone())
+    } else {
+        (None, None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_get_container_entries() {
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": { "name": "test" },
+            "spec": {
+                "containers": [
+                    { "name": "alpine", "image": "alpine:3.20" },
+                    { "name": "sidecar", "image": "busybox:1.36" },
+                    { "name": "pending" }
+                ]
+            },
+            "status": {
+                "containerStatuses": [
+                    {
+                        "name": "sidecar",
+                        "image": "docker.io/library/busybox:1.36",
+                        "imageID": "",
+                        "ready": false,
+                        "restartCount": 4,
+                        "state": { "waiting": { "reason": "CrashLoopBackOff" } }
+                    },
+                    {
+                        "name": "alpine",
+                        "image": "docker.io/library/alpine:3.20",
+                        "imageID": "",
+                        "ready": true,
+                        "restartCount": 0,
+                        "state": { "running": { "startedAt": "2024-11-05T13:46:00Z" } }
+                    }
+                ]
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            ContainerEntry::from_pod(&pod),
+            vec![
+                ContainerEntry {
+                    name: "alpine".to_string(),
+                    image: Some("alpine:3.20".to_string()),
+                    ready: true,
+                    restart_count: 0,
+                    state: Some("Running".to_string()),
+                    reason: None,
+                },
+                ContainerEntry {
+                    name: "sidecar".to_string(),
+                    image: Some("busybox:1.36".to_string()),
+                    ready: false,
+                    restart_count: 4,
+                    state: Some("Waiting".to_string()),
+                    reason: Some("CrashLoopBackOff".to_string()),
+                },
+                ContainerEntry {
+                    name: "pending".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
@@ -1,8 +1,32 @@
 use std::fmt;
 use std::path::{Path, PathBuf};
 
+use thiserror::Error;
+
+/// Prefix of the path segment selecting the namespace, as in `/ns:staging/pod/container/path`
+pub const NAMESPACE_PREFIX: &str = "ns:";
+
+/// Error of a malformed path, identifying the invalid component
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum KubePathError {
+    /// A container was given without a pod
+    #[error("container {0:?} without a pod")]
+    ContainerWithoutPod(String),
+    /// The namespace segment isn't a namespace name
+    #[error("invalid namespace {0:?}")]
+    InvalidNamespace(String),
+    /// The pod segment isn't a pod name
+    #[error("invalid pod name {0:?}")]
+    InvalidPod(String),
+    /// The container segment isn't a container name
+    #[error("invalid container name {0:?}")]
+    InvalidContainer(String),
+}
+
 #[derive(Default, Clone)]
 pub struct KubePath {
+    /// Namespace set with a leading `ns:<name>` segment; the default namespace is used if unset
+    pub namespace: Option<String>,
     pub pod: Option<String>,
     pub container: Option<String>,
     pub path: Option<PathBuf>,
@@ -11,6 +35,11 @@ pub struct KubePath {
 impl fmt::Display for KubePath {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut path = String::from("/");
+        if let Some(namespace) = &self.namespace {
+            path.push_str(NAMESPACE_PREFIX);
+            path.push_str(namespace);
+            path.push('/');
+        }
         if let Some(pod) = &self.pod {
             path.push_str(pod);
             path.push('/');
@@ -28,7 +57,13 @@ impl fmt::Display for KubePath {
 
 impl KubePath {
     /// Get Kube Path from a path, using the current pod and container.
-    pub fn from_path(pod: Option<&str>, container: Option<&str>, path: &Path) -> Self {
+    ///
+    /// Fails if the path selects a namespace, pod or container which can't be one, such as `..`.
+    pub fn from_path(
+        pod: Option<&str>,
+        container: Option<&str>,
+        path: &Path,
+    ) -> Result<Self, KubePathError> {
         if path.is_absolute() {
             Self::from_absolute_path(path)
         } else {
@@ -38,18 +73,19 @@ impl KubePath {
 
     /// Get Kube Path from an absolute resource path.
     ///
-    /// The syntax is `/pod/container/path/to/file`
-    fn from_absolute_path(path: &Path) -> Self {
+    /// The syntax is `/pod/container/path/to/file`, optionally preceded by a `ns:<namespace>` segment
+    fn from_absolute_path(path: &Path) -> Result<Self, KubePathError> {
         let mut p = KubePath::default();
 
-        let mut parts = path.iter();
+        let mut parts = path.iter().peekable();
         parts.next(); // skip the root
 
+        p.namespace = parse_namespace(&mut parts)?;
         if let Some(pod) = parts.next() {
-            p.pod = Some(pod.to_string_lossy().trim_matches('/').to_string());
+            p.pod = Some(parse_name(pod, KubePathError::InvalidPod)?);
         }
         if let Some(container) = parts.next() {
-            p.container = Some(container.to_string_lossy().trim_matches('/').to_string());
+            p.container = Some(parse_name(container, KubePathError::InvalidContainer)?);
         }
 
         // path must be absolute in this case
@@ -61,28 +97,35 @@ impl KubePath {
         if path != Path::new("/") {
             p.path = Some(path);
         }
-        p
+        Ok(p)
     }
 
     /// Get Kube Path from a relative path, using the current pod and container.
-    fn from_relative_path(pod: Option<&str>, container: Option<&str>, path: &Path) -> Self {
+    fn from_relative_path(
+        pod: Option<&str>,
+        container: Option<&str>,
+        path: &Path,
+    ) -> Result<Self, KubePathError> {
         let mut p = KubePath::default();
 
-        if pod.is_none() && container.is_some() {
-            panic!("Cannot specify a container without a pod");
+        if let (None, Some(container)) = (pod, container) {
+            return Err(KubePathError::ContainerWithoutPod(container.to_string()));
         }
 
-        let mut parts = path.iter();
+        let mut parts = path.iter().peekable();
         if let Some(pod) = pod {
             p.pod = Some(pod.to_string());
-        } else if let Some(pod) = parts.next() {
-            p.pod = Some(pod.to_string_lossy().trim_matches('/').to_string());
+        } else {
+            p.namespace = parse_namespace(&mut parts)?;
+            if let Some(pod) = parts.next() {
+                p.pod = Some(parse_name(pod, KubePathError::InvalidPod)?);
+            }
         }
 
         if let Some(container) = container {
             p.container = Some(container.to_string());
         } else if let Some(container) = parts.next() {
-            p.container = Some(container.to_string_lossy().trim_matches('/').to_string());
+            p.container = Some(parse_name(container, KubePathError::InvalidContainer)?);
         }
 
         // if pod and container are not specified, the path must be trated as absolute
@@ -101,8 +144,46 @@ impl KubePath {
             p.path = Some(path);
         }
 
-        p
+        Ok(p)
+    }
+}
+
+/// Consume the leading `ns:<namespace>` segment of `parts`, if any, returning the namespace
+fn parse_namespace<'a>(
+    parts: &mut std::iter::Peekable<impl Iterator<Item = &'a std::ffi::OsStr>>,
+) -> Result<Option<String>, KubePathError> {
+    let Some(namespace) = parts
+        .peek()
+        .and_then(|part| part.to_str())
+        .and_then(|part| part.strip_prefix(NAMESPACE_PREFIX))
+        .filter(|namespace| !namespace.is_empty())
+    else {
+        return Ok(None);
+    };
+    if !is_name(namespace) {
+        return Err(KubePathError::InvalidNamespace(namespace.to_string()));
     }
+    let namespace = namespace.to_string();
+    parts.next();
+    Ok(Some(namespace))
+}
+
+/// Get the name of a pod or a container from the path segment `part`, failing with `err` if it can't be one
+fn parse_name(
+    part: &std::ffi::OsStr,
+    err: impl FnOnce(String) -> KubePathError,
+) -> Result<String, KubePathError> {
+    let name = part.to_string_lossy().trim_matches('/').to_string();
+    if is_name(&name) {
+        Ok(name)
+    } else {
+        Err(err(name))
+    }
+}
+
+/// Whether `name` may be the name of a namespace, a pod or a container: the relative segments `.` and `..` may not
+fn is_name(name: &str) -> bool {
+    !matches!(name, "" | "." | "..")
 }
 
 #[cfg(test)]
@@ -115,27 +196,27 @@ mod test {
     #[test]
     fn test_from_absolute_path() {
         let path = Path::new("/pod/container/path/to/file");
-        let p = KubePath::from_path(None, None, path);
+        let p = KubePath::from_path(None, None, path).unwrap();
         assert_eq!(p.pod, Some("pod".to_string()));
         assert_eq!(p.container, Some("container".to_string()));
         assert_eq!(p.path, Some(PathBuf::from("/path/to/file")));
 
         let path = Path::new("/pod/container");
 
-        let p = KubePath::from_path(None, None, path);
+        let p = KubePath::from_path(None, None, path).unwrap();
         assert_eq!(p.pod, Some("pod".to_string()));
         assert_eq!(p.container, Some("container".to_string()));
 
         let path = Path::new("/pod");
 
-        let p = KubePath::from_path(None, None, path);
+        let p = KubePath::from_path(None, None, path).unwrap();
         assert_eq!(p.pod, Some("pod".to_string()));
         assert!(p.container.is_none());
         assert!(p.path.is_none());
 
         let path = Path::new("/");
 
-        let p = KubePath::from_path(None, None, path);
+        let p = KubePath::from_path(None, None, path).unwrap();
         assert!(p.pod.is_none());
         assert!(p.container.is_none());
         assert!(p.path.is_none());
@@ -144,28 +225,149 @@ mod test {
     #[test]
     fn test_relative_path() {
         let path = Path::new("path/to/file");
-        let p = KubePath::from_path(Some("pod"), Some("container"), path);
+        let p = KubePath::from_path(Some("pod"), Some("container"), path).unwrap();
         assert_eq!(p.pod, Some("pod".to_string()));
         assert_eq!(p.container, Some("container".to_string()));
         assert_eq!(p.path, Some(PathBuf::from("path/to/file")));
 
         let path = Path::new("container/path/to/file");
-        let p = KubePath::from_path(Some("pod"), None, path);
+        let p = KubePath::from_path(Some("pod"), None, path).unwrap();
         assert_eq!(p.pod, Some("pod".to_string()));
         assert_eq!(p.container, Some("container".to_string()));
         assert_eq!(p.path, Some(PathBuf::from("/path/to/file")));
 
         let path = Path::new("pod/container/path/to/file");
-        let p = KubePath::from_path(None, None, path);
+        let p = KubePath::from_path(None, None, path).unwrap();
         assert_eq!(p.pod, Some("pod".to_string()));
         assert_eq!(p.container, Some("container".to_string()));
         assert_eq!(p.path, Some(PathBuf::from("/path/to/file")));
     }
 
     #[test]
-    #[should_panic]
-    fn test_relative_path_panic() {
+    fn test_path_with_trailing_slash() {
+        for (pod, container, path) in [
+            (None, None, "/pod/container/dir"),
+            (None, None, "pod/container/dir"),
+            (Some("pod"), None, "container/dir"),
+            (Some("pod"), Some("container"), "dir"),
+            (None, None, "/ns:staging/pod/container/dir"),
+        ] {
+            let with_slash = format!("{path}/");
+            let p = KubePath::from_path(pod, container, Path::new(path)).unwrap();
+            let p_slash = KubePath::from_path(pod, container, Path::new(&with_slash)).unwrap();
+            // compare the strings, since paths equal regardless of trailing slashes
+            assert_eq!(p.to_string(), p_slash.to_string(), "{path}");
+        }
+        for path in ["/pod", "/pod/container", "/ns:staging"] {
+            let with_slash = format!("{path}/");
+            let p = KubePath::from_path(None, None, Path::new(path)).unwrap();
+            let p_slash = KubePath::from_path(None, None, Path::new(&with_slash)).unwrap();
+            assert_eq!(p.to_string(), p_slash.to_string(), "{path}");
+            assert!(p_slash.path.is_none());
+        }
+    }
+
+    #[test]
+    fn test_relative_path_without_pod() {
         let path = Path::new("path/to/file");
-        KubePath::from_path(None, Some("container"), path);
+        assert_eq!(
+            KubePath::from_path(None, Some("container"), path).err(),
+            Some(KubePathError::ContainerWithoutPod("container".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_malformed_path() {
+        for (pod, container, path, err) in [
+            (
+                None,
+                None,
+                "/..",
+                KubePathError::InvalidPod("..".to_string()),
+            ),
+            (
+                None,
+                None,
+                "..",
+                KubePathError::InvalidPod("..".to_string()),
+            ),
+            (
+                None,
+                None,
+                "./pod",
+                KubePathError::InvalidPod(".".to_string()),
+            ),
+            (
+                None,
+                None,
+                "/pod/../file",
+                KubePathError::InvalidContainer("..".to_string()),
+            ),
+            (
+                Some("pod"),
+                None,
+                "../file",
+                KubePathError::InvalidContainer("..".to_string()),
+            ),
+            (
+                None,
+                None,
+                "/ns:../pod",
+                KubePathError::InvalidNamespace("..".to_string()),
+            ),
+        ] {
+            assert_eq!(
+                KubePath::from_path(pod, container, Path::new(path)).err(),
+                Some(err),
+                "{path}"
+            );
+        }
+        // relative segments are fine in the container
+        let p = KubePath::from_path(Some("pod"), Some("container"), Path::new("../file")).unwrap();
+        assert_eq!(p.path, Some(PathBuf::from("../file")));
+    }
+
+    #[test]
+    fn test_namespaced_path() {
+        let p =
+            KubePath::from_path(None, None, Path::new("ns:staging/pod/container/file")).unwrap();
+        assert_eq!(p.namespace, Some("staging".to_string()));
+        assert_eq!(p.pod, Some("pod".to_string()));
+        assert_eq!(p.container, Some("container".to_string()));
+        assert_eq!(p.path, Some(PathBuf::from("/file")));
+
+        let p = KubePath::from_path(
+            Some("other"),
+            Some("alpine"),
+            Path::new("/ns:staging/pod/container/path/to/file"),
+        )
+        .unwrap();
+        assert_eq!(p.namespace, Some("staging".to_string()));
+        assert_eq!(p.pod, Some("pod".to_string()));
+        assert_eq!(p.container, Some("container".to_string()));
+        assert_eq!(p.path, Some(PathBuf::from("/path/to/file")));
+
+        let p = KubePath::from_path(None, None, Path::new("/ns:staging")).unwrap();
+        assert_eq!(p.namespace, Some("staging".to_string()));
+        assert!(p.pod.is_none());
+        assert!(p.container.is_none());
+        assert!(p.path.is_none());
+    }
+
+    #[test]
+    fn test_path_without_namespace() {
+        let p = KubePath::from_path(None, None, Path::new("/pod/container/file")).unwrap();
+        assert!(p.namespace.is_none());
+        assert_eq!(p.pod, Some("pod".to_string()));
+
+        // only a leading segment selects the namespace
+        let p = KubePath::from_path(None, None, Path::new("/pod/ns:staging/file")).unwrap();
+        assert!(p.namespace.is_none());
+        assert_eq!(p.container, Some("ns:staging".to_string()));
+
+        // an empty name isn't a namespace
+        let p = KubePath::from_path(None, None, Path::new("/ns:/container")).unwrap();
+        assert!(p.namespace.is_none());
+        assert_eq!(p.pod, Some("ns:".to_string()));
     }
 }
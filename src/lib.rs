@@ -44,6 +44,9 @@
 //!
 //! So paths have the following structure: `/pod-name/container-name/path/to/file`.
 //!
+//! Pods of the default namespace are at the root; the pods of another namespace can be reached with a leading `ns:` segment,
+//! as in `/ns:staging/pod-name/container-name/path/to/file`.
+//!
 //! ```rust,ignore
 //!
 //! // import remotefs trait and client
@@ -113,13 +116,18 @@ extern crate lazy_regex;
 #[macro_use]
 extern crate log;
 
+mod error;
 mod kube_container_fs;
 mod kube_multipod_fs;
 mod utils;
 
+pub use error::KubeFsError;
 pub use kube::Config;
-pub use kube_container_fs::KubeContainerFs;
-pub use kube_multipod_fs::KubeMultiPodFs;
+pub use kube_container_fs::{
+    KubeContainerFs, ListOptions, MetricsSink, PodInfo, SecurityLabels, SharedKubeClient, SortBy,
+    SymlinkFollow, SyncOptions, SyncReport,
+};
+pub use kube_multipod_fs::{ContainerEntry, KubeMultiPodFs, PodExecResult};
 
 // -- test logging
 #[cfg(test)]
@@ -2,6 +2,7 @@
 //!
 //! parser utils
 
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
 use chrono::format::ParseError;
@@ -37,6 +38,296 @@ pub fn parse_lstime(tm: &str, fmt_year: &str, fmt_hours: &str) -> Result<SystemT
         .unwrap_or(SystemTime::UNIX_EPOCH))
 }
 
+/// Parse the `total N` header line of `ls -l` output, returning the block count
+pub fn parse_ls_total(line: &str) -> Option<u64> {
+    line.trim()
+        .strip_prefix("total")
+        .and_then(|total| total.trim().parse::<u64>().ok())
+}
+
+/// Unquote a name printed by `ls` with the `shell` or `shell-escape` quoting styles,
+/// such as `'my file.txt'`, `"it's.txt"` or `'new'$'\n''line'`.
+///
+/// Names which don't start with a quote, or which aren't valid quoted words, are returned as they are.
+pub fn unquote_ls_name(name: &str) -> String {
+    if !(name.starts_with('\'') || name.starts_with('"') || name.starts_with("$'")) {
+        return name.to_string();
+    }
+    unquote_shell_word(name).unwrap_or_else(|| name.to_string())
+}
+
+fn unquote_shell_word(word: &str) -> Option<String> {
+    let mut unquoted: Vec<u8> = Vec::with_capacity(word.len());
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => loop {
+                match chars.next()? {
+                    '\'' => break,
+                    c => push_char(&mut unquoted, c),
+                }
+            },
+            '"' => loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        c @ ('"' | '\\' | '$' | '`') => push_char(&mut unquoted, c),
+                        c => {
+                            push_char(&mut unquoted, '\\');
+                            push_char(&mut unquoted, c);
+                        }
+                    },
+                    c => push_char(&mut unquoted, c),
+                }
+            },
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        '\\' => unquoted.push(ansi_c_escape(&mut chars)?),
+                        c => push_char(&mut unquoted, c),
+                    }
+                }
+            }
+            '\\' => push_char(&mut unquoted, chars.next()?),
+            c => push_char(&mut unquoted, c),
+        }
+    }
+
+    String::from_utf8(unquoted).ok()
+}
+
+fn push_char(bytes: &mut Vec<u8>, c: char) {
+    let mut buf = [0; 4];
+    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+/// Decode the escape sequence following a `\` in a `$'...'` word
+fn ansi_c_escape(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<u8> {
+    let byte = match chars.next()? {
+        'a' => 0x07,
+        'b' => 0x08,
+        'e' | 'E' => 0x1b,
+        'f' => 0x0c,
+        'n' => b'\n',
+        'r' => b'\r',
+        't' => b'\t',
+        'v' => 0x0b,
+        c @ ('\\' | '\'' | '"' | '?') => c as u8,
+        c @ '0'..='7' => {
+            let mut value = c.to_digit(8)?;
+            for _ in 0..2 {
+                match chars.peek().and_then(|c| c.to_digit(8)) {
+                    Some(digit) => {
+                        value = value * 8 + digit;
+                        chars.next();
+                    }
+                    None => break,
+                }
+            }
+            u8::try_from(value).ok()?
+        }
+        'x' => {
+            let mut value = 0;
+            for _ in 0..2 {
+                match chars.peek().and_then(|c| c.to_digit(16)) {
+                    Some(digit) => {
+                        value = value * 16 + digit;
+                        chars.next();
+                    }
+                    None => break,
+                }
+            }
+            value as u8
+        }
+        _ => return None,
+    };
+    Some(byte)
+}
+
+/// Parse the output of `du -sk`, returning the size in bytes.
+///
+/// Both GNU and busybox print `<kilobytes><tab><path>`; the last line is the total.
+pub fn parse_du_output(output: &str) -> Option<u64> {
+    output
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| line.split_whitespace().next())
+        .and_then(|kilobytes| kilobytes.parse::<u64>().ok())
+        .map(|kilobytes| kilobytes * 1024)
+}
+
+/// Parse the output of `env`, returning the variables in order.
+///
+/// If the output is NUL-separated (`env -0`), it is split on NUL, so values may contain anything.
+/// Otherwise, it is split on lines, and lines which don't start with `NAME=` are considered the
+/// continuation of the previous value, which contained a newline.
+pub fn parse_env(output: &str) -> Vec<(String, String)> {
+    if output.contains('\0') {
+        return output
+            .split('\0')
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+    }
+
+    let mut vars: Vec<(String, String)> = Vec::new();
+    for line in output.lines() {
+        match line.split_once('=') {
+            Some((name, value)) if is_env_name(name) => {
+                vars.push((name.to_string(), value.to_string()))
+            }
+            _ => {
+                if let Some((_, value)) = vars.last_mut() {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+            }
+        }
+    }
+
+    vars
+}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A filesystem mounted in the container, as listed in `/proc/mounts`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountEntry {
+    pub mount_point: PathBuf,
+    pub read_only: bool,
+}
+
+/// Parse the content of `/proc/mounts`, skipping malformed lines.
+///
+/// Each line is `device mount_point fs_type options dump pass`, with spaces and other
+/// special characters in the mount point escaped as octal (e.g. `\040`).
+pub fn parse_proc_mounts(mounts: &str) -> Vec<MountEntry> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?;
+            let options = fields.nth(1)?;
+            Some(MountEntry {
+                mount_point: PathBuf::from(unescape_octal(mount_point)),
+                read_only: options.split(',').any(|opt| opt == "ro"),
+            })
+        })
+        .collect()
+}
+
+/// Find the mount `path` belongs to, which is the one with the longest mount point containing it.
+///
+/// When the same mount point is mounted more than once, the last mount wins, since it hides the others.
+pub fn find_mount<'a>(mounts: &'a [MountEntry], path: &Path) -> Option<&'a MountEntry> {
+    mounts
+        .iter()
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.components().count())
+}
+
+/// Parse the output of `ls -Zd`, returning the security context of the file.
+///
+/// Recent coreutils print `<context> <path>`, while older ones print the long format with the context
+/// after the owner and the group. `?` means the file has no context, e.g. because SELinux is disabled.
+pub fn parse_ls_context(output: &str) -> Option<String> {
+    let line = output.lines().find(|line| !line.trim().is_empty())?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let context = match fields.first() {
+        Some(mode) if is_ls_mode(mode) => fields.get(3)?,
+        first => first?,
+    };
+    (*context != "?").then(|| context.to_string())
+}
+
+fn is_ls_mode(field: &str) -> bool {
+    field.len() >= 10
+        && field
+            .chars()
+            .skip(1)
+            .take(9)
+            .all(|c| matches!(c, 'r' | 'w' | 'x' | 's' | 'S' | 't' | 'T' | '-'))
+}
+
+/// Parse the output of `getfattr -d -m -`, returning the attributes as name and value, in order.
+///
+/// Values are quoted, with special characters escaped as octal (e.g. `\012`); binary values
+/// (`0x` or `0s` encoded) are kept as printed.
+pub fn parse_getfattr(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .map(|line| match line.split_once('=') {
+            Some((name, value)) => {
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .map(unescape_octal)
+                    .unwrap_or_else(|| value.to_string());
+                (name.to_string(), value)
+            }
+            None => (line.to_string(), String::new()),
+        })
+        .collect()
+}
+
+/// Parse the identities of two files, one per line, returning whether they're the same file.
+///
+/// Lines start either with `<device>:<inode>`, as printed by `stat -c '%d:%i'`, or with the inode alone,
+/// as printed by `ls -i` (e.g. on busybox without `stat`), which can't tell apart files on different devices.
+pub fn parse_same_file(output: &str) -> Option<bool> {
+    let ids: Vec<&str> = output
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    let is_id = |id: &str| {
+        let numbers: Vec<&str> = id.split(':').collect();
+        numbers.len() <= 2
+            && numbers
+                .iter()
+                .all(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    };
+    match ids.as_slice() {
+        [a, b] if is_id(a) && is_id(b) => Some(a == b),
+        _ => None,
+    }
+}
+
+fn unescape_octal(s: &str) -> String {
+    let mut unescaped: Vec<u8> = Vec::with_capacity(s.len());
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match escaped {
+            Some(byte) => {
+                unescaped.push(byte);
+                i += 4;
+            }
+            None => {
+                unescaped.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
 #[cfg(test)]
 mod test {
 
@@ -91,4 +382,207 @@ mod test {
         assert!(parse_lstime("Feb 31 2018", "%b %d %Y", "%b %d %H:%M").is_err());
         assert!(parse_lstime("Feb 15 25:32", "%b %d %Y", "%b %d %H:%M").is_err());
     }
+
+    #[test]
+    fn should_parse_ls_total() {
+        assert_eq!(parse_ls_total("total 12"), Some(12));
+        assert_eq!(parse_ls_total("total 0"), Some(0));
+        assert_eq!(parse_ls_total("  total   4096  "), Some(4096));
+        // bad cases
+        assert_eq!(parse_ls_total("total"), None);
+        assert_eq!(parse_ls_total("total 12K"), None);
+        assert_eq!(
+            parse_ls_total("-rw-r--r-- 1 root root  2056 Nov  5 16:32 total"),
+            None
+        );
+        assert_eq!(parse_ls_total(""), None);
+    }
+
+    #[test]
+    fn should_unquote_ls_name() {
+        assert_eq!(unquote_ls_name("plain.txt").as_str(), "plain.txt");
+        assert_eq!(unquote_ls_name("'my file.txt'").as_str(), "my file.txt");
+        assert_eq!(unquote_ls_name("\"it's.txt\"").as_str(), "it's.txt");
+        assert_eq!(unquote_ls_name("'it'\\''s a file'").as_str(), "it's a file");
+        assert_eq!(
+            unquote_ls_name("\"say \\\"hi\\\" it's\"").as_str(),
+            "say \"hi\" it's"
+        );
+        assert_eq!(unquote_ls_name("'new'$'\\n''line'").as_str(), "new\nline");
+        assert_eq!(unquote_ls_name("'caf'$'\\303\\251'").as_str(), "café");
+        // not valid quoted words
+        assert_eq!(unquote_ls_name("'unterminated").as_str(), "'unterminated");
+        assert_eq!(unquote_ls_name("$'\\q'").as_str(), "$'\\q'");
+    }
+
+    #[test]
+    fn should_parse_proc_mounts() {
+        let mounts = parse_proc_mounts(
+            "overlay / overlay rw,relatime,lowerdir=/var/lib/docker/overlay2/l/A:/var/lib/docker/overlay2/l/B 0 0
+proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+tmpfs /etc/config tmpfs ro,relatime,size=16384k 0 0
+/dev/sda1 /data/my\\040volume ext4 rw,relatime 0 0
+tmpfs /var/run/secrets/kubernetes.io/serviceaccount tmpfs ro,relatime 0 0
+bad line
+",
+        );
+        assert_eq!(
+            mounts,
+            vec![
+                MountEntry {
+                    mount_point: PathBuf::from("/"),
+                    read_only: false
+                },
+                MountEntry {
+                    mount_point: PathBuf::from("/proc"),
+                    read_only: false
+                },
+                MountEntry {
+                    mount_point: PathBuf::from("/etc/config"),
+                    read_only: true
+                },
+                MountEntry {
+                    mount_point: PathBuf::from("/data/my volume"),
+                    read_only: false
+                },
+                MountEntry {
+                    mount_point: PathBuf::from("/var/run/secrets/kubernetes.io/serviceaccount"),
+                    read_only: true
+                },
+            ]
+        );
+
+        let mount_point = |path: &str| {
+            find_mount(&mounts, Path::new(path))
+                .map(|mount| mount.mount_point.to_string_lossy().into_owned())
+        };
+        assert_eq!(
+            mount_point("/etc/config/app.yaml").as_deref(),
+            Some("/etc/config")
+        );
+        assert_eq!(mount_point("/etc/configs").as_deref(), Some("/"));
+        assert_eq!(
+            mount_point("/data/my volume/a.txt").as_deref(),
+            Some("/data/my volume")
+        );
+        assert_eq!(mount_point("/tmp").as_deref(), Some("/"));
+        assert_eq!(find_mount(&[], Path::new("/tmp")), None);
+    }
+
+    #[test]
+    fn should_parse_env() {
+        let expected = vec![
+            (String::from("PATH"), String::from("/usr/bin:/bin")),
+            (String::from("DSN"), String::from("user=me;password=a=b")),
+            (String::from("EMPTY"), String::new()),
+            (
+                String::from("CERT"),
+                String::from("-----BEGIN CERTIFICATE-----\nMIIBx+/A==\n-----END CERTIFICATE-----"),
+            ),
+            (String::from("_private1"), String::from("1")),
+        ];
+        // lines
+        assert_eq!(
+            parse_env(
+                "PATH=/usr/bin:/bin\nDSN=user=me;password=a=b\nEMPTY=\nCERT=-----BEGIN CERTIFICATE-----\nMIIBx+/A==\n-----END CERTIFICATE-----\n_private1=1\n"
+            ),
+            expected
+        );
+        // NUL-separated
+        assert_eq!(
+            parse_env(
+                "PATH=/usr/bin:/bin\0DSN=user=me;password=a=b\0EMPTY=\0CERT=-----BEGIN CERTIFICATE-----\nMIIBx+/A==\n-----END CERTIFICATE-----\0_private1=1\0"
+            ),
+            expected
+        );
+        // NUL-separated values may contain anything
+        assert_eq!(
+            parse_env("A=1\nB=2\0"),
+            vec![(String::from("A"), String::from("1\nB=2"))]
+        );
+        assert!(parse_env("").is_empty());
+    }
+
+    #[test]
+    fn should_parse_du_output() {
+        // GNU coreutils
+        assert_eq!(parse_du_output("4\t/tmp/a\n"), Some(4096));
+        assert_eq!(parse_du_output("1234\t/tmp/my dir\n"), Some(1263616));
+        // busybox
+        assert_eq!(parse_du_output("12\t/tmp/a"), Some(12288));
+        assert_eq!(parse_du_output("0\t/tmp/empty\n\n"), Some(0));
+        // bad cases
+        assert_eq!(parse_du_output(""), None);
+        assert_eq!(parse_du_output("4.0K\t/tmp/a\n"), None);
+    }
+
+    #[test]
+    fn should_parse_ls_context() {
+        assert_eq!(
+            parse_ls_context("system_u:object_r:container_file_t:s0:c123,c456 /data/a.txt\n")
+                .as_deref(),
+            Some("system_u:object_r:container_file_t:s0:c123,c456")
+        );
+        // old coreutils long format
+        assert_eq!(
+            parse_ls_context(
+                "-rw-r--r--. root root unconfined_u:object_r:user_home_t:s0 /root/a.txt\n"
+            )
+            .as_deref(),
+            Some("unconfined_u:object_r:user_home_t:s0")
+        );
+        // no SELinux
+        assert_eq!(parse_ls_context("? /data/a.txt\n"), None);
+        assert_eq!(parse_ls_context(""), None);
+    }
+
+    #[test]
+    fn should_parse_getfattr() {
+        let output = "# file: /data/a.txt\nsecurity.selinux=\"system_u:object_r:container_file_t:s0\"\nuser.note=\"line\\012two\"\nuser.bin=0sAAEC\nuser.empty\n\n";
+        assert_eq!(
+            parse_getfattr(output),
+            vec![
+                (
+                    "security.selinux".to_string(),
+                    "system_u:object_r:container_file_t:s0".to_string()
+                ),
+                ("user.note".to_string(), "line\ntwo".to_string()),
+                ("user.bin".to_string(), "0sAAEC".to_string()),
+                ("user.empty".to_string(), String::new()),
+            ]
+        );
+        assert!(parse_getfattr("").is_empty());
+    }
+
+    #[test]
+    fn should_parse_same_file() {
+        // stat -c '%d:%i'
+        assert_eq!(
+            parse_same_file("64768:1835021\n64768:1835021\n"),
+            Some(true)
+        );
+        assert_eq!(
+            parse_same_file("64768:1835021\n64768:1835022\n"),
+            Some(false)
+        );
+        // same inode on another device
+        assert_eq!(parse_same_file("64768:1835021\n66:1835021\n"), Some(false));
+        // ls -i
+        assert_eq!(
+            parse_same_file(" 1835021 /tmp/a.txt\n 1835021 /tmp/b.txt\n"),
+            Some(true)
+        );
+        assert_eq!(
+            parse_same_file("1835021 /tmp/a.txt\n1835022 /tmp/b.txt\n"),
+            Some(false)
+        );
+        // bad cases
+        assert_eq!(parse_same_file(""), None);
+        assert_eq!(parse_same_file("64768:1835021\n"), None);
+        assert_eq!(
+            parse_same_file("ls: /tmp/a.txt: No such file or directory\n1 /tmp/b.txt\n"),
+            None
+        );
+        assert_eq!(parse_same_file("1:2:3\n1:2:3\n"), None);
+    }
 }
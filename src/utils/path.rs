@@ -8,14 +8,53 @@ use std::path::{Path, PathBuf};
 use path_slash::PathExt as _;
 
 /// Absolutize target path if relative.
+///
+/// Trailing slashes are removed, so that `dir/` and `dir` are the same path for the commands run on it.
 pub fn absolutize(wrkdir: &Path, target: &Path) -> PathBuf {
-    match target.is_absolute() {
+    let path = match target.is_absolute() {
         true => target.to_path_buf(),
         false => {
             let mut p: PathBuf = wrkdir.to_path_buf();
             p.push(target);
             resolve(&p)
         }
+    };
+    trim_trailing_slashes(&path)
+}
+
+/// Remove the trailing slashes of `path`, except for the root
+fn trim_trailing_slashes(path: &Path) -> PathBuf {
+    let path_str = path.to_string_lossy();
+    match path_str.trim_end_matches('/') {
+        "" if path_str.starts_with('/') => PathBuf::from("/"),
+        trimmed if trimmed.len() == path_str.len() => path.to_path_buf(),
+        trimmed => PathBuf::from(trimmed),
+    }
+}
+
+/// Expand a leading `~` in `target` to `home`, if set.
+///
+/// Only `~` as the whole first component is expanded; `~user` and `~` elsewhere in the path are kept as they are.
+pub fn expand_home(home: Option<&Path>, target: &Path) -> PathBuf {
+    let (Some(home), Ok(rest)) = (home, target.strip_prefix("~")) else {
+        return target.to_path_buf();
+    };
+    match rest.as_os_str().is_empty() {
+        true => home.to_path_buf(),
+        false => home.join(rest),
+    }
+}
+
+/// Quote `path` for the shell in single quotes, so that the shell never expands anything in it
+pub fn quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', r"'\''"))
+}
+
+/// Get the working directory printed by `pwd`, removing the trailing newline and slashes, except for the root
+pub fn parse_pwd(output: &str) -> PathBuf {
+    match output.trim().trim_end_matches('/') {
+        "" => PathBuf::from("/"),
+        path => PathBuf::from(path),
     }
 }
 
@@ -47,4 +86,79 @@ mod test {
             Path::new("/tmp/readme.txt")
         );
     }
+
+    #[test]
+    fn absolutize_path_with_trailing_slash() {
+        // compare the strings, since paths equal regardless of trailing slashes
+        for (target, expected) in [
+            ("docs/", "/home/omar/docs"),
+            ("docs//", "/home/omar/docs"),
+            ("docs", "/home/omar/docs"),
+            ("/tmp/", "/tmp"),
+            ("/tmp", "/tmp"),
+            ("/", "/"),
+            ("//", "/"),
+        ] {
+            assert_eq!(
+                absolutize(Path::new("/home/omar"), Path::new(target)).as_os_str(),
+                expected,
+                "{target}"
+            );
+        }
+        assert_eq!(
+            absolutize(Path::new("/"), Path::new("docs/")).as_os_str(),
+            "/docs"
+        );
+    }
+
+    #[test]
+    fn should_quote_path() {
+        assert_eq!(quote(Path::new("/tmp/my dir")), "'/tmp/my dir'");
+        assert_eq!(
+            quote(Path::new("/tmp/it's $(id); `id`")),
+            r"'/tmp/it'\''s $(id); `id`'"
+        );
+    }
+
+    #[test]
+    fn should_expand_home() {
+        let home = Some(Path::new("/home/omar"));
+        assert_eq!(
+            expand_home(home, Path::new("~")).as_path(),
+            Path::new("/home/omar")
+        );
+        assert_eq!(
+            expand_home(home, Path::new("~/")).as_path(),
+            Path::new("/home/omar")
+        );
+        assert_eq!(
+            expand_home(home, Path::new("~/logs/a.log")).as_path(),
+            Path::new("/home/omar/logs/a.log")
+        );
+        assert_eq!(
+            expand_home(home, Path::new("logs/~/a.log")).as_path(),
+            Path::new("logs/~/a.log")
+        );
+        assert_eq!(
+            expand_home(home, Path::new("/tmp/~")).as_path(),
+            Path::new("/tmp/~")
+        );
+        assert_eq!(
+            expand_home(home, Path::new("~omar/a.log")).as_path(),
+            Path::new("~omar/a.log")
+        );
+        assert_eq!(
+            expand_home(None, Path::new("~/a.log")).as_path(),
+            Path::new("~/a.log")
+        );
+    }
+
+    #[test]
+    fn should_parse_pwd() {
+        assert_eq!(parse_pwd("/tmp\n").as_path(), Path::new("/tmp"));
+        assert_eq!(parse_pwd("/tmp/\n").as_path(), Path::new("/tmp"));
+        assert_eq!(parse_pwd("/tmp/a dir//").as_path(), Path::new("/tmp/a dir"));
+        assert_eq!(parse_pwd("/\n").as_path(), Path::new("/"));
+        assert_eq!(parse_pwd("//").as_path(), Path::new("/"));
+    }
 }